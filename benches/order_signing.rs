@@ -0,0 +1,57 @@
+// cargo bench --bench order_signing
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use polymarket::clob_client::{
+    client::ClobClient,
+    clob_types::{ApiCreds, CreateOrderOptions, OrderArgs},
+};
+use rust_decimal_macros::dec;
+
+// Throwaway key, nothing is sent anywhere.
+const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+const TOKEN_ID: &str =
+    "104468181147316868388088006861839293041095272602974154655578369735976654024471";
+
+fn order_signing(c: &mut Criterion) {
+    let creds = ApiCreds {
+        api_key: String::new(),
+        api_secret: String::new(),
+        api_pass: String::new(),
+    };
+    let client = ClobClient::new(KEY, creds, Some(2), None);
+    let options = CreateOrderOptions {
+        tick_size: "0.01",
+        neg_risk: true,
+        min_size: None,
+    };
+    let order_args = OrderArgs::new(TOKEN_ID, dec!(0.5), dec!(10), "BUY", None, None, None, None);
+    // The template is built on the first order, outside the measurement.
+    client.order_template(TOKEN_ID, true).unwrap();
+
+    let mut group = c.benchmark_group("order_signing");
+    group.bench_function("OrderBuilder::create_order", |b| {
+        b.iter(|| {
+            client
+                .builder
+                .create_order(black_box(&order_args), &options)
+                .unwrap()
+        })
+    });
+    group.bench_function("ClobClient::create_order_fast", |b| {
+        b.iter(|| {
+            client
+                .create_order_fast(
+                    TOKEN_ID,
+                    "BUY",
+                    black_box(dec!(0.5)),
+                    black_box(dec!(10)),
+                    &options,
+                    0,
+                )
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, order_signing);
+criterion_main!(benches);
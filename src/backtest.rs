@@ -0,0 +1,388 @@
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+use crate::config::EVENT_QUEUE_CAPACITY;
+use crate::exchange_listeners::event_processor::{CountingSender, SocketEvent};
+use crate::exchange_listeners::poly_models::Listener;
+use crate::marketmaking::poly_market_struct::Market;
+
+/// A line written by `BBOLoggingStrategy`: sizes by price of both sides of a book.
+#[derive(Debug, Clone, Deserialize)]
+struct RecordedBook {
+    bids: BTreeMap<String, f64>,
+    asks: BTreeMap<String, f64>,
+    timestamp: String,
+}
+
+struct Frame {
+    timestamp: i64,
+    asset_id: String,
+    book: RecordedBook,
+}
+
+/// Books recorded by `BBOLoggingStrategy`, oldest first.
+pub struct Recording {
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    /// Loads the books recorded under `dir` as `<asset_id>.ndjson`. Other files, such as
+    /// `trades.ndjson`, are skipped.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let frames = load_recorded_books(dir)?;
+        info!(
+            "[Backtest] Loaded {} books from {}",
+            frames.len(),
+            dir.display()
+        );
+        Ok(Self { frames })
+    }
+
+    /// Stand-in market metadata for every recorded asset, as no markets are fetched in a
+    /// backtest. The tick size is the coarsest one all recorded prices fit.
+    pub fn markets(&self) -> HashMap<String, Arc<Market>> {
+        let mut fine_ticks: HashMap<&str, bool> = HashMap::new();
+        for frame in &self.frames {
+            let fine = frame
+                .book
+                .bids
+                .keys()
+                .chain(frame.book.asks.keys())
+                .any(|price| !price.ends_with('0'));
+            *fine_ticks.entry(frame.asset_id.as_str()).or_default() |= fine;
+        }
+        fine_ticks
+            .into_iter()
+            .filter_map(|(asset_id, fine)| {
+                let market = json!({
+                    "clobTokenIds": serde_json::to_string(&[asset_id]).ok()?,
+                    "orderPriceMinTickSize": if fine { 0.001 } else { 0.01 },
+                });
+                let market: Market = serde_json::from_value(market).ok()?;
+                Some((asset_id.to_string(), Arc::new(market)))
+            })
+            .collect()
+    }
+}
+
+fn load_recorded_books(dir: &Path) -> Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        let asset_id = match (path.file_stem(), path.extension()) {
+            (Some(stem), Some(ext)) if ext == "ndjson" => stem.to_string_lossy().into_owned(),
+            _ => continue,
+        };
+        if asset_id.is_empty() || !asset_id.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RecordedBook>(line) {
+                Ok(book) => frames.push(Frame {
+                    timestamp: book.timestamp.parse().unwrap_or(0),
+                    asset_id: asset_id.clone(),
+                    book,
+                }),
+                Err(e) => warn!("[Backtest] Skipping {}:{}: {}", path.display(), i + 1, e),
+            }
+        }
+    }
+    frames.sort_by_key(|frame| frame.timestamp);
+    Ok(frames)
+}
+
+/// Levels of one side that differ from `previous` to `next`, with their new size;
+/// levels that disappeared get size 0.
+fn level_changes(
+    previous: &BTreeMap<String, f64>,
+    next: &BTreeMap<String, f64>,
+) -> Vec<(String, f64)> {
+    let mut changes: Vec<(String, f64)> = next
+        .iter()
+        .filter(|(price, size)| previous.get(*price) != Some(*size))
+        .map(|(price, size)| (price.clone(), *size))
+        .collect();
+    changes.extend(
+        previous
+            .keys()
+            .filter(|price| !next.contains_key(*price))
+            .map(|price| (price.clone(), 0.0)),
+    );
+    changes
+}
+
+fn best_price(levels: &BTreeMap<String, f64>, highest: bool) -> Option<&String> {
+    let prices = levels
+        .keys()
+        .filter_map(|price| price.parse::<f64>().ok().map(|value| (value, price)));
+    let best = if highest {
+        prices.max_by(|a, b| a.0.total_cmp(&b.0))
+    } else {
+        prices.min_by(|a, b| a.0.total_cmp(&b.0))
+    };
+    best.map(|(_, price)| price)
+}
+
+fn book_message(asset_id: &str, book: &RecordedBook) -> Value {
+    let levels = |side: &BTreeMap<String, f64>| -> Vec<Value> {
+        side.iter()
+            .map(|(price, size)| json!({ "price": price, "size": size.to_string() }))
+            .collect()
+    };
+    json!({
+        "event_type": "book",
+        "asset_id": asset_id,
+        "timestamp": book.timestamp,
+        "bids": levels(&book.bids),
+        "asks": levels(&book.asks),
+    })
+}
+
+/// Legacy `price_change` message taking `previous` to `next`, or `None` if the book
+/// did not change.
+fn price_change_message(
+    asset_id: &str,
+    previous: &RecordedBook,
+    next: &RecordedBook,
+) -> Option<Value> {
+    let best_bid = best_price(&next.bids, true);
+    let best_ask = best_price(&next.asks, false);
+    let changes: Vec<Value> = level_changes(&previous.bids, &next.bids)
+        .into_iter()
+        .map(|change| (change, "BUY"))
+        .chain(
+            level_changes(&previous.asks, &next.asks)
+                .into_iter()
+                .map(|change| (change, "SELL")),
+        )
+        .map(|((price, size), side)| {
+            json!({
+                "asset_id": asset_id,
+                "price": price,
+                "size": size.to_string(),
+                "side": side,
+                "best_bid": best_bid,
+                "best_ask": best_ask,
+            })
+        })
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+    Some(json!({
+        "event_type": "price_change",
+        "asset_id": asset_id,
+        "timestamp": next.timestamp,
+        "price_changes": changes,
+    }))
+}
+
+/// Replays `recording` through the event processor in timestamp order: the first book
+/// of every asset as a snapshot, later ones as the price changes between them. Returns
+/// once every event is handled, with the number of events sent.
+pub async fn replay_recorded_books(recording: Recording, events: &CountingSender) -> Result<usize> {
+    let mut last: HashMap<String, RecordedBook> = HashMap::new();
+    let mut sent = 0;
+    for frame in recording.frames {
+        let message = match last.get(&frame.asset_id) {
+            Some(previous) => price_change_message(&frame.asset_id, previous, &frame.book),
+            None => Some(book_message(&frame.asset_id, &frame.book)),
+        };
+        if let Some(message) = message {
+            // The queues are sized for live feeds, so wait rather than flood them.
+            while events.pending() >= EVENT_QUEUE_CAPACITY / 2 {
+                sleep(Duration::from_millis(1)).await;
+            }
+            events
+                .send(SocketEvent::Market {
+                    listener: Listener::PolyMarketLegacy,
+                    payload: serde_json::to_vec(&message)?,
+                })
+                .map_err(|_| anyhow!("event processor stopped"))?;
+            sent += 1;
+        }
+        last.insert(frame.asset_id, frame.book);
+    }
+    while events.pending() > 0 {
+        sleep(Duration::from_millis(1)).await;
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::event_processor::spawn_event_processor;
+    use crate::exchange_listeners::paper_exchange;
+    use crate::exchange_listeners::poly_client::PolyClient;
+    use crate::exchange_listeners::poly_models::{AggOrderbook, OrderSide};
+    use crate::exchange_listeners::{AppState, PolyMarketState};
+    use crate::strategies::{
+        PaperFillStrategy, Strategy, StrategyContext, UpdateOrderbookStrategy,
+    };
+    use std::sync::atomic::AtomicI64;
+
+    fn book(bids: &[(&str, f64)], asks: &[(&str, f64)]) -> RecordedBook {
+        book_at(bids, asks, 1_700_000_000_000)
+    }
+
+    fn book_at(bids: &[(&str, f64)], asks: &[(&str, f64)], timestamp: i64) -> RecordedBook {
+        let side = |levels: &[(&str, f64)]| {
+            levels
+                .iter()
+                .map(|(price, size)| (price.to_string(), *size))
+                .collect()
+        };
+        RecordedBook {
+            bids: side(bids),
+            asks: side(asks),
+            timestamp: timestamp.to_string(),
+        }
+    }
+
+    /// Bids 10 at 0.50 on the first snapshot of a book.
+    struct Bidder;
+
+    impl Strategy for Bidder {
+        fn name(&self) -> &'static str {
+            "Bidder"
+        }
+
+        fn poly_handle_market_agg_orderbook(
+            &self,
+            ctx: Arc<StrategyContext>,
+            _listener: Listener,
+            snapshot: &AggOrderbook,
+        ) {
+            PolyClient::place_limit_order(
+                Arc::clone(&ctx.poly_state),
+                Some(self.name()),
+                &snapshot.asset_id,
+                OrderSide::Buy,
+                500,
+                10_000,
+                "0.01",
+                false,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn diffs_consecutive_books_into_price_changes() {
+        let previous = book(&[("0.480", 10.0), ("0.490", 5.0)], &[("0.510", 7.0)]);
+        let next = book(&[("0.480", 12.0)], &[("0.510", 7.0), ("0.500", 3.0)]);
+
+        let message = price_change_message("1", &previous, &next).unwrap();
+        let changes: Vec<(String, String, String)> = message["price_changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| {
+                (
+                    change["side"].as_str().unwrap().to_string(),
+                    change["price"].as_str().unwrap().to_string(),
+                    change["size"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        let expected = [
+            ("BUY", "0.480", "12"),
+            ("BUY", "0.490", "0"),
+            ("SELL", "0.500", "3"),
+        ];
+        assert_eq!(
+            changes,
+            expected
+                .map(|(side, price, size)| (side.into(), price.into(), size.into()))
+                .to_vec()
+        );
+        assert_eq!(message["price_changes"][0]["best_bid"], "0.480");
+        assert_eq!(message["price_changes"][0]["best_ask"], "0.500");
+
+        assert!(price_change_message("1", &next, &next).is_none());
+    }
+
+    #[tokio::test]
+    async fn paper_orders_fill_against_the_replayed_books_on_their_clock() {
+        let start = 1_700_000_000_000;
+        let frames = [
+            book_at(&[("0.48", 10.0)], &[("0.52", 20.0)], start),
+            book_at(
+                &[("0.48", 10.0)],
+                &[("0.50", 4.0), ("0.52", 20.0)],
+                start + 1_000,
+            ),
+            book_at(&[("0.48", 10.0)], &[("0.49", 50.0)], start + 2_000),
+        ];
+        let recording = Recording {
+            frames: frames
+                .into_iter()
+                .map(|book| Frame {
+                    timestamp: book.timestamp.parse().unwrap(),
+                    asset_id: "1".to_string(),
+                    book,
+                })
+                .collect(),
+        };
+        let poly_state = Arc::new(PolyMarketState {
+            markets: Arc::new(recording.markets().into_iter().collect()),
+            paper_trading: true,
+            replay_clock: Some(Arc::new(AtomicI64::new(0))),
+            ..Default::default()
+        });
+        let (resync_tx, _resync_rx) = tokio::sync::mpsc::unbounded_channel();
+        let events = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            vec![
+                Arc::new(UpdateOrderbookStrategy::new(resync_tx)) as Arc<dyn Strategy>,
+                Arc::new(PaperFillStrategy::new()),
+                Arc::new(Bidder),
+            ],
+            1,
+        );
+
+        assert_eq!(replay_recorded_books(recording, &events).await.unwrap(), 3);
+        for _ in 0..100 {
+            if poly_state.paper_ledger.fills().len() == 2 {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        let fills: Vec<(i64, u32, u32)> = poly_state
+            .paper_ledger
+            .fills()
+            .iter()
+            .map(|fill| (fill.timestamp_ms, fill.price, fill.size))
+            .collect();
+        // Partly filled by the 4 offered at 0.50, then the rest once the ask drops below.
+        assert_eq!(
+            fills,
+            vec![(start + 1_000, 500, 4_000), (start + 2_000, 500, 6_000)]
+        );
+        assert_eq!(poly_state.now().timestamp_millis(), start + 2_000);
+        assert_eq!(poly_state.open_order_count(), 0);
+        assert_eq!(
+            poly_state.positions.get("1").unwrap().read().unwrap().size,
+            10_000
+        );
+        // Paid 5 for 10 shares now marked at (0.48 + 0.49) / 2.
+        let summary = paper_exchange::summary(&poly_state);
+        assert!((summary.pnl - (4.85 - 5.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{anyhow, bail, Result};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: polymarket [run|paper|backtest] [options]
+
+Commands:
+  run                    Trade live (default)
+  paper                  Live feeds; orders are logged instead of sent
+  backtest               Replay the books recorded under --data; orders are logged
+
+Options:
+  --config <file>        JSON launch config: {\"strategies\": [{\"name\": ..., \"params\": {...}}]}
+  --strategies <a,b,..>  Strategies to run, overriding the list of the config
+  --data <dir>           Recorded books to backtest on [default: output]
+  --list-strategies      Print the registered strategies and their parameters
+  -h, --help             Print this help";
+
+/// How orders are handled and where events come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Run,
+    Paper,
+    Backtest,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    pub mode: Mode,
+    pub config: Option<PathBuf>,
+    pub strategies: Option<Vec<String>>,
+    pub data: PathBuf,
+    pub list_strategies: bool,
+    pub help: bool,
+}
+
+/// Parses the command line, without the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Cli> {
+    let mut cli = Cli {
+        mode: Mode::Run,
+        config: None,
+        strategies: None,
+        data: PathBuf::from("output"),
+        list_strategies: false,
+        help: false,
+    };
+    let mut args = args.into_iter().peekable();
+    if let Some(mode) = args.peek().and_then(|command| parse_mode(command)) {
+        cli.mode = mode;
+        args.next();
+    }
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+        match arg.as_str() {
+            "--config" => cli.config = Some(value()?.into()),
+            "--strategies" => {
+                cli.strategies = Some(
+                    value()?
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect(),
+                )
+            }
+            "--data" => cli.data = value()?.into(),
+            "--list-strategies" => cli.list_strategies = true,
+            "-h" | "--help" => cli.help = true,
+            _ => bail!("unexpected argument '{}'", arg),
+        }
+    }
+    Ok(cli)
+}
+
+fn parse_mode(command: &str) -> Option<Mode> {
+    match command {
+        "run" => Some(Mode::Run),
+        "paper" => Some(Mode::Paper),
+        "backtest" => Some(Mode::Backtest),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Cli> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_mode_and_options() {
+        let cli = parse("").unwrap();
+        assert_eq!((cli.mode, cli.data), (Mode::Run, PathBuf::from("output")));
+
+        let cli = parse("backtest --data books --strategies KoenStrategy,,FillMetrics").unwrap();
+        assert_eq!(cli.mode, Mode::Backtest);
+        assert_eq!(cli.data, PathBuf::from("books"));
+        assert_eq!(
+            cli.strategies,
+            Some(vec!["KoenStrategy".to_string(), "FillMetrics".to_string()])
+        );
+
+        assert!(parse("paper --config").is_err());
+        assert!(parse("--config a.json paper").is_err());
+    }
+}
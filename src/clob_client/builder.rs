@@ -4,22 +4,27 @@ use ethers::{
     utils::to_checksum,
 };
 use lazy_static::lazy_static;
+use rust_decimal::Decimal;
 use std::collections::HashMap;
-use tiny_keccak::{Hasher, Keccak};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
 
-use super::clob_auth::make_domain;
 use super::constants::{EXCHANGE, NEG_RISK_EXCHANGE, POLYGON};
+use super::eip712::{self, make_domain, EIP712Domain, Eip712Struct};
 use super::utils::{generate_seed, prepend_zx};
 use super::{
-    clob_types::{CreateOrderOptions, OrderArgs},
+    clob_types::{CreateOrderOptions, OrderArgs, SignedOrderPayload},
     signer::PolySigner,
 };
 
 const BUY: &str = "BUY";
 const SELL: &str = "SELL";
 
-const UTILS_BUY: usize = 0;
-const UTILS_SELL: usize = 1;
+const TOKEN_DECIMALS: f64 = 1e6;
+
+const UTILS_BUY: u8 = 0;
+const UTILS_SELL: u8 = 1;
 
 lazy_static! {
     pub static ref ROUND_CONFIG: HashMap<String, RoundConfig> = {
@@ -61,24 +66,8 @@ lazy_static! {
 }
 
 lazy_static! {
-    pub static ref DOMAIN_SEPARATOR_HASH: [u8; 32] = {
-        let domain_separator = make_domain(
-            Some("Polymarket CTF Exchange"),
-            Some("1"),
-            Some(U256::from(POLYGON)),
-            Some(*EXCHANGE),
-        );
-        domain_separator.struct_hash()
-    };
-    pub static ref DOMAIN_SEPARATOR_HASH_NEG_RISK: [u8; 32] = {
-        let domain_separator = make_domain(
-            Some("Polymarket CTF Exchange"),
-            Some("1"),
-            Some(U256::from(POLYGON)),
-            Some(*NEG_RISK_EXCHANGE),
-        );
-        domain_separator.struct_hash()
-    };
+    pub static ref DOMAIN_SEPARATOR_HASH: [u8; 32] = exchange_domain(false).struct_hash();
+    pub static ref DOMAIN_SEPARATOR_HASH_NEG_RISK: [u8; 32] = exchange_domain(true).struct_hash();
     pub static ref MESSAGE_PREFIX: [u8; 34] = {
         let mut prefix = [0u8; 34];
         prefix[0] = 0x19;
@@ -96,7 +85,22 @@ lazy_static! {
 }
 
 lazy_static! {
-    pub static ref TYPE_HASH: [u8; 32] = compute_type_hash();
+    pub static ref TYPE_HASH: [u8; 32] = Order::type_hash();
+}
+
+/// EIP-712 domain of the CTF exchange (or the neg-risk exchange) orders are signed against.
+pub fn exchange_domain(neg_risk: bool) -> EIP712Domain<'static> {
+    let exchange = if neg_risk {
+        *NEG_RISK_EXCHANGE
+    } else {
+        *EXCHANGE
+    };
+    make_domain(
+        Some("Polymarket CTF Exchange"),
+        Some("1"),
+        Some(U256::from(POLYGON)),
+        Some(exchange),
+    )
 }
 
 #[derive(Debug)]
@@ -106,47 +110,143 @@ pub struct OrderBuilder {
     pub funder: Address,
 }
 
-pub fn get_order_amounts(
-    side: &str,
-    size: f64,
-    price: f64,
-    round_config: &RoundConfig,
-) -> Result<(usize, i64, i64), String> {
-    let raw_price = round_normal(price, round_config.price);
-    if side == BUY {
-        let raw_taker_amt = round_down(size, round_config.size);
-        let mut raw_maker_amt = raw_taker_amt * raw_price;
-
-        if decimal_places(raw_maker_amt) > round_config.amount {
-            raw_maker_amt = round_up(raw_maker_amt, round_config.amount + 4);
-            if decimal_places(raw_maker_amt) > round_config.amount {
-                raw_maker_amt = round_down(raw_maker_amt, round_config.amount)
-            }
-        }
-        // TODO: this is important data to push to the db
-        let maker_amount = to_token_decimals(raw_maker_amt);
-        let taker_amount = to_token_decimals(raw_taker_amt);
-        Ok((UTILS_BUY, maker_amount, taker_amount))
-    } else if side == SELL {
-        let raw_maker_amt = round_down(size, round_config.size);
-        let mut raw_taker_amt = raw_maker_amt * raw_price;
+/// Maker/taker amounts of an order, in 6-decimal token units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderAmounts {
+    pub side: u8,
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+}
 
-        if decimal_places(raw_taker_amt) > round_config.amount {
-            raw_taker_amt = round_up(raw_taker_amt, round_config.amount + 4);
-            if decimal_places(raw_taker_amt) > round_config.amount {
-                raw_taker_amt = round_down(raw_taker_amt, round_config.amount);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderAmountError {
+    InvalidSide(String),
+    UnknownTickSize(String),
+    PriceOffTick {
+        price: Decimal,
+        tick_size: Decimal,
+    },
+    PriceOutOfRange {
+        price: Decimal,
+        tick_size: Decimal,
+    },
+    SizeBelowMinimum {
+        size: Decimal,
+        min_size: Decimal,
+    },
+    ZeroAmount {
+        maker_amount: u64,
+        taker_amount: u64,
+    },
+}
+
+impl fmt::Display for OrderAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderAmountError::InvalidSide(side) => {
+                write!(
+                    f,
+                    "order side must be '{}' or '{}', got '{}'",
+                    BUY, SELL, side
+                )
+            }
+            OrderAmountError::UnknownTickSize(tick) => write!(f, "unknown tick size '{}'", tick),
+            OrderAmountError::PriceOffTick { price, tick_size } => {
+                write!(
+                    f,
+                    "price {} is not a multiple of tick size {}",
+                    price, tick_size
+                )
             }
+            OrderAmountError::PriceOutOfRange { price, tick_size } => write!(
+                f,
+                "price {} outside of [{}, {}]",
+                price,
+                tick_size,
+                Decimal::ONE - tick_size
+            ),
+            OrderAmountError::SizeBelowMinimum { size, min_size } => {
+                write!(f, "size {} below market minimum {}", size, min_size)
+            }
+            OrderAmountError::ZeroAmount {
+                maker_amount,
+                taker_amount,
+            } => write!(
+                f,
+                "computed zero amount (maker={}, taker={})",
+                maker_amount, taker_amount
+            ),
         }
+    }
+}
+
+impl Error for OrderAmountError {}
+
+/// Computes maker/taker amounts as py-clob-client's `get_order_amounts` does for the
+/// given tick size. Prices and sizes are checked as exact decimals; the amounts go
+/// through the Python client's f64 rounding steps, so they match its output exactly,
+/// including where f64 takes a cent off a size (19.99 is floored to 19.98).
+///
+/// Unlike the Python client, prices that are not a multiple of the tick size are
+/// rejected with `PriceOffTick` instead of being rounded to the nearest tick.
+pub fn get_order_amounts(
+    side: &str,
+    size: Decimal,
+    price: Decimal,
+    tick_size: &str,
+    min_size: Option<Decimal>,
+) -> Result<OrderAmounts, OrderAmountError> {
+    let round_config = ROUND_CONFIG
+        .get(tick_size)
+        .ok_or_else(|| OrderAmountError::UnknownTickSize(tick_size.to_string()))?;
+    let tick = Decimal::from_str(tick_size)
+        .map_err(|_| OrderAmountError::UnknownTickSize(tick_size.to_string()))?;
+
+    if price < tick || price > Decimal::ONE - tick {
+        return Err(OrderAmountError::PriceOutOfRange {
+            price,
+            tick_size: tick,
+        });
+    }
+    if !(price % tick).is_zero() {
+        return Err(OrderAmountError::PriceOffTick {
+            price,
+            tick_size: tick,
+        });
+    }
+    let raw_price = round_normal(to_f64(price), round_config.price);
 
-        let maker_amount = to_token_decimals(raw_maker_amt);
-        let taker_amount = to_token_decimals(raw_taker_amt);
+    let raw_size = round_down(to_f64(size), round_config.size);
+    if let Some(min_size) = min_size {
+        let size = Decimal::from_str(&raw_size.to_string()).unwrap_or_default();
+        if size < min_size {
+            return Err(OrderAmountError::SizeBelowMinimum { size, min_size });
+        }
+    }
+    let raw_other = round_amount(raw_size * raw_price, round_config.amount);
 
-        Ok((UTILS_SELL, maker_amount, taker_amount))
+    let (side, raw_maker_amt, raw_taker_amt) = if side == BUY {
+        (UTILS_BUY, raw_other, raw_size)
+    } else if side == SELL {
+        (UTILS_SELL, raw_size, raw_other)
     } else {
-        Err(format!("order_args.side must be '{}' or '{}'", BUY, SELL))
+        return Err(OrderAmountError::InvalidSide(side.to_string()));
+    };
+
+    let maker_amount = to_token_decimals(raw_maker_amt);
+    let taker_amount = to_token_decimals(raw_taker_amt);
+    if maker_amount == 0 || taker_amount == 0 {
+        return Err(OrderAmountError::ZeroAmount {
+            maker_amount,
+            taker_amount,
+        });
     }
 
-    // Ok(("".to_string(), 0.2, 0.3))
+    Ok(OrderAmounts {
+        side,
+        maker_amount,
+        taker_amount,
+    })
 }
 
 impl OrderBuilder {
@@ -158,50 +258,6 @@ impl OrderBuilder {
         }
     }
 
-    pub fn get_order_amounts(
-        &self,
-        side: &str,
-        size: f64,
-        price: f64,
-        round_config: &RoundConfig,
-    ) -> Result<(usize, i64, i64), String> {
-        let raw_price = round_normal(price, round_config.price);
-        if side == BUY {
-            let raw_taker_amt = round_down(size, round_config.size);
-            let mut raw_maker_amt = raw_taker_amt * raw_price;
-
-            if decimal_places(raw_maker_amt) > round_config.amount {
-                raw_maker_amt = round_up(raw_maker_amt, round_config.amount + 4);
-                if decimal_places(raw_maker_amt) > round_config.amount {
-                    raw_maker_amt = round_down(raw_maker_amt, round_config.amount)
-                }
-            }
-            // TODO: this is important data to push to the db
-            let maker_amount = to_token_decimals(raw_maker_amt);
-            let taker_amount = to_token_decimals(raw_taker_amt);
-            Ok((UTILS_BUY, maker_amount, taker_amount))
-        } else if side == SELL {
-            let raw_maker_amt = round_down(size, round_config.size);
-            let mut raw_taker_amt = raw_maker_amt * raw_price;
-
-            if decimal_places(raw_taker_amt) > round_config.amount {
-                raw_taker_amt = round_up(raw_taker_amt, round_config.amount + 4);
-                if decimal_places(raw_taker_amt) > round_config.amount {
-                    raw_taker_amt = round_down(raw_taker_amt, round_config.amount);
-                }
-            }
-
-            let maker_amount = to_token_decimals(raw_maker_amt);
-            let taker_amount = to_token_decimals(raw_taker_amt);
-
-            Ok((UTILS_SELL, maker_amount, taker_amount))
-        } else {
-            Err(format!("order_args.side must be '{}' or '{}'", BUY, SELL))
-        }
-
-        // Ok(("".to_string(), 0.2, 0.3))
-    }
-
     // pub fn create_order2(&self) -> SignedOrder {
     //     return None;
     // }
@@ -210,15 +266,18 @@ impl OrderBuilder {
         &self,
         order_args: &OrderArgs,
         options: &CreateOrderOptions,
-    ) -> SignedOrder {
-        let (side, maker_amount, taker_amount) = self
-            .get_order_amounts(
-                &order_args.side,
-                order_args.size,
-                order_args.price,
-                &ROUND_CONFIG.get(options.tick_size).unwrap(),
-            )
-            .unwrap();
+    ) -> Result<SignedOrder, Box<dyn Error + Send + Sync>> {
+        let OrderAmounts {
+            side,
+            maker_amount,
+            taker_amount,
+        } = get_order_amounts(
+            order_args.side,
+            order_args.size,
+            order_args.price,
+            options.tick_size,
+            options.min_size,
+        )?;
 
         let mut data = OrderData {
             maker: Some(self.funder.clone()),
@@ -226,7 +285,7 @@ impl OrderBuilder {
             token_id: Some(&order_args.token_id),
             maker_amount: Some(maker_amount as usize),
             taker_amount: Some(taker_amount as usize),
-            side: Some(side as u8),
+            side: Some(side),
             fee_rate_bps: Some(order_args.fee_rate_bps as usize),
             nonce: order_args.nonce as usize,
             signer: Some(self.signer.address()),
@@ -256,8 +315,12 @@ impl OrderBuilder {
         self.sign_prepared_order(order, options.neg_risk)
     }
 
-    pub fn sign_prepared_order(&self, order: Order, neg_risk: bool) -> SignedOrder {
-        let order_struct_hash = compute_order_struct_hash(&order);
+    pub fn sign_prepared_order(
+        &self,
+        order: Order,
+        neg_risk: bool,
+    ) -> Result<SignedOrder, Box<dyn Error + Send + Sync>> {
+        let order_struct_hash = order.struct_hash();
         let mut message = Vec::with_capacity(2 + 32 + 32);
         if neg_risk {
             message.extend_from_slice(&MESSAGE_PREFIX_NEG_RISK[..]);
@@ -271,9 +334,9 @@ impl OrderBuilder {
 
         let digest_h256 = H256::from_slice(&digest);
 
-        let signature = prepend_zx(self.signer.sign(&digest_h256));
+        let signature = prepend_zx(self.signer.sign(&digest_h256)?);
 
-        SignedOrder { order, signature }
+        Ok(SignedOrder { order, signature })
     }
 }
 
@@ -332,70 +395,6 @@ pub struct Order {
     pub signature_type: U256,
 }
 
-const ORDER_TYPE: &str = "Order(uint256 salt,address maker,address signer,address taker,uint256 tokenId,uint256 makerAmount,uint256 takerAmount,uint256 expiration,uint256 nonce,uint256 feeRateBps,uint8 side,uint8 signatureType)";
-
-pub fn encode_uint256(value: &U256) -> [u8; 32] {
-    let mut buf = [0u8; 32];
-    value.to_big_endian(&mut buf);
-    buf
-}
-
-pub fn encode_uint8(value: u8) -> [u8; 32] {
-    let mut buf = [0u8; 32];
-    buf[31] = value;
-    buf
-}
-
-pub fn encode_address(addr: &Address) -> [u8; 32] {
-    let mut buf = [0u8; 32];
-    buf[12..].copy_from_slice(addr.as_bytes());
-    buf
-}
-
-pub fn compute_type_hash() -> [u8; 32] {
-    let mut hasher = Keccak::v256();
-    hasher.update(ORDER_TYPE.as_bytes());
-    let mut output = [0u8; 32];
-    hasher.finalize(&mut output);
-
-    output
-}
-
-pub fn encode_order(order: &Order) -> Vec<u8> {
-    let mut encoded: Vec<u8> = Vec::with_capacity(32 * 12);
-
-    encoded.extend_from_slice(&encode_uint256(&order.salt));
-    encoded.extend_from_slice(&encode_address(&order.maker));
-    encoded.extend_from_slice(&encode_address(&order.signer));
-    encoded.extend_from_slice(&encode_address(&order.taker));
-    encoded.extend_from_slice(&encode_uint256(&order.token_id)); // add later
-    encoded.extend_from_slice(&encode_uint256(&order.maker_amount)); // add later
-    encoded.extend_from_slice(&encode_uint256(&order.taker_amount)); // add later
-    encoded.extend_from_slice(&encode_uint256(&order.expiration));
-    encoded.extend_from_slice(&encode_uint256(&order.nonce));
-    encoded.extend_from_slice(&encode_uint256(&order.fee_rate_bps));
-    encoded.extend_from_slice(&encode_uint8(order.side));
-    encoded.extend_from_slice(&encode_uint256(&order.signature_type));
-
-    encoded
-}
-
-pub fn compute_order_struct_hash(order: &Order) -> [u8; 32] {
-    let type_hash = *TYPE_HASH;
-    let encoded_values = encode_order(order);
-
-    let mut data = Vec::with_capacity(32 + encoded_values.len());
-    data.extend_from_slice(&type_hash);
-    data.extend_from_slice(&encoded_values);
-
-    let mut hasher = Keccak::v256();
-    hasher.update(&data);
-    let mut output = [0u8; 32];
-    hasher.finalize(&mut output);
-
-    output
-}
-
 impl Order {
     pub fn to_dict(&self) -> HashMap<&str, String> {
         let mut order_map = HashMap::new();
@@ -423,9 +422,9 @@ pub struct SignedOrder {
 }
 
 pub struct RoundConfig {
-    price: usize,
-    size: usize,
-    amount: usize,
+    price: u32,
+    size: u32,
+    amount: u32,
 }
 
 impl SignedOrder {
@@ -453,23 +452,109 @@ impl SignedOrder {
 
         order_map
     }
+
+    /// `eth_signTypedData_v4` payload of the order.
+    pub fn typed_data(&self, neg_risk: bool) -> serde_json::Value {
+        eip712::typed_data_json(&exchange_domain(neg_risk), &self.order)
+    }
+
+    pub fn recover_signer(&self, neg_risk: bool) -> Result<Address, Box<dyn Error + Send + Sync>> {
+        let digest = eip712::signing_digest(&exchange_domain(neg_risk), &self.order);
+        eip712::recover_signer(&digest, &self.signature)
+    }
+
+    /// Checks offline that the signature was produced by `order.signer`.
+    pub fn verify(&self, neg_risk: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let recovered = self.recover_signer(neg_risk)?;
+        if recovered != self.order.signer {
+            return Err(format!(
+                "order signed by {:?}, expected {:?}",
+                recovered, self.order.signer
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn to_payload(&self) -> SignedOrderPayload {
+        let side = if self.order.side == UTILS_BUY {
+            BUY
+        } else {
+            SELL
+        };
+        SignedOrderPayload {
+            salt: self.order.salt.low_u64(),
+            maker: to_checksum(&self.order.maker, None),
+            signer: to_checksum(&self.order.signer, None),
+            taker: to_checksum(&self.order.taker, None),
+            token_id: self.order.token_id.to_string(),
+            maker_amount: self.order.maker_amount.to_string(),
+            taker_amount: self.order.taker_amount.to_string(),
+            expiration: self.order.expiration.to_string(),
+            nonce: self.order.nonce.to_string(),
+            fee_rate_bps: self.order.fee_rate_bps.to_string(),
+            side: side.to_string(),
+            signature_type: self.order.signature_type.low_u64(),
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+// The helpers below mirror py-clob-client's `order_builder/helpers.py`.
+
+// Parsed from the decimal string, as Python's `float()` does.
+fn to_f64(x: Decimal) -> f64 {
+    f64::from_str(&x.to_string()).unwrap_or_default()
 }
 
-fn round_down(x: f64, sig_digits: usize) -> f64 {
-    (x * 10f64.powi(sig_digits as i32)).floor() / 10f64.powi(sig_digits as i32)
+fn round_down(x: f64, dp: u32) -> f64 {
+    (x * 10f64.powi(dp as i32)).floor() / 10f64.powi(dp as i32)
 }
 
-fn round_normal(x: f64, sig_digits: usize) -> f64 {
-    (x * 10f64.powi(sig_digits as i32)).round() / 10f64.powi(sig_digits as i32)
+// Python's `round` rounds halves to even.
+fn round_normal(x: f64, dp: u32) -> f64 {
+    (x * 10f64.powi(dp as i32)).round_ties_even() / 10f64.powi(dp as i32)
+}
+
+fn round_up(x: f64, dp: u32) -> f64 {
+    (x * 10f64.powi(dp as i32)).ceil() / 10f64.powi(dp as i32)
+}
+
+// Decimals of the shortest representation; Python's `str` keeps one for whole numbers.
+fn decimal_places(x: f64) -> u32 {
+    let x = x.to_string();
+    x.find('.').map_or(1, |dot| (x.len() - dot - 1) as u32)
+}
+
+// Same two-step rounding as the Python client: try to absorb noise with a ceil at
+// `amount + 4` decimals, and only truncate to `amount` if that was not enough.
+fn round_amount(x: f64, amount: u32) -> f64 {
+    if decimal_places(x) <= amount {
+        return x;
+    }
+    let rounded = round_up(x, amount + 4);
+    if decimal_places(rounded) > amount {
+        round_down(rounded, amount)
+    } else {
+        rounded
+    }
+}
+
+fn to_token_decimals(x: f64) -> u64 {
+    round_normal(x * TOKEN_DECIMALS, 0) as u64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::clob_client::constants::POLYGON;
-    use crate::credentials::{ADDRESS, PRIVATE_KEY, SIGNER};
+    use crate::credentials::{ADDRESS, SIGNER};
+    use proptest::prelude::*;
+    use rust_decimal_macros::dec;
     use serde_json::Value;
-    use std::str::FromStr;
+
+    // Key that produced the signature in the known-order fixture.
+    const FIXTURE_KEY: &str = "0x8dc78334ff702005b631e249d1e02e76e179af634e4c3869add8dc007b4de411";
 
     fn parse_address(value: &Value, key: &str) -> Address {
         let as_str = value[key]
@@ -519,32 +604,214 @@ mod tests {
 
         let signature_type = order_value["signatureType"].as_u64().unwrap();
 
-        let signer = PolySigner::new(PRIVATE_KEY, POLYGON);
+        let signer = PolySigner::new(FIXTURE_KEY, POLYGON);
         let builder = OrderBuilder::new(signer, Some(signature_type), Some(*SIGNER));
 
-        let signed = builder.sign_prepared_order(order, true);
+        let signed = builder.sign_prepared_order(order, true).unwrap();
         let expected_signature = order_value["signature"].as_str().unwrap();
         assert_eq!(signed.signature, expected_signature);
     }
-}
 
-fn round_up(x: f64, sig_digits: usize) -> f64 {
-    (x * 10f64.powi(sig_digits as i32)).ceil() / 10f64.powi(sig_digits as i32)
-}
+    // Line-by-line port of py-clob-client's `get_order_amounts` and its helpers, f64 and
+    // all; checked against the Python output in `order_amounts_match_golden_vectors`.
+    mod python {
+        fn round_down(x: f64, sig_digits: i32) -> f64 {
+            (x * 10f64.powi(sig_digits)).floor() / 10f64.powi(sig_digits)
+        }
+
+        // Python's `round` rounds halves to even.
+        fn round_normal(x: f64, sig_digits: i32) -> f64 {
+            (x * 10f64.powi(sig_digits)).round_ties_even() / 10f64.powi(sig_digits)
+        }
+
+        fn round_up(x: f64, sig_digits: i32) -> f64 {
+            (x * 10f64.powi(sig_digits)).ceil() / 10f64.powi(sig_digits)
+        }
+
+        // `abs(Decimal(str(x)).as_tuple().exponent)`: `str` always keeps a decimal, 3.0
+        // has one place.
+        fn decimal_places(x: f64) -> i32 {
+            let x = x.to_string();
+            x.find('.').map_or(1, |dot| (x.len() - dot - 1) as i32)
+        }
+
+        fn to_token_decimals(x: f64) -> u64 {
+            let mut f = 1e6 * x;
+            if decimal_places(f) > 0 {
+                f = round_normal(f, 0);
+            }
+            f as u64
+        }
 
-fn to_token_decimals(x: f64) -> i64 {
-    let mut f = 10f64.powi(6) * x;
-    if decimal_places(f) > 0 {
-        f = round_normal(f, 0);
+        pub fn get_order_amounts(is_buy: bool, size: f64, price: f64, tick: &str) -> (u64, u64) {
+            let (price_digits, size_digits, amount_digits) = match tick {
+                "0.1" => (1, 2, 3),
+                "0.01" => (2, 2, 4),
+                "0.001" => (3, 2, 5),
+                "0.0001" => (4, 2, 6),
+                _ => panic!("unknown tick size {tick}"),
+            };
+            let raw_price = round_normal(price, price_digits);
+            let raw_size = round_down(size, size_digits);
+            let mut raw_other = raw_size * raw_price;
+            if decimal_places(raw_other) > amount_digits {
+                raw_other = round_up(raw_other, amount_digits + 4);
+                if decimal_places(raw_other) > amount_digits {
+                    raw_other = round_down(raw_other, amount_digits);
+                }
+            }
+            if is_buy {
+                (to_token_decimals(raw_other), to_token_decimals(raw_size))
+            } else {
+                (to_token_decimals(raw_size), to_token_decimals(raw_other))
+            }
+        }
     }
-    f.round() as i64
-}
 
-fn decimal_places(value: f64) -> usize {
-    let str_value = value.to_string();
-    if let Some(dot_index) = str_value.find('.') {
-        str_value.len() - dot_index - 1
-    } else {
-        0
+    fn amounts(
+        side: &str,
+        size: &str,
+        price: &str,
+        tick: &str,
+    ) -> Result<(u64, u64), OrderAmountError> {
+        get_order_amounts(
+            side,
+            Decimal::from_str(size).unwrap(),
+            Decimal::from_str(price).unwrap(),
+            tick,
+            None,
+        )
+        .map(|a| (a.maker_amount, a.taker_amount))
+    }
+
+    #[test]
+    fn order_amounts_match_golden_vectors() {
+        // (side, size, price, tick) -> (maker, taker), as produced by py-clob-client's
+        // `get_order_amounts`. From 0.57 on, its f64 floor takes a cent off the size.
+        let vectors = [
+            (BUY, "100", "0.5", "0.01", 50_000_000, 100_000_000),
+            (SELL, "100", "0.5", "0.01", 100_000_000, 50_000_000),
+            (BUY, "5", "0.943", "0.001", 4_715_000, 5_000_000),
+            (SELL, "12.34", "0.057", "0.001", 12_340_000, 703_380),
+            (BUY, "21.04", "0.1234", "0.0001", 2_596_336, 21_040_000),
+            (SELL, "7.77", "0.9999", "0.0001", 7_770_000, 7_769_223),
+            (BUY, "250.5", "0.3", "0.1", 75_150_000, 250_500_000),
+            (SELL, "19.5", "0.02", "0.01", 19_500_000, 390_000),
+            (BUY, "5.01", "0.999", "0.001", 5_004_990, 5_010_000),
+            (SELL, "1000", "0.42", "0.01", 1_000_000_000, 420_000_000),
+            (BUY, "33.33", "0.33", "0.01", 10_998_900, 33_330_000),
+            (SELL, "8.88", "0.0888", "0.0001", 8_880_000, 788_544),
+            (BUY, "0.57", "0.7", "0.1", 392_000, 560_000),
+            (SELL, "19.99", "0.01", "0.01", 19_980_000, 199_800),
+            (SELL, "4.35", "0.5", "0.01", 4_340_000, 2_170_000),
+            (BUY, "1.15", "0.33", "0.01", 376_200, 1_140_000),
+            (BUY, "64.6", "0.56", "0.01", 36_170_400, 64_590_000),
+            (SELL, "8.2", "0.123", "0.001", 8_190_000, 1_007_370),
+            (BUY, "528.43", "0.909", "0.001", 480_333_780, 528_420_000),
+            (SELL, "18.65", "0.0597", "0.0001", 18_640_000, 1_112_808),
+            (BUY, "566.17", "0.1348", "0.0001", 76_318_368, 566_160_000),
+        ];
+        for (side, size, price, tick, maker, taker) in vectors {
+            assert_eq!(
+                amounts(side, size, price, tick).unwrap(),
+                (maker, taker),
+                "{side} {size} @ {price} (tick {tick})"
+            );
+            assert_eq!(
+                python::get_order_amounts(
+                    side == BUY,
+                    f64::from_str(size).unwrap(),
+                    f64::from_str(price).unwrap(),
+                    tick
+                ),
+                (maker, taker),
+                "{side} {size} @ {price} (tick {tick})"
+            );
+        }
+    }
+
+    #[test]
+    fn order_amounts_reject_invalid_input() {
+        assert!(matches!(
+            amounts("HOLD", "10", "0.5", "0.01"),
+            Err(OrderAmountError::InvalidSide(_))
+        ));
+        assert!(matches!(
+            amounts(BUY, "10", "0.5", "0.05"),
+            Err(OrderAmountError::UnknownTickSize(_))
+        ));
+        assert!(matches!(
+            amounts(BUY, "10", "0.505", "0.01"),
+            Err(OrderAmountError::PriceOffTick { .. })
+        ));
+        assert!(matches!(
+            amounts(BUY, "10", "0.995", "0.01"),
+            Err(OrderAmountError::PriceOutOfRange { .. })
+        ));
+        assert!(matches!(
+            amounts(SELL, "10", "1", "0.01"),
+            Err(OrderAmountError::PriceOutOfRange { .. })
+        ));
+        assert!(matches!(
+            amounts(BUY, "10", "0", "0.01"),
+            Err(OrderAmountError::PriceOutOfRange { .. })
+        ));
+        assert!(matches!(
+            amounts(BUY, "0.001", "0.5", "0.01"),
+            Err(OrderAmountError::ZeroAmount { .. })
+        ));
+        assert_eq!(
+            get_order_amounts(BUY, dec!(4.99), dec!(0.5), "0.01", Some(dec!(5))),
+            Err(OrderAmountError::SizeBelowMinimum {
+                size: dec!(4.99),
+                min_size: dec!(5)
+            })
+        );
+        assert!(get_order_amounts(BUY, dec!(5), dec!(0.5), "0.01", Some(dec!(5))).is_ok());
+    }
+
+    fn tick_strategy() -> impl Strategy<Value = (&'static str, u32)> {
+        prop_oneof![
+            Just(("0.1", 1)),
+            Just(("0.01", 2)),
+            Just(("0.001", 3)),
+            Just(("0.0001", 4))
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn order_amounts_match_python(
+            (tick, price_dp) in tick_strategy(),
+            price_frac in 0.0f64..1.0,
+            size_units in 1i64..10_000_000,
+            size_dp in 0u32..=4,
+            is_buy in any::<bool>(),
+        ) {
+            let steps = 10i64.pow(price_dp);
+            let price_units = 1 + (price_frac * (steps - 1) as f64) as i64;
+            prop_assume!(price_units < steps);
+            let price = Decimal::new(price_units, price_dp);
+            let size = Decimal::new(size_units, size_dp);
+
+            let side = if is_buy { BUY } else { SELL };
+            let expected = python::get_order_amounts(
+                is_buy,
+                f64::from_str(&size.to_string()).unwrap(),
+                f64::from_str(&price.to_string()).unwrap(),
+                tick,
+            );
+            match get_order_amounts(side, size, price, tick, None) {
+                Ok(got) => {
+                    prop_assert_eq!((got.maker_amount, got.taker_amount), expected);
+                    prop_assert_eq!(got.side, if is_buy { UTILS_BUY } else { UTILS_SELL });
+                }
+                // Sizes under a cent floor to nothing there as well.
+                Err(OrderAmountError::ZeroAmount { .. }) => {
+                    prop_assert!(expected.0 == 0 || expected.1 == 0)
+                }
+                Err(e) => prop_assert!(false, "{}", e),
+            }
+        }
     }
 }
@@ -1,30 +1,34 @@
-use super::builder::{get_order_amounts, OrderBuilder, SignedOrder, ROUND_CONFIG};
+use super::builder::{get_order_amounts, OrderBuilder, SignedOrder};
 use super::clob_types::{ApiCreds, CreateOrderOptions, OrderArgs};
 use super::constants::{HOST, L2, POLYGON};
 use super::endpoints::{CANCEL_ALL, CANCEL_ORDERS};
 use super::headers::create_level_2_headers;
 use super::hmac::build_hmac_signature;
 use super::http_helpers::post;
-use super::prebuilt_order::PrebuiltOrder;
+use super::prebuilt_order::{OrderTemplate, OrderTemplateCache};
 use super::signer::PolySigner;
 use super::utils::{order_to_json, prepend_zx};
 use super::{clob_types::RequestArgs, http_helpers::delete};
-use crate::clob_client::builder::encode_order;
-use crate::clob_client::clob_types::{BalanceAllowanceParameters, OpenOrderParams};
+use crate::clob_client::clob_types::{
+    BalanceAllowanceParameters, BalanceAllowanceResponse, CancelOrdersResponse, OpenOrderParams,
+    OpenOrderResponse, OpenOrdersPage, PostOrderResponse,
+};
 use crate::clob_client::constants::END_CURSOR;
 use crate::clob_client::endpoints::{GET_BALANCE_ALLOWANCE, GET_LAST_TRADES_PRICES, ORDERS};
 use crate::clob_client::http_helpers::{
-    add_balance_allowance_params_to_url, build_query_params, get,
+    add_balance_allowance_params_to_url, build_query_params, get, parse_response,
 };
 use ethers::abi::token;
 use ethers::types::Address;
 use ethers::utils::{keccak256, to_checksum};
 use num_cpus;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rust_decimal::Decimal;
 use serde_json::Value;
 use std::error::Error;
 use std::process;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tiny_keccak::{Hasher, Keccak};
 use tokio::runtime::{Builder, Runtime};
@@ -50,6 +54,7 @@ pub struct ClobClient {
     pub mode: u128,
     pub checksum_address: String,
     pub high_priority_runtime: Runtime,
+    pub order_templates: OrderTemplateCache,
 }
 
 impl ClobClient {
@@ -60,7 +65,16 @@ impl ClobClient {
         signature_type: Option<u64>,
         funder: Option<Address>,
     ) -> Self {
-        let signer = PolySigner::new(key, POLYGON);
+        Self::with_signer(PolySigner::new(key, POLYGON), creds, signature_type, funder)
+    }
+
+    /// Same as `new`, but with any signer backend instead of a raw private key.
+    pub fn with_signer(
+        signer: PolySigner,
+        creds: ApiCreds,
+        signature_type: Option<u64>,
+        funder: Option<Address>,
+    ) -> Self {
         let address_checksum = to_checksum(&signer.address(), None);
         let high_priority_runtime = Builder::new_multi_thread()
             .worker_threads(num_cpus::get()) // Use all available CPU cores
@@ -74,13 +88,14 @@ impl ClobClient {
             builder: OrderBuilder::new(signer, signature_type, funder),
             checksum_address: address_checksum,
             high_priority_runtime: high_priority_runtime,
+            order_templates: OrderTemplateCache::default(),
         }
     }
 
     pub async fn cancel_orders(
         &self,
         order_ids: &[&str],
-    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    ) -> Result<CancelOrdersResponse, Box<dyn Error + Send + Sync>> {
         let body = convert_vec_to_json_value(order_ids);
 
         let request_args = RequestArgs {
@@ -91,21 +106,21 @@ impl ClobClient {
 
         let url = format!("{}{}", HOST, request_args.request_path);
         let headers = create_level_2_headers(&self.signer, &self.creds, &request_args);
-        let resp = delete(&url, Some(headers.to_header_map()), body.as_ref()).await;
+        let resp = delete(&url, Some(headers.to_header_map()), body.as_ref())
+            .await
+            .and_then(|value| parse_response::<CancelOrdersResponse>(&value));
         match resp {
             Ok(result) => {
-                // Treat missing or non-array `not_canceled` as empty slice
-                let not_canceled = result
-                    .get("not_canceled")
-                    .and_then(|v| v.as_array().map(|vec| vec.as_slice()))
-                    .unwrap_or(&[]);
-
-                if !not_canceled.is_empty() {
-                    println!("there are {} item(s) in `not_canceled`", not_canceled.len());
+                if !result.not_canceled.is_empty() {
+                    println!(
+                        "there are {} item(s) in `not_canceled`: {:?}",
+                        result.not_canceled.len(),
+                        result.not_canceled
+                    );
                     println!("FAIL SAFE HIT");
                     process::exit(1);
                 }
-                return Ok(result);
+                Ok(result)
             }
 
             Err(e) => {
@@ -113,12 +128,11 @@ impl ClobClient {
                 println!("order ids {:?}", order_ids);
                 println!("FAIL SAFE HIT");
                 process::exit(1);
-                return Err(e);
             }
         }
     }
 
-    pub async fn cancel_all(&self) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    pub async fn cancel_all(&self) -> Result<CancelOrdersResponse, Box<dyn Error + Send + Sync>> {
         // return Ok(().into());
         let request_args = RequestArgs {
             method: "DELETE",
@@ -129,14 +143,12 @@ impl ClobClient {
         let open_orders = self.get_orders(None, None).await?;
         let mut orders_to_cancel = vec![];
         for order in open_orders.iter() {
-            if let Some("No") = order.get("outcome").and_then(Value::as_str) {
-                if let Some(id_str) = order.get("id").and_then(Value::as_str) {
-                    orders_to_cancel.push(id_str);
-                }
+            if order.outcome == "No" && !order.id.is_empty() {
+                orders_to_cancel.push(order.id.as_str());
             }
         }
         if orders_to_cancel.is_empty() {
-            return Ok(().into());
+            return Ok(CancelOrdersResponse::default());
         }
         return self.cancel_orders(orders_to_cancel.as_slice()).await;
         // let url = format!("{}{}", HOST, request_args.request_path);
@@ -162,24 +174,58 @@ impl ClobClient {
         order_args: &OrderArgs,
         tick_size: &str,
         neg_risk: bool,
-    ) -> SignedOrder {
+        min_size: Option<Decimal>,
+    ) -> Result<SignedOrder, Box<dyn Error + Send + Sync>> {
         let order_options = CreateOrderOptions {
             tick_size: tick_size,
             neg_risk: neg_risk,
+            min_size,
         };
         // self.builder.create_order();
         self.builder.create_order(order_args, &order_options)
     }
 
-    pub fn build_prebuilt_order(&self) -> PrebuiltOrder {
-        super::prebuilt_order::build_prebuilt_order(&self.creds, &self.signer, self.builder.funder)
+    /// Builds order templates up front so the first order on each asset skips the encoding.
+    pub fn warm_order_templates<'a>(&self, assets: impl IntoIterator<Item = (&'a str, bool)>) {
+        for (token_id, neg_risk) in assets {
+            if let Err(e) = self
+                .order_templates
+                .get_or_build(&self.builder, token_id, neg_risk)
+            {
+                log::warn!("Failed to build order template for {}: {}", token_id, e);
+            }
+        }
+    }
+
+    pub fn order_template(
+        &self,
+        token_id: &str,
+        neg_risk: bool,
+    ) -> Result<Arc<OrderTemplate>, Box<dyn Error + Send + Sync>> {
+        self.order_templates
+            .get_or_build(&self.builder, token_id, neg_risk)
+    }
+
+    /// Low-latency alternative to `create_order`, signing from a cached per-asset template.
+    pub fn create_order_fast(
+        &self,
+        token_id: &str,
+        side: &str,
+        price: Decimal,
+        size: Decimal,
+        options: &CreateOrderOptions,
+        expiration: u64,
+    ) -> Result<SignedOrder, Box<dyn Error + Send + Sync>> {
+        let amounts = get_order_amounts(side, size, price, options.tick_size, options.min_size)?;
+        let template = self.order_template(token_id, options.neg_risk)?;
+        template.sign(&amounts, expiration)
     }
 
     pub async fn post_taker_order(
         &self,
         order: &SignedOrder,
         order_type: &str,
-    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    ) -> Result<PostOrderResponse, Box<dyn Error + Send + Sync>> {
         let body = Some(order_to_json(order, &self.creds.api_key, order_type));
         let request_args = RequestArgs {
             method: "POST",
@@ -240,24 +286,27 @@ impl ClobClient {
             handles.push(handle);
         }
 
-        // Wait for all spawned tasks and collect their results
+        // Wait for all spawned tasks; the first accepted response wins.
         let results: Vec<_> = futures::future::join_all(handles).await;
-        // println!("results {:?}", results);
-        // for (i, res) in results.into_iter().enumerate() {
-        // match res {
-        // Ok(Ok(response)) =>,// println!("Response {}: {:?}", i, response),
-        // Ok(Err(e)) => eprintln!("Request {} failed: {}", i, e),
-        // Err(e) => eprintln!("Task {} panicked: {:?}", i, e),
-        // }
-        // }
-        Ok(().into())
+        let mut last_error: Option<Box<dyn Error + Send + Sync>> = None;
+        for res in results {
+            match res {
+                Ok(Ok(value)) => match parse_response::<PostOrderResponse>(&value) {
+                    Ok(response) => return Ok(response),
+                    Err(e) => last_error = Some(e),
+                },
+                Ok(Err(e)) => last_error = Some(e),
+                Err(e) => last_error = Some(Box::new(e)),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| "no taker order request was sent".into()))
     }
 
     pub async fn post_order(
         &self,
         order: &SignedOrder,
         order_type: &str,
-    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    ) -> Result<PostOrderResponse, Box<dyn Error + Send + Sync>> {
         let body = Some(order_to_json(order, &self.creds.api_key, order_type));
 
         let request_args = RequestArgs {
@@ -304,13 +353,14 @@ impl ClobClient {
             HeaderValue::from_str(&self.creds.api_pass).unwrap(),
         );
 
-        post(&url, Some(headers), body.as_ref()).await
+        let value = post(&url, Some(headers), body.as_ref()).await?;
+        parse_response(&value)
     }
 
     pub async fn get_balance_allowance(
         &self,
         mut params: BalanceAllowanceParameters,
-    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    ) -> Result<BalanceAllowanceResponse, Box<dyn Error + Send + Sync>> {
         let request_args = RequestArgs {
             method: "GET",
             request_path: GET_BALANCE_ALLOWANCE,
@@ -330,14 +380,15 @@ impl ClobClient {
         }
 
         let url = add_balance_allowance_params_to_url(&pre_url, Some(&params));
-        get(&url, Some(headers.to_header_map())).await
+        let value = get(&url, Some(headers.to_header_map())).await?;
+        parse_response(&value)
     }
 
     pub async fn get_orders(
         &self,
         params: Option<OpenOrderParams>,
         next_cursor: Option<String>,
-    ) -> Result<Vec<Value>, Box<dyn Error + Send + Sync>> {
+    ) -> Result<Vec<OpenOrderResponse>, Box<dyn Error + Send + Sync>> {
         let request_args = RequestArgs {
             method: "GET",
             request_path: ORDERS,
@@ -354,17 +405,13 @@ impl ClobClient {
                 params.as_ref(),
                 &cursor,
             );
-            let response = get(&url, Some(headers.to_header_map())).await.unwrap();
-            cursor = response
-                .get("next_cursor")
-                .and_then(Value::as_str)
-                .map(str::to_string)
+            let response = get(&url, Some(headers.to_header_map())).await?;
+            let page: OpenOrdersPage = parse_response(&response)?;
+            cursor = page
+                .next_cursor
+                .filter(|c| !c.is_empty())
                 .unwrap_or_else(|| END_CURSOR.to_string());
-            if let Some(data) = response.get("data").and_then(Value::as_array) {
-                for item in data {
-                    results.push(item.clone());
-                }
-            }
+            results.extend(page.data);
         }
 
         Ok(results)
@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use super::constants::ZERO_ADDRESS;
 use ethers::types::Address;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
 #[derive(Clone, Debug)]
@@ -51,8 +54,8 @@ pub struct RequestArgs<'a> {
 #[derive(Clone, Debug)]
 pub struct OrderArgs<'a> {
     pub token_id: &'a str,
-    pub price: f64,
-    pub size: f64,
+    pub price: Decimal,
+    pub size: Decimal,
     pub side: &'a str,
     pub fee_rate_bps: usize, // default 0
     pub nonce: usize,        // default 0
@@ -63,8 +66,8 @@ pub struct OrderArgs<'a> {
 impl<'a> OrderArgs<'a> {
     pub fn new(
         token_id: &'a str,
-        price: f64,
-        size: f64,
+        price: Decimal,
+        size: Decimal,
         side: &'a str,
         fee_rate_bps: Option<usize>,
         nonce: Option<usize>,
@@ -88,4 +91,235 @@ impl<'a> OrderArgs<'a> {
 pub struct CreateOrderOptions<'a> {
     pub tick_size: &'a str, // ["0.1", "0.01", "0.001", "0.0001"]
     pub neg_risk: bool,
+    pub min_size: Option<Decimal>, // market `orderMinSize`, if known
+}
+
+/// Order body sent to `POST /order`, mirroring the py-clob-client payload.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedOrderPayload {
+    pub salt: u64,
+    pub maker: String,
+    pub signer: String,
+    pub taker: String,
+    pub token_id: String,
+    pub maker_amount: String,
+    pub taker_amount: String,
+    pub expiration: String,
+    pub nonce: String,
+    pub fee_rate_bps: String,
+    pub side: String,
+    pub signature_type: u64,
+    pub signature: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostOrderRequest<'a> {
+    pub order: SignedOrderPayload,
+    pub owner: &'a str,
+    pub order_type: &'a str,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PostOrderStatus {
+    Live,
+    Matched,
+    Delayed,
+    Unmatched,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+/// Response of `POST /order`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PostOrderResponse {
+    #[serde(default)]
+    pub success: bool,
+    #[serde(rename = "errorMsg", default)]
+    pub error_msg: String,
+    #[serde(rename = "orderID", alias = "orderId", default)]
+    pub order_id: String,
+    #[serde(default)]
+    pub status: PostOrderStatus,
+    #[serde(
+        rename = "makingAmount",
+        default,
+        deserialize_with = "string_or_number"
+    )]
+    pub making_amount: String,
+    #[serde(
+        rename = "takingAmount",
+        default,
+        deserialize_with = "string_or_number"
+    )]
+    pub taking_amount: String,
+    #[serde(rename = "transactionsHashes", default)]
+    pub transactions_hashes: Vec<String>,
+    #[serde(rename = "tradeIDs", default)]
+    pub trade_ids: Vec<String>,
+}
+
+impl PostOrderResponse {
+    /// Returns the order id if the exchange accepted the order.
+    pub fn accepted_order_id(&self) -> Option<&str> {
+        if self.order_id.is_empty() || !self.error_msg.is_empty() {
+            None
+        } else {
+            Some(self.order_id.as_str())
+        }
+    }
+}
+
+/// A single resting order as returned by `GET /data/orders`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct OpenOrderResponse {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub maker_address: String,
+    #[serde(default)]
+    pub market: String,
+    #[serde(default)]
+    pub asset_id: String,
+    #[serde(default)]
+    pub side: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub original_size: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub size_matched: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub price: String,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub expiration: String,
+    #[serde(default)]
+    pub order_type: String,
+    #[serde(default)]
+    pub associate_trades: Vec<String>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// One page of `GET /data/orders`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct OpenOrdersPage {
+    #[serde(default)]
+    pub data: Vec<OpenOrderResponse>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub count: Option<i64>,
+}
+
+/// Response of `GET /balance-allowance`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct BalanceAllowanceResponse {
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub balance: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub allowance: String,
+    #[serde(default)]
+    pub allowances: HashMap<String, String>,
+}
+
+/// Response of `DELETE /orders` and `DELETE /cancel-all`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CancelOrdersResponse {
+    #[serde(default)]
+    pub canceled: Vec<String>,
+    #[serde(default)]
+    pub not_canceled: HashMap<String, String>,
+}
+
+impl CancelOrdersResponse {
+    pub fn is_canceled(&self, order_id: &str) -> bool {
+        self.canceled.iter().any(|id| id == order_id)
+    }
+}
+
+// The CLOB is not consistent about quoting numeric fields, so accept both.
+fn string_or_number<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Null => Ok(String::new()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected string or number, got {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_post_order_responses() {
+        let live: PostOrderResponse = serde_json::from_str(
+            r#"{"errorMsg":"","orderID":"0xabc","takingAmount":"","makingAmount":"","status":"live","transactionsHashes":[],"success":true}"#,
+        )
+        .unwrap();
+        assert_eq!(live.status, PostOrderStatus::Live);
+        assert_eq!(live.accepted_order_id(), Some("0xabc"));
+
+        // A taker order that traded immediately.
+        let matched: PostOrderResponse = serde_json::from_str(
+            r#"{"errorMsg":"","orderID":"0xdef","takingAmount":"100","makingAmount":52.5,"status":"matched","transactionsHashes":["0x01"],"tradeIDs":["t-1","t-2"],"success":true}"#,
+        )
+        .unwrap();
+        assert_eq!(matched.status, PostOrderStatus::Matched);
+        assert_eq!(matched.making_amount, "52.5");
+        assert_eq!(matched.trade_ids, vec!["t-1", "t-2"]);
+
+        let rejected: PostOrderResponse = serde_json::from_str(
+            r#"{"errorMsg":"not enough balance / allowance","orderID":"","status":"paused","success":false}"#,
+        )
+        .unwrap();
+        assert_eq!(rejected.status, PostOrderStatus::Unknown);
+        assert_eq!(rejected.accepted_order_id(), None);
+    }
+
+    #[test]
+    fn parses_cancel_response_with_not_canceled_map() {
+        let resp: CancelOrdersResponse = serde_json::from_str(
+            r#"{"canceled":["0x1"],"not_canceled":{"0x2":"matched orders can't be canceled","0x3":"order not found"}}"#,
+        )
+        .unwrap();
+        assert!(resp.is_canceled("0x1"));
+        assert!(!resp.is_canceled("0x2"));
+        assert_eq!(resp.not_canceled.len(), 2);
+        assert_eq!(resp.not_canceled["0x3"], "order not found");
+
+        let empty: CancelOrdersResponse = serde_json::from_str("{}").unwrap();
+        assert!(empty.canceled.is_empty() && empty.not_canceled.is_empty());
+    }
+
+    #[test]
+    fn parses_open_orders_page() {
+        let page: OpenOrdersPage = serde_json::from_str(
+            r#"{"data":[{"id":"0x1","status":"LIVE","asset_id":"123","side":"BUY","original_size":"50","size_matched":0,"price":"0.47","outcome":"No","expiration":"0","order_type":"GTC","associate_trades":[],"created_at":1700000000}],"next_cursor":"LTE=","limit":100,"count":1}"#,
+        )
+        .unwrap();
+        assert_eq!(page.next_cursor.as_deref(), Some("LTE="));
+        let order = &page.data[0];
+        assert_eq!(
+            (order.price.as_str(), order.size_matched.as_str()),
+            ("0.47", "0")
+        );
+        assert_eq!(order.outcome, "No");
+    }
 }
@@ -1,29 +1,456 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use ethers::types::{Address, Signature, H256, U256};
+use ethers::utils::{keccak256, to_checksum};
 use lazy_static::lazy_static;
+use serde_json::{json, Map, Value};
 
+use super::builder::Order;
 use super::constants::POLYGON;
 
-use super::clob_auth::EIP712Domain;
-
-const CLOB_DOMAIN_NAME: &str = "ClobAuthDomain";
-const CLOB_VERSION: &str = "1";
-const MSG_TO_SIGN: &str = "This message attests that I control the given wallet";
+pub const CLOB_DOMAIN_NAME: &str = "ClobAuthDomain";
+pub const CLOB_VERSION: &str = "1";
+pub const MSG_TO_SIGN: &str = "This message attests that I control the given wallet";
 
 lazy_static! {
-    static ref CLOB_AUTH_DOMAIN: EIP712Domain<'static> = EIP712Domain::new(
+    pub static ref CLOB_AUTH_DOMAIN: EIP712Domain<'static> = make_domain(
         Some(CLOB_DOMAIN_NAME),
         Some(CLOB_VERSION),
-        Some(ethers::types::U256::from(POLYGON)),  // This should be a constant as well
+        Some(U256::from(POLYGON)),
         None
     );
 }
 
-// fn get_clob_auth_domain() -> &'static EIP712Domain<'static> {
-//     &CLOB_AUTH_DOMAIN
-//     // let eip_domain = EIP712Domain::new(
-//     //     Some(CLOB_DOMAIN_NAME),
-//     //     Some(CLOB_VERSION),
-//     //     Some(chain_id),
-//     //     None
-//     // );
-//     // eip_domain
-// }
+/// Solidity types used by the Polymarket typed structs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Address,
+    Uint256,
+    Uint8,
+    String,
+}
+
+impl FieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::Address => "address",
+            FieldType::Uint256 => "uint256",
+            FieldType::Uint8 => "uint8",
+            FieldType::String => "string",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    Address(Address),
+    Uint256(U256),
+    Uint8(u8),
+    String(String),
+}
+
+impl FieldValue {
+    /// `encodeData` encoding of a single member.
+    pub fn encode(&self) -> [u8; 32] {
+        match self {
+            FieldValue::Address(addr) => encode_address(addr),
+            FieldValue::Uint256(value) => encode_uint256(value),
+            FieldValue::Uint8(value) => encode_uint8(*value),
+            FieldValue::String(value) => keccak256(value.as_bytes()),
+        }
+    }
+
+    /// `eth_signTypedData_v4` JSON representation of a single member.
+    pub fn to_json(&self) -> Value {
+        match self {
+            FieldValue::Address(addr) => Value::String(to_checksum(addr, None)),
+            FieldValue::Uint256(value) => Value::String(value.to_string()),
+            FieldValue::Uint8(value) => json!(value),
+            FieldValue::String(value) => Value::String(value.clone()),
+        }
+    }
+}
+
+pub fn encode_uint256(value: &U256) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    value.to_big_endian(&mut buf);
+    buf
+}
+
+pub fn encode_uint8(value: u8) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf[31] = value;
+    buf
+}
+
+pub fn encode_address(addr: &Address) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf[12..].copy_from_slice(addr.as_bytes());
+    buf
+}
+
+/// A flat EIP-712 struct (no nested struct members).
+pub trait Eip712Struct {
+    const TYPE_NAME: &'static str;
+    const FIELDS: &'static [(&'static str, FieldType)];
+
+    /// Member values, in `FIELDS` order.
+    fn values(&self) -> Vec<FieldValue>;
+
+    fn encode_type() -> String {
+        let members: Vec<String> = Self::FIELDS
+            .iter()
+            .map(|(name, ty)| format!("{} {}", ty.as_str(), name))
+            .collect();
+        format!("{}({})", Self::TYPE_NAME, members.join(","))
+    }
+
+    fn type_hash() -> [u8; 32] {
+        keccak256(Self::encode_type().as_bytes())
+    }
+
+    fn encode_data(&self) -> Vec<u8> {
+        let values = self.values();
+        let mut encoded = Vec::with_capacity(32 * values.len());
+        for value in &values {
+            encoded.extend_from_slice(&value.encode());
+        }
+        encoded
+    }
+
+    fn struct_hash(&self) -> [u8; 32] {
+        let encoded = self.encode_data();
+        let mut data = Vec::with_capacity(32 + encoded.len());
+        data.extend_from_slice(&Self::type_hash());
+        data.extend_from_slice(&encoded);
+        keccak256(&data)
+    }
+
+    fn message_json(&self) -> Value {
+        let mut message = Map::new();
+        for ((name, _), value) in Self::FIELDS.iter().zip(self.values()) {
+            message.insert(name.to_string(), value.to_json());
+        }
+        Value::Object(message)
+    }
+}
+
+pub struct EIP712Domain<'a> {
+    pub name: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub chain_id: Option<U256>,
+    pub verifying_contract: Option<Address>,
+}
+
+impl<'a> EIP712Domain<'a> {
+    pub fn new(
+        name: Option<&'a str>,
+        version: Option<&'a str>,
+        chain_id: Option<U256>,
+        verifying_contract: Option<Address>,
+    ) -> Self {
+        Self {
+            name,
+            version,
+            chain_id,
+            verifying_contract,
+        }
+    }
+
+    /// Present members only, in canonical EIP-712 domain order.
+    fn members(&self) -> Vec<(&'static str, FieldType, FieldValue)> {
+        let mut members = Vec::with_capacity(4);
+        if let Some(name) = self.name {
+            members.push((
+                "name",
+                FieldType::String,
+                FieldValue::String(name.to_string()),
+            ));
+        }
+        if let Some(version) = self.version {
+            members.push((
+                "version",
+                FieldType::String,
+                FieldValue::String(version.to_string()),
+            ));
+        }
+        if let Some(chain_id) = self.chain_id {
+            members.push(("chainId", FieldType::Uint256, FieldValue::Uint256(chain_id)));
+        }
+        if let Some(verifying_contract) = self.verifying_contract {
+            members.push((
+                "verifyingContract",
+                FieldType::Address,
+                FieldValue::Address(verifying_contract),
+            ));
+        }
+        members
+    }
+
+    pub fn encode_type(&self) -> String {
+        let members: Vec<String> = self
+            .members()
+            .iter()
+            .map(|(name, ty, _)| format!("{} {}", ty.as_str(), name))
+            .collect();
+        format!("EIP712Domain({})", members.join(","))
+    }
+
+    pub fn struct_hash(&self) -> [u8; 32] {
+        let members = self.members();
+        let mut data = Vec::with_capacity(32 * (members.len() + 1));
+        data.extend_from_slice(&keccak256(self.encode_type().as_bytes()));
+        for (_, _, value) in &members {
+            data.extend_from_slice(&value.encode());
+        }
+        keccak256(&data)
+    }
+
+    fn types_json(&self) -> Value {
+        Value::Array(
+            self.members()
+                .iter()
+                .map(|(name, ty, _)| json!({ "name": name, "type": ty.as_str() }))
+                .collect(),
+        )
+    }
+
+    fn to_json(&self) -> Value {
+        let mut domain = Map::new();
+        for (name, ty, value) in self.members() {
+            // Wallets expect a numeric chainId in the domain.
+            let value = match (ty, value) {
+                (FieldType::Uint256, FieldValue::Uint256(v)) if v <= U256::from(u64::MAX) => {
+                    json!(v.as_u64())
+                }
+                (_, value) => value.to_json(),
+            };
+            domain.insert(name.to_string(), value);
+        }
+        Value::Object(domain)
+    }
+}
+
+pub fn make_domain<'a>(
+    name: Option<&'a str>,
+    version: Option<&'a str>,
+    chain_id: Option<U256>,
+    verifying_contract: Option<Address>,
+) -> EIP712Domain<'a> {
+    EIP712Domain::new(name, version, chain_id, verifying_contract)
+}
+
+/// `keccak256(0x19 0x01 || domainSeparator || hashStruct(message))`.
+pub fn signing_digest<T: Eip712Struct>(domain: &EIP712Domain, message: &T) -> [u8; 32] {
+    let mut data = [0u8; 66];
+    data[0] = 0x19;
+    data[1] = 0x01;
+    data[2..34].copy_from_slice(&domain.struct_hash());
+    data[34..].copy_from_slice(&message.struct_hash());
+    keccak256(data)
+}
+
+/// Payload for `eth_signTypedData_v4`.
+pub fn typed_data_json<T: Eip712Struct>(domain: &EIP712Domain, message: &T) -> Value {
+    let fields: Vec<Value> = T::FIELDS
+        .iter()
+        .map(|(name, ty)| json!({ "name": name, "type": ty.as_str() }))
+        .collect();
+    let mut types = Map::new();
+    types.insert("EIP712Domain".to_string(), domain.types_json());
+    types.insert(T::TYPE_NAME.to_string(), Value::Array(fields));
+
+    json!({
+        "types": types,
+        "primaryType": T::TYPE_NAME,
+        "domain": domain.to_json(),
+        "message": message.message_json(),
+    })
+}
+
+/// Recovers the address that produced `signature` (hex, with or without `0x`) over `digest`.
+pub fn recover_signer(
+    digest: &[u8; 32],
+    signature: &str,
+) -> Result<Address, Box<dyn Error + Send + Sync>> {
+    let signature = Signature::from_str(signature.trim_start_matches("0x"))?;
+    Ok(signature.recover(H256::from(*digest))?)
+}
+
+impl Eip712Struct for Order {
+    const TYPE_NAME: &'static str = "Order";
+    const FIELDS: &'static [(&'static str, FieldType)] = &[
+        ("salt", FieldType::Uint256),
+        ("maker", FieldType::Address),
+        ("signer", FieldType::Address),
+        ("taker", FieldType::Address),
+        ("tokenId", FieldType::Uint256),
+        ("makerAmount", FieldType::Uint256),
+        ("takerAmount", FieldType::Uint256),
+        ("expiration", FieldType::Uint256),
+        ("nonce", FieldType::Uint256),
+        ("feeRateBps", FieldType::Uint256),
+        ("side", FieldType::Uint8),
+        ("signatureType", FieldType::Uint8),
+    ];
+
+    fn values(&self) -> Vec<FieldValue> {
+        vec![
+            FieldValue::Uint256(self.salt),
+            FieldValue::Address(self.maker),
+            FieldValue::Address(self.signer),
+            FieldValue::Address(self.taker),
+            FieldValue::Uint256(self.token_id),
+            FieldValue::Uint256(self.maker_amount),
+            FieldValue::Uint256(self.taker_amount),
+            FieldValue::Uint256(self.expiration),
+            FieldValue::Uint256(self.nonce),
+            FieldValue::Uint256(self.fee_rate_bps),
+            FieldValue::Uint8(self.side),
+            FieldValue::Uint8(self.signature_type.low_u32() as u8),
+        ]
+    }
+}
+
+/// L1 authentication message signed when deriving API keys.
+#[derive(Debug, Clone)]
+pub struct ClobAuth {
+    pub address: Address,
+    pub timestamp: String,
+    pub nonce: U256,
+    pub message: String,
+}
+
+impl ClobAuth {
+    pub fn new(address: Address, timestamp: &str, nonce: U256) -> Self {
+        Self {
+            address,
+            timestamp: timestamp.to_string(),
+            nonce,
+            message: MSG_TO_SIGN.to_string(),
+        }
+    }
+}
+
+impl Eip712Struct for ClobAuth {
+    const TYPE_NAME: &'static str = "ClobAuth";
+    const FIELDS: &'static [(&'static str, FieldType)] = &[
+        ("address", FieldType::Address),
+        ("timestamp", FieldType::String),
+        ("nonce", FieldType::Uint256),
+        ("message", FieldType::String),
+    ];
+
+    fn values(&self) -> Vec<FieldValue> {
+        vec![
+            FieldValue::Address(self.address),
+            FieldValue::String(self.timestamp.clone()),
+            FieldValue::Uint256(self.nonce),
+            FieldValue::String(self.message.clone()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clob_client::builder::{exchange_domain, OrderBuilder, SignedOrder};
+    use crate::clob_client::signer::PolySigner;
+    use ethers::types::transaction::eip712::{Eip712, TypedData};
+
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    // Order signed in production (see `builder::tests::replicates_known_signature`).
+    fn known_order() -> SignedOrder {
+        SignedOrder {
+            order: Order {
+                salt: U256::from(1_260_445_392_909u64),
+                maker: Address::from_str("0xB0A60787710f8D6254dC0E304Fc72b6A3907e0C2").unwrap(),
+                signer: Address::from_str("0x59Bb2eca7dDC4553fA936129D3613b1aA340C278").unwrap(),
+                taker: Address::zero(),
+                token_id: U256::from_dec_str(
+                    "104468181147316868388088006861839293041095272602974154655578369735976654024471",
+                )
+                .unwrap(),
+                maker_amount: U256::from(4_715_000u64),
+                taker_amount: U256::from(5_000_000u64),
+                expiration: U256::zero(),
+                nonce: U256::zero(),
+                fee_rate_bps: U256::zero(),
+                side: 0,
+                signature_type: U256::from(2u64),
+            },
+            signature: "0xf12cf29df658868b426ecec75b7071b99e4862f84c92428e8bc56bf47f9831921a95ff1cd4b0fc3c9a22940b0c5d1d2ffc13ddb2f16fac58a30d884c3f552cef1b".to_string(),
+        }
+    }
+
+    fn ethers_digest(typed_data: Value) -> [u8; 32] {
+        let typed_data: TypedData = serde_json::from_value(typed_data).unwrap();
+        typed_data.encode_eip712().unwrap()
+    }
+
+    #[test]
+    fn order_type_string() {
+        assert_eq!(
+            Order::encode_type(),
+            "Order(uint256 salt,address maker,address signer,address taker,uint256 tokenId,uint256 makerAmount,uint256 takerAmount,uint256 expiration,uint256 nonce,uint256 feeRateBps,uint8 side,uint8 signatureType)"
+        );
+        assert_eq!(
+            ClobAuth::encode_type(),
+            "ClobAuth(address address,string timestamp,uint256 nonce,string message)"
+        );
+    }
+
+    #[test]
+    fn recovers_known_order_signer() {
+        let signed = known_order();
+        assert_eq!(signed.recover_signer(true).unwrap(), signed.order.signer);
+        assert!(signed.verify(true).is_ok());
+        // Same signature against the wrong exchange domain recovers someone else.
+        assert!(signed.verify(false).is_err());
+
+        let mut tampered = known_order();
+        tampered.order.maker_amount = U256::from(4_715_001u64);
+        assert!(tampered.verify(true).is_err());
+    }
+
+    #[test]
+    fn typed_data_json_matches_ethers_encoder() {
+        let order = known_order().order;
+        for neg_risk in [false, true] {
+            let domain = exchange_domain(neg_risk);
+            assert_eq!(
+                ethers_digest(typed_data_json(&domain, &order)),
+                signing_digest(&domain, &order)
+            );
+        }
+
+        let auth = ClobAuth::new(order.signer, "10000000", U256::from(23u64));
+        assert_eq!(
+            ethers_digest(typed_data_json(&CLOB_AUTH_DOMAIN, &auth)),
+            signing_digest(&CLOB_AUTH_DOMAIN, &auth)
+        );
+    }
+
+    #[test]
+    fn signed_orders_verify_offline() {
+        let signer = PolySigner::new(TEST_KEY, POLYGON);
+        let builder = OrderBuilder::new(signer.clone(), Some(0), None);
+        for neg_risk in [false, true] {
+            let mut order = known_order().order;
+            order.signer = signer.address();
+            let signed = builder.sign_prepared_order(order, neg_risk).unwrap();
+            assert_eq!(signed.recover_signer(neg_risk).unwrap(), signer.address());
+            assert!(signed.verify(neg_risk).is_ok());
+        }
+
+        let auth = ClobAuth::new(signer.address(), "10000000", U256::from(23u64));
+        let digest = signing_digest(&CLOB_AUTH_DOMAIN, &auth);
+        let signature = signer.sign(&H256::from(digest)).unwrap();
+        assert_eq!(
+            recover_signer(&digest, &signature).unwrap(),
+            signer.address()
+        );
+    }
+}
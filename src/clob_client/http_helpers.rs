@@ -3,10 +3,12 @@ use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue},
     Client, Proxy, StatusCode,
 };
+use serde::de::DeserializeOwned;
 use serde_json::Value;
-use std::{error::Error, str::FromStr};
+use std::{error::Error, str::FromStr, time::Instant};
 
 use crate::clob_client::clob_types::BalanceAllowanceParameters;
+use crate::metrics::METRICS;
 
 pub const GET: &str = "GET";
 pub const POST: &str = "POST";
@@ -83,6 +85,31 @@ pub async fn request(
     method: &str,
     headers: Option<HeaderMap>,
     data: Option<&Value>,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let started = Instant::now();
+    let result = send_request(endpoint, method, headers, data).await;
+    let status = if result.is_ok() { "ok" } else { "error" };
+    METRICS
+        .rest_request_seconds
+        .with(&[method, endpoint_path(endpoint), status])
+        .observe_duration(started.elapsed());
+    result
+}
+
+/// Path of `endpoint` without host, query and trailing order IDs, so label values stay few.
+fn endpoint_path(endpoint: &str) -> &str {
+    let path = endpoint.split_once("://").map_or(endpoint, |(_, rest)| {
+        rest.find('/').map_or("/", |i| &rest[i..])
+    });
+    let path = path.split('?').next().unwrap_or(path);
+    path.find("/0x").map_or(path, |i| &path[..=i])
+}
+
+async fn send_request(
+    endpoint: &str,
+    method: &str,
+    headers: Option<HeaderMap>,
+    data: Option<&Value>,
 ) -> Result<Value, Box<dyn Error + Send + Sync>> {
     // Load headers with common fields
     let headers = overload_headers(method, headers);
@@ -162,6 +189,14 @@ pub async fn delete(
     request(endpoint, DELETE, headers, data).await
 }
 
+/// Deserializes a CLOB response into its typed model, keeping the raw body in the error.
+pub fn parse_response<T: DeserializeOwned>(
+    value: &Value,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    T::deserialize(value)
+        .map_err(|e| format!("Failed to parse response: {}. Raw: {}", e, value).into())
+}
+
 pub fn build_query_params(url: &str, param: &str, val: &str) -> String {
     let mut url_with_params = String::from(url);
 
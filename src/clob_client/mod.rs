@@ -1,15 +1,13 @@
+pub mod builder;
 pub mod client;
 pub mod clob_types;
 pub mod constants;
+pub mod eip712;
 pub mod headers;
 pub mod http_helpers;
 pub mod prebuilt_order;
 pub mod signer;
 
-mod builder;
-mod clob_auth;
-mod eip712;
-mod eip712struct;
 mod endpoints;
 mod hmac;
 mod utils;
@@ -1,205 +1,217 @@
-use std::str::FromStr;
+use std::{error::Error, sync::Arc};
 
+use dashmap::DashMap;
 use ethers::{
-    types::{Address, H256},
-    utils::{keccak256, to_checksum},
+    types::{Address, H256, U256},
+    utils::keccak256,
 };
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use serde_json::{json, Map, Value};
-use tiny_keccak::{Hasher, Keccak};
 
 use super::{
-    builder::{encode_order, encode_uint256, Order, OrderData, MESSAGE_PREFIX, TYPE_HASH},
-    clob_types::ApiCreds,
-    constants::ZERO_ADDRESS,
+    builder::{
+        Order, OrderAmounts, OrderBuilder, SignedOrder, MESSAGE_PREFIX, MESSAGE_PREFIX_NEG_RISK,
+        TYPE_HASH,
+    },
+    eip712::{encode_address, encode_uint256, encode_uint8},
     signer::PolySigner,
     utils::{generate_seed, prepend_zx},
 };
 
-const UTILS_BUY: u8 = 0;
-
+// Byte offsets of the fields inside `TYPE_HASH || encode_order(order)`.
+const SALT_OFFSET: usize = 32;
+const MAKER_OFFSET: usize = 64;
+const SIGNER_OFFSET: usize = 96;
+const TAKER_OFFSET: usize = 128;
+const TOKEN_ID_OFFSET: usize = 160;
+const MAKER_AMOUNT_OFFSET: usize = 192;
+const TAKER_AMOUNT_OFFSET: usize = 224;
+const EXPIRATION_OFFSET: usize = 256;
+const NONCE_OFFSET: usize = 288;
+const FEE_RATE_BPS_OFFSET: usize = 320;
+const SIDE_OFFSET: usize = 352;
+const SIGNATURE_TYPE_OFFSET: usize = 384;
+const STRUCT_LEN: usize = 416;
+
+/// Pre-encoded EIP-712 order for one asset. Only salt, amounts, side and expiration
+/// are patched per order, everything else is encoded once.
 #[derive(Debug, Clone)]
-pub struct PrebuiltOrder {
-    pub headers: HeaderMap,
-    pub body: Value,
-    pub encoded: Vec<u8>,
-    pub message: Vec<u8>,
-    pub data: Vec<u8>,
-    pub signer: PolySigner,
-    pub order: Order,
+pub struct OrderTemplate {
+    pub token_id: U256,
+    pub neg_risk: bool,
+    maker: Address,
+    signer_address: Address,
+    taker: Address,
+    nonce: U256,
+    fee_rate_bps: U256,
+    signature_type: U256,
+    signer: PolySigner,
+    encoded: [u8; STRUCT_LEN],
 }
 
-pub fn update_encoded_order(
-    encoded: &mut Vec<u8>,
-    token_id: &str,
-    maker_amount: i64,
-    taker_amount: i64,
-) {
-    // Calculate the starting positions of each field in the encoded vector
-    let token_id_start = 32 + 60; // salt (32 bytes) + maker, signer, taker addresses (3 * 20 bytes)
-    let maker_amount_start = token_id_start + 32; // token_id (32 bytes)
-    let taker_amount_start = maker_amount_start + 32; // maker_amount (32 bytes)
-
-    // Encode the new values
-    let token_id_encoded = encode_uint256(&ethers::types::U256::from_dec_str(token_id).unwrap());
-    let maker_amount_encoded = encode_uint256(&ethers::types::U256::from(maker_amount));
-    let taker_amount_encoded = encode_uint256(&ethers::types::U256::from(taker_amount));
-
-    // Replace the old values with the new encoded values
-    encoded[token_id_start..token_id_start + 32].copy_from_slice(&token_id_encoded);
-    encoded[maker_amount_start..maker_amount_start + 32].copy_from_slice(&maker_amount_encoded);
-    encoded[taker_amount_start..taker_amount_start + 32].copy_from_slice(&taker_amount_encoded);
+impl OrderTemplate {
+    pub fn new(
+        builder: &OrderBuilder,
+        token_id: &str,
+        neg_risk: bool,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let token_id = U256::from_dec_str(token_id)?;
+        let signer_address = builder.signer.address();
+        let taker = Address::zero();
+        let nonce = U256::zero();
+        let fee_rate_bps = U256::zero();
+        let signature_type = U256::from(builder.sig_type);
+
+        let mut encoded = [0u8; STRUCT_LEN];
+        encoded[..SALT_OFFSET].copy_from_slice(&TYPE_HASH[..]);
+        encoded[MAKER_OFFSET..SIGNER_OFFSET].copy_from_slice(&encode_address(&builder.funder));
+        encoded[SIGNER_OFFSET..TAKER_OFFSET].copy_from_slice(&encode_address(&signer_address));
+        encoded[TAKER_OFFSET..TOKEN_ID_OFFSET].copy_from_slice(&encode_address(&taker));
+        encoded[TOKEN_ID_OFFSET..MAKER_AMOUNT_OFFSET].copy_from_slice(&encode_uint256(&token_id));
+        encoded[NONCE_OFFSET..FEE_RATE_BPS_OFFSET].copy_from_slice(&encode_uint256(&nonce));
+        encoded[FEE_RATE_BPS_OFFSET..SIDE_OFFSET].copy_from_slice(&encode_uint256(&fee_rate_bps));
+        encoded[SIGNATURE_TYPE_OFFSET..].copy_from_slice(&encode_uint256(&signature_type));
+
+        Ok(Self {
+            token_id,
+            neg_risk,
+            maker: builder.funder,
+            signer_address,
+            taker,
+            nonce,
+            fee_rate_bps,
+            signature_type,
+            signer: builder.signer.clone(),
+            encoded,
+        })
+    }
+
+    /// Signs an order with a fresh salt.
+    pub fn sign(
+        &self,
+        amounts: &OrderAmounts,
+        expiration: u64,
+    ) -> Result<SignedOrder, Box<dyn Error + Send + Sync>> {
+        self.sign_with_salt(generate_seed(), amounts, expiration)
+    }
+
+    pub fn sign_with_salt(
+        &self,
+        salt: u64,
+        amounts: &OrderAmounts,
+        expiration: u64,
+    ) -> Result<SignedOrder, Box<dyn Error + Send + Sync>> {
+        let salt = U256::from(salt);
+        let maker_amount = U256::from(amounts.maker_amount);
+        let taker_amount = U256::from(amounts.taker_amount);
+        let expiration = U256::from(expiration);
+
+        let mut encoded = self.encoded;
+        encoded[SALT_OFFSET..MAKER_OFFSET].copy_from_slice(&encode_uint256(&salt));
+        encoded[MAKER_AMOUNT_OFFSET..TAKER_AMOUNT_OFFSET]
+            .copy_from_slice(&encode_uint256(&maker_amount));
+        encoded[TAKER_AMOUNT_OFFSET..EXPIRATION_OFFSET]
+            .copy_from_slice(&encode_uint256(&taker_amount));
+        encoded[EXPIRATION_OFFSET..NONCE_OFFSET].copy_from_slice(&encode_uint256(&expiration));
+        encoded[SIDE_OFFSET..SIGNATURE_TYPE_OFFSET].copy_from_slice(&encode_uint8(amounts.side));
+
+        let prefix = if self.neg_risk {
+            &MESSAGE_PREFIX_NEG_RISK[..]
+        } else {
+            &MESSAGE_PREFIX[..]
+        };
+        let mut message = [0u8; 66];
+        message[..34].copy_from_slice(prefix);
+        message[34..].copy_from_slice(&keccak256(encoded));
+
+        let digest = H256::from(keccak256(message));
+        let signature = prepend_zx(self.signer.sign(&digest)?);
+
+        Ok(SignedOrder {
+            order: Order {
+                salt,
+                maker: self.maker,
+                signer: self.signer_address,
+                taker: self.taker,
+                token_id: self.token_id,
+                maker_amount,
+                taker_amount,
+                expiration,
+                nonce: self.nonce,
+                fee_rate_bps: self.fee_rate_bps,
+                side: amounts.side,
+                signature_type: self.signature_type,
+            },
+            signature,
+        })
+    }
+}
+
+/// Per-asset order templates, keyed by token id.
+#[derive(Debug, Default)]
+pub struct OrderTemplateCache {
+    templates: DashMap<String, Arc<OrderTemplate>>,
 }
 
-pub fn build_prebuilt_order(
-    creds: &ApiCreds,
-    signer: &PolySigner,
-    funder: Address,
-) -> PrebuiltOrder {
-    let funder = Some(funder);
-    let signer = signer.clone();
-
-    // let (side, maker_amount, taker_amount) = self.get_order_amounts(&order_args.side, order_args.size, order_args.price, &ROUND_CONFIG.get(options.tick_size).unwrap()).unwrap();
-
-    // let order_args = OrderArgs::new(None, None, None, "BUY", None, None, None, None);
-    let mut data = OrderData {
-        maker: funder,
-        taker: ZERO_ADDRESS,
-        token_id: Some(
-            "51212617306694642074721933439542657092794027549268550682028214048094292871139",
-        ), // add later
-        maker_amount: Some(0 as usize), // add later
-        taker_amount: Some(0 as usize), // add later
-        side: Some(UTILS_BUY as u8),
-        fee_rate_bps: Some(0 as usize),
-        nonce: 0 as usize,
-        signer: Some(signer.address()),
-        expiration: 0,
-        signature_type: 1 as usize,
-    };
-
-    let order = Order {
-        salt: ethers::types::U256::from(generate_seed()),
-        maker: data.maker.unwrap(),
-        signer: data.signer.unwrap(),
-        taker: data.taker,
-        token_id: ethers::types::U256::from_dec_str(&data.token_id.unwrap()).unwrap(), // add later
-        maker_amount: ethers::types::U256::from(data.maker_amount.unwrap()),           // add later
-        taker_amount: ethers::types::U256::from(data.taker_amount.unwrap()),           // add later
-        expiration: ethers::types::U256::from(data.expiration),
-        nonce: ethers::types::U256::from(data.nonce),
-        fee_rate_bps: ethers::types::U256::from(data.fee_rate_bps.unwrap()),
-        side: data.side.unwrap(),
-        signature_type: ethers::types::U256::from(data.signature_type),
-    };
-
-    let type_hash = *TYPE_HASH;
-    let encoded_values = encode_order(&order);
-    let mut data = Vec::with_capacity(32 + encoded_values.len());
-    data.extend_from_slice(&type_hash);
-    // data.extend_from_slice(&encoded_values); -> need to do this after we get them entries correct
-    // let mut hasher = Keccak::v256();
-    // hasher.update(&data); -> need to do this after we get them entries correct
-    // let mut output = [0u8; 32]; -> need to do this after we get them entries correct
-    // hasher.finalize(&mut output); -> need to do this after we get them entries correct
-
-    let mut message = Vec::with_capacity(2 + 32 + 32);
-    message.extend_from_slice(&MESSAGE_PREFIX[..]);
-    // message.extend_from_slice(&order_struct_hash); -> order_struct_hash == output, but its not finalized yet
-
-    // let digest = keccak256(&message); -> need to do this after we get them entries correct
-
-    // let digest_h256 = H256::from_slice(&digest); -> need to do this after we get them entries correct
-
-    // let signature = prepend_zx(signer.sign(digest_h256)); -> need to do this after we get them entries correct
-
-    // Create a mutable JSON map
-    let mut order_json_map = Map::new();
-
-    let mut order_map = order.to_dict();
-
-    order_map.insert("signature", "".to_string()); // --> need to do this after we get them entries correct
-
-    let side_str = if order_map["side"] == "0" {
-        "BUY"
-    } else {
-        "SELL"
-    };
-    order_map.insert("side", side_str.to_string());
-
-    // Define the keys that need to be converted to integers
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        HeaderName::from_str("POLY_ADDRESS").unwrap(),
-        HeaderValue::from_str(&to_checksum(&signer.address(), None)).unwrap(),
-    );
-    // headers.insert(HeaderName::from_str("POLY_SIGNATURE").unwrap(), HeaderValue::from_str(&hmac_sig).unwrap()); --> need to do this after we get them entries correct
-    // headers.insert(HeaderName::from_str("POLY_TIMESTAMP").unwrap(), HeaderValue::from_str(&timestamp).unwrap()); --> need to do this after we get them entries correct
-    headers.insert(
-        HeaderName::from_str("POLY_API_KEY").unwrap(),
-        HeaderValue::from_str(&creds.api_key).unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_str("POLY_PASSPHRASE").unwrap(),
-        HeaderValue::from_str(&creds.api_pass).unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_str("User-Agent").unwrap(),
-        HeaderValue::from_str("py_clob_client").unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_str("Accept-Encoding").unwrap(),
-        HeaderValue::from_str("deflate").unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_str("Accept").unwrap(),
-        HeaderValue::from_str("*/*").unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_str("Connection").unwrap(),
-        HeaderValue::from_str("keep-alive").unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_str("Content-Type").unwrap(),
-        HeaderValue::from_str("application/json").unwrap(),
-    );
-
-    let keys_to_convert_to_int = vec!["signatureType", "salt"]; // Replace with the actual keys
-
-    for (k, v) in order_map {
-        if keys_to_convert_to_int.contains(&k) {
-            // Try to parse the value as an integer
-            if let Ok(int_value) = v.parse::<i64>() {
-                order_json_map.insert(k.to_string(), Value::Number(int_value.into()));
-            } else {
-                // If parsing fails, insert as a string
-                order_json_map.insert(k.to_string(), Value::String(v));
+impl OrderTemplateCache {
+    pub fn get_or_build(
+        &self,
+        builder: &OrderBuilder,
+        token_id: &str,
+        neg_risk: bool,
+    ) -> Result<Arc<OrderTemplate>, Box<dyn Error + Send + Sync>> {
+        if let Some(template) = self.templates.get(token_id) {
+            if template.neg_risk == neg_risk {
+                return Ok(Arc::clone(template.value()));
             }
-        } else {
-            // Insert as a string for non-integer values
-            order_json_map.insert(k.to_string(), Value::String(v));
         }
+        let template = Arc::new(OrderTemplate::new(builder, token_id, neg_risk)?);
+        self.templates
+            .insert(token_id.to_string(), Arc::clone(&template));
+        Ok(template)
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clob_client::builder::get_order_amounts;
+    use crate::clob_client::constants::POLYGON;
+    use crate::credentials::SIGNER;
+    use rust_decimal_macros::dec;
+
+    // Throwaway key, nothing is sent anywhere.
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
 
-    // Create the "order" JSON value
-    let order_value = Value::Object(order_json_map);
-
-    // Construct the final JSON object
-    let body = json!({
-        "order": order_value,
-        "owner": creds.api_key.clone(),
-        "orderType": "GTC"
-    });
-
-    let prebuilt_order = PrebuiltOrder {
-        headers: headers,
-        body: body,
-        encoded: encoded_values,
-        message: message,
-        data: data,
-        signer: signer,
-        order: order,
-    };
-
-    prebuilt_order
+    const TOKEN_ID: &str =
+        "104468181147316868388088006861839293041095272602974154655578369735976654024471";
+
+    fn builder() -> OrderBuilder {
+        OrderBuilder::new(PolySigner::new(KEY, POLYGON), Some(2), Some(*SIGNER))
+    }
+
+    #[test]
+    fn template_matches_order_builder() {
+        let builder = builder();
+        for (side, neg_risk) in [("BUY", true), ("SELL", false)] {
+            let amounts = get_order_amounts(side, dec!(5), dec!(0.943), "0.001", None).unwrap();
+            let template = OrderTemplate::new(&builder, TOKEN_ID, neg_risk).unwrap();
+            let fast = template
+                .sign_with_salt(1_260_445_392_909, &amounts, 1_700_000_000)
+                .unwrap();
+
+            let expected = builder
+                .sign_prepared_order(fast.order.clone(), neg_risk)
+                .unwrap();
+            assert_eq!(fast.signature, expected.signature);
+            assert_eq!(fast.order.maker_amount, U256::from(amounts.maker_amount));
+            assert_eq!(fast.order.taker_amount, U256::from(amounts.taker_amount));
+        }
+    }
 }
@@ -1,41 +1,405 @@
 use ethers::prelude::*;
-use ethers::signers::{LocalWallet, Signer};
+use ethers::signers::{LocalWallet, Signer as EthersSigner};
+use serde_json::{json, Value};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Env var pointing at a remote signer's Unix socket.
+pub const SIGNER_SOCKET_ENV: &str = "POLY_SIGNER_SOCKET";
+/// Env var pointing at an encrypted ethers keystore JSON file.
+pub const KEYSTORE_PATH_ENV: &str = "POLY_KEYSTORE_PATH";
+/// Env var holding the keystore passphrase.
+pub const KEYSTORE_PASSWORD_ENV: &str = "POLY_KEYSTORE_PASSWORD";
+/// Bound on every read and write to the remote signer, so a hung signer fails the order.
+pub const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Backend able to produce raw secp256k1 signatures over a 32-byte digest.
+pub trait Signer: Send + Sync + fmt::Debug {
+    fn address(&self) -> Address;
+
+    /// Returns the 65-byte `r || s || v` signature of `hash`.
+    fn sign_hash(&self, hash: &H256) -> Result<[u8; 65], Box<dyn Error + Send + Sync>>;
+}
+
+/// Private key held in process memory.
+#[derive(Clone, Debug)]
+pub struct LocalKeySigner {
+    wallet: LocalWallet,
+}
+
+impl LocalKeySigner {
+    pub fn new(private_key: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            wallet: LocalWallet::from_str(private_key)?,
+        })
+    }
+}
+
+impl From<LocalWallet> for LocalKeySigner {
+    fn from(wallet: LocalWallet) -> Self {
+        Self { wallet }
+    }
+}
+
+impl Signer for LocalKeySigner {
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    fn sign_hash(&self, hash: &H256) -> Result<[u8; 65], Box<dyn Error + Send + Sync>> {
+        Ok(self.wallet.sign_hash(*hash)?.into())
+    }
+}
+
+/// Key decrypted from an ethers keystore JSON file at startup.
+#[derive(Clone, Debug)]
+pub struct KeystoreSigner {
+    inner: LocalKeySigner,
+    path: PathBuf,
+}
+
+impl KeystoreSigner {
+    pub fn open(
+        path: impl AsRef<Path>,
+        password: &str,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let wallet = LocalWallet::decrypt_keystore(path.as_ref(), password)?;
+        Ok(Self {
+            inner: wallet.into(),
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Opens the keystore at `path`, reading the passphrase from `POLY_KEYSTORE_PASSWORD`.
+    pub fn open_with_env_password(
+        path: impl AsRef<Path>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let password = env::var(KEYSTORE_PASSWORD_ENV)
+            .map_err(|_| format!("{} is not set", KEYSTORE_PASSWORD_ENV))?;
+        Self::open(path, &password)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Signer for KeystoreSigner {
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn sign_hash(&self, hash: &H256) -> Result<[u8; 65], Box<dyn Error + Send + Sync>> {
+        self.inner.sign_hash(hash)
+    }
+}
+
+/// Signs through a separate process listening on a Unix socket.
+///
+/// The protocol is newline-delimited JSON, one request per line:
+/// `{"method":"address"}` -> `{"address":"0x.."}` and
+/// `{"method":"sign_hash","hash":"0x.."}` -> `{"signature":"0x.."}`.
+/// Failures are reported as `{"error":".."}`. Every signature is checked to recover to
+/// the address the signer reported.
+#[derive(Debug)]
+pub struct UnixSocketSigner {
+    path: PathBuf,
+    address: Address,
+    connection: Mutex<Option<BufReader<UnixStream>>>,
+}
+
+impl UnixSocketSigner {
+    pub fn connect(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let signer = Self {
+            path: path.as_ref().to_path_buf(),
+            address: Address::zero(),
+            connection: Mutex::new(None),
+        };
+        let response = signer.request(&json!({ "method": "address" }))?;
+        let address = response["address"]
+            .as_str()
+            .ok_or("remote signer response is missing `address`")?;
+        Ok(Self {
+            address: Address::from_str(address)?,
+            ..signer
+        })
+    }
+
+    fn request(&self, request: &Value) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        // Socket I/O is blocking; on a multi-threaded runtime hand the worker's other
+        // tasks off to another thread while waiting.
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| self.blocking_request(request))
+            }
+            _ => self.blocking_request(request),
+        }
+    }
+
+    fn blocking_request(&self, request: &Value) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| "remote signer connection mutex poisoned")?;
+        let result = self.round_trip(&mut connection, request);
+        if result.is_err() {
+            // Drop the stream so the next request reconnects.
+            *connection = None;
+        }
+        let response = result?;
+        if let Some(error) = response.get("error") {
+            return Err(format!("remote signer error: {}", error).into());
+        }
+        Ok(response)
+    }
+
+    fn round_trip(
+        &self,
+        connection: &mut Option<BufReader<UnixStream>>,
+        request: &Value,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        if connection.is_none() {
+            let stream = UnixStream::connect(&self.path)?;
+            stream.set_read_timeout(Some(REMOTE_SIGNER_TIMEOUT))?;
+            stream.set_write_timeout(Some(REMOTE_SIGNER_TIMEOUT))?;
+            *connection = Some(BufReader::new(stream));
+        }
+        let reader = connection.as_mut().expect("connection was just opened");
+
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        reader.get_mut().write_all(&line)?;
+
+        let mut response = String::new();
+        if reader.read_line(&mut response)? == 0 {
+            return Err("remote signer closed the connection".into());
+        }
+        Ok(serde_json::from_str(&response)?)
+    }
+}
+
+impl Signer for UnixSocketSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_hash(&self, hash: &H256) -> Result<[u8; 65], Box<dyn Error + Send + Sync>> {
+        let request = json!({
+            "method": "sign_hash",
+            "hash": format!("{:#x}", hash),
+        });
+        let response = self.request(&request)?;
+        let signature = response["signature"]
+            .as_str()
+            .ok_or("remote signer response is missing `signature`")?;
+        let bytes: [u8; 65] = hex::decode(signature.trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| "remote signer returned a signature that is not 65 bytes")?;
+        let recovered = Signature::try_from(&bytes[..])?.recover(RecoveryMessage::Hash(*hash))?;
+        if recovered != self.address {
+            return Err(format!(
+                "remote signer signature recovers to {:?}, expected {:?}",
+                recovered, self.address
+            )
+            .into());
+        }
+        Ok(bytes)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct PolySigner {
-    private_key: LocalWallet,
+    backend: Arc<dyn Signer>,
+    address: Address,
     chain_id: u128,
 }
 
 impl PolySigner {
     pub fn new(private_key: &str, chain_id: u128) -> Self {
-        let wallet = LocalWallet::from_str(private_key).expect("Invalid private key");
+        let backend = LocalKeySigner::new(private_key).expect("Invalid private key");
+        Self::from_backend(Arc::new(backend), chain_id)
+    }
 
+    pub fn from_backend(backend: Arc<dyn Signer>, chain_id: u128) -> Self {
         Self {
-            private_key: wallet,
-            chain_id: 137,
+            address: backend.address(),
+            backend,
+            chain_id,
         }
     }
 
+    /// Picks the backend from the environment: `POLY_SIGNER_SOCKET`, then `POLY_KEYSTORE_PATH`.
+    pub fn from_env(chain_id: u128) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let backend: Arc<dyn Signer> = if let Ok(socket) = env::var(SIGNER_SOCKET_ENV) {
+            log::info!("Using remote signer at {}", socket);
+            Arc::new(UnixSocketSigner::connect(socket)?)
+        } else if let Ok(path) = env::var(KEYSTORE_PATH_ENV) {
+            log::info!("Using keystore signer from {}", path);
+            Arc::new(KeystoreSigner::open_with_env_password(path)?)
+        } else {
+            return Err(format!(
+                "no signer configured: set {} or {}",
+                SIGNER_SOCKET_ENV, KEYSTORE_PATH_ENV
+            )
+            .into());
+        };
+        Ok(Self::from_backend(backend, chain_id))
+    }
+
     pub fn address(&self) -> Address {
-        self.private_key.address()
+        self.address
     }
 
     pub fn get_chain_id(&self) -> u128 {
         self.chain_id
     }
 
-    pub fn sign(&self, message_hash: &H256) -> String {
-        let signature = self
-            .private_key
-            .sign_hash(*message_hash)
-            .expect("Signing failed");
+    pub fn sign(&self, message_hash: &H256) -> Result<String, Box<dyn Error + Send + Sync>> {
+        // Encode the 65 signature bytes to a hexadecimal string
+        Ok(hex::encode(self.backend.sign_hash(message_hash)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+    use std::time::Instant;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const OTHER_KEY: &str = "0x8da4ef21b864d2cc526dbdb2a120bd2874c36c9d0a1fb7f8c63d7f7a8b41de8f";
+
+    fn socket_path(name: &str) -> PathBuf {
+        let path =
+            env::temp_dir().join(format!("poly-signer-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// Mock remote signer reporting `announced` and signing with `key`. With `answer_signs`
+    /// unset it reads sign requests but never replies.
+    fn serve_one(
+        listener: UnixListener,
+        announced: Address,
+        key: LocalKeySigner,
+        answer_signs: bool,
+    ) {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap_or(0) > 0 {
+            let request: Value = serde_json::from_str(&line).unwrap();
+            let response = match request["method"].as_str() {
+                Some("address") => json!({ "address": format!("{:#x}", announced) }),
+                Some("sign_hash") if !answer_signs => {
+                    line.clear();
+                    continue;
+                }
+                Some("sign_hash") => {
+                    let hash = H256::from_str(request["hash"].as_str().unwrap()).unwrap();
+                    let signature = key.sign_hash(&hash).unwrap();
+                    json!({ "signature": format!("0x{}", hex::encode(signature)) })
+                }
+                _ => json!({ "error": "unknown method" }),
+            };
+            let mut out = response.to_string();
+            out.push('\n');
+            if reader.get_mut().write_all(out.as_bytes()).is_err() {
+                break;
+            }
+            line.clear();
+        }
+    }
+
+    fn spawn_mock(
+        name: &str,
+        announced: Address,
+        key: LocalKeySigner,
+        answer_signs: bool,
+    ) -> (PathBuf, thread::JoinHandle<()>) {
+        let path = socket_path(name);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || serve_one(listener, announced, key, answer_signs));
+        (path, server)
+    }
+
+    #[test]
+    fn unix_socket_signer_matches_local_key() {
+        let local = LocalKeySigner::new(TEST_KEY).unwrap();
+        let (path, server) = spawn_mock("ok", local.address(), local.clone(), true);
+
+        let remote =
+            PolySigner::from_backend(Arc::new(UnixSocketSigner::connect(&path).unwrap()), 137);
+        let local = PolySigner::from_backend(Arc::new(local), 137);
+        let hash = H256::from(ethers::utils::keccak256(b"order"));
+
+        assert_eq!(remote.address(), local.address());
+        assert_eq!(remote.sign(&hash).unwrap(), local.sign(&hash).unwrap());
+
+        drop(remote);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn unix_socket_signer_rejects_signatures_from_another_key() {
+        let announced = LocalKeySigner::new(TEST_KEY).unwrap().address();
+        let other = LocalKeySigner::new(OTHER_KEY).unwrap();
+        let (path, server) = spawn_mock("other-key", announced, other, true);
+
+        let remote = UnixSocketSigner::connect(&path).unwrap();
+        let hash = H256::from(ethers::utils::keccak256(b"order"));
+        let error = remote.sign_hash(&hash).unwrap_err();
+        assert!(error.to_string().contains("recovers to"), "{}", error);
+
+        drop(remote);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn unix_socket_signer_times_out_on_a_hung_signer() {
+        let local = LocalKeySigner::new(TEST_KEY).unwrap();
+        let (path, server) = spawn_mock("hung", local.address(), local, false);
+
+        let remote = UnixSocketSigner::connect(&path).unwrap();
+        let hash = H256::from(ethers::utils::keccak256(b"order"));
+        let start = Instant::now();
+        assert!(remote.sign_hash(&hash).is_err());
+        assert!(start.elapsed() < REMOTE_SIGNER_TIMEOUT * 4);
+
+        drop(remote);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn keystore_signer_matches_local_key() {
+        let dir = env::temp_dir().join(format!("poly-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key = hex::decode(TEST_KEY.trim_start_matches("0x")).unwrap();
+        let mut rng = ethers::core::rand::thread_rng();
+        LocalWallet::encrypt_keystore(&dir, &mut rng, key, "passphrase", Some("key.json")).unwrap();
 
-        // Convert the signature into a 65-byte array
-        let sig_bytes: [u8; 65] = signature.into();
+        let keystore = KeystoreSigner::open(dir.join("key.json"), "passphrase").unwrap();
+        let local = LocalKeySigner::new(TEST_KEY).unwrap();
+        let hash = H256::from(ethers::utils::keccak256(b"order"));
+        assert_eq!(keystore.address(), local.address());
+        assert_eq!(
+            keystore.sign_hash(&hash).unwrap(),
+            local.sign_hash(&hash).unwrap()
+        );
+        assert!(KeystoreSigner::open(dir.join("key.json"), "wrong").is_err());
 
-        // Encode the signature bytes to a hexadecimal string
-        hex::encode(sig_bytes)
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
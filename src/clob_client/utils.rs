@@ -1,8 +1,9 @@
 use chrono::Utc;
 use rand::Rng;
-use serde_json::{json, Map, Value};
+use serde_json::Value;
 
 use super::builder::SignedOrder;
+use super::clob_types::PostOrderRequest;
 
 pub fn prepend_zx(mut in_str: String) -> String {
     /*
@@ -31,37 +32,10 @@ pub fn generate_seed() -> u64 {
 }
 
 pub fn order_to_json(order: &SignedOrder, owner: &str, order_type: &str) -> Value {
-    // Get the order as a HashMap<String, String>
-    let order_dict = order.to_dict();
-
-    // Create a mutable JSON map
-    let mut order_json_map = Map::new();
-
-    // Define the keys that need to be converted to integers
-    let keys_to_convert_to_int = vec!["signatureType", "salt"]; // Replace with the actual keys
-
-    for (k, v) in order_dict {
-        if keys_to_convert_to_int.contains(&k) {
-            // Try to parse the value as an integer
-            if let Ok(int_value) = v.parse::<i64>() {
-                order_json_map.insert(k.to_string(), Value::Number(int_value.into()));
-            } else {
-                // If parsing fails, insert as a string
-                order_json_map.insert(k.to_string(), Value::String(v));
-            }
-        } else {
-            // Insert as a string for non-integer values
-            order_json_map.insert(k.to_string(), Value::String(v));
-        }
-    }
-
-    // Create the "order" JSON value
-    let order_value = Value::Object(order_json_map);
-
-    // Construct the final JSON object
-    json!({
-        "order": order_value,
-        "owner": owner,
-        "orderType": order_type
-    })
+    let request = PostOrderRequest {
+        order: order.to_payload(),
+        owner,
+        order_type,
+    };
+    serde_json::to_value(request).expect("order payload is always serializable")
 }
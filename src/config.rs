@@ -1 +1,15 @@
+use crate::exchange_listeners::Crypto;
+
 pub const RATE_LIMIT_WAIT_TIME: u32 = 200; // 5 requests per second
+pub const TRACKED_CRYPTOS: &[Crypto] = &[Crypto::BTC]; // underlyings with price listeners
+pub const VARIANCE_PROFILE_PATH: &str = "daily_half_hourly_variance_profiles_1m.json"; // intraday seasonality
+pub const VARIANCE_PROFILE_STATE_PATH: &str = "data/variance_profile_live.json"; // online updates of the profile, preferred at startup
+pub const EVENT_SHARDS: usize = 4; // market-data workers of the event processor
+pub const EVENT_QUEUE_CAPACITY: usize = 10_000; // per lane, beyond which top-of-book prices are dropped
+pub const EVENT_LAG_THRESHOLD_MS: u64 = 250; // queueing delay that pauses order placement
+pub const METRICS_ADDR: &str = "127.0.0.1:9184"; // Prometheus scrape endpoint
+pub const CONTROL_ADDR: &str = "127.0.0.1:9185"; // control API, local only
+pub const STRATEGY_HANDLER_BUDGET_US: u64 = 500; // per strategy callback, flagged when exceeded
+pub const SHUTDOWN_TIMEOUT_MS: u64 = 5_000; // for cancels to be confirmed and queued events handled
+pub const MARKET_DISCOVERY_INTERVAL_SECS: u64 = 300; // how often daily, above and range crypto markets are rediscovered
+pub const LAUNCH_CONFIG_POLL_MS: u64 = 2_000; // how often --config is checked for changed strategy params
@@ -0,0 +1,573 @@
+//! Local HTTP/JSON API to inspect and steer the running bot.
+//!
+//! - `GET  /strategies`: strategies with their params, pause state and open orders
+//! - `POST /strategies/{name}/pause`, `POST /strategies/{name}/resume`
+//! - `PUT  /strategies/{name}/params`: replace params with the JSON body
+//! - `POST /strategies/{name}/cancel`, `POST /assets/{asset_id}/cancel`: cancel open orders
+//! - `POST /assets/{asset_id}/flatten`: cancel the asset's orders and sell its position
+//! - `POST /kill-switch`: stop strategies from placing orders and cancel every open order
+//! - `GET  /state[?asset={asset_id}]`: books, orders, positions and trading flags
+//!
+//! Every request must carry `Authorization: Bearer <token>` with the token from
+//! `POLY_CONTROL_TOKEN`; without it the API is not served. A paused strategy keeps
+//! getting events but may not place orders.
+pub mod server;
+
+use log::{error, info, warn};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::exchange_listeners::poly_client::PolyClient;
+use crate::exchange_listeners::poly_models::{OpenOrder, OrderSide};
+use crate::exchange_listeners::states::PolyMarketState;
+use crate::strategies::{registry, Strategy};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+pub struct ControlPlane {
+    poly_state: Arc<PolyMarketState>,
+    strategies: Vec<Arc<dyn Strategy>>,
+}
+
+impl ControlPlane {
+    pub fn new(poly_state: Arc<PolyMarketState>, strategies: Vec<Arc<dyn Strategy>>) -> Self {
+        Self {
+            poly_state,
+            strategies,
+        }
+    }
+
+    pub async fn handle(&self, method: &str, target: &str, body: &[u8]) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            ("GET", ["strategies"]) => self.list_strategies(),
+            ("POST", ["strategies", name, action]) => {
+                let Some(strategy) = self.strategy(name) else {
+                    return Response::error(404, format!("no running strategy '{}'", name));
+                };
+                match *action {
+                    "pause" => self.pause(strategy),
+                    "resume" => self.resume(strategy),
+                    "cancel" => {
+                        let name = strategy.name();
+                        self.cancel(move |_, order| order.strategy() == Some(name))
+                            .await
+                    }
+                    _ => Response::error(404, "not found"),
+                }
+            }
+            ("PUT", ["strategies", name, "params"]) => match self.strategy(name) {
+                Some(strategy) => Self::set_params(strategy, body),
+                None => Response::error(404, format!("no running strategy '{}'", name)),
+            },
+            ("POST", ["assets", asset_id, "cancel"]) => {
+                let asset_id = asset_id.to_string();
+                self.cancel(move |asset, _| asset == asset_id).await
+            }
+            ("POST", ["assets", asset_id, "flatten"]) => self.flatten(asset_id).await,
+            ("POST", ["kill-switch"]) => self.trip_kill_switch().await,
+            ("GET", ["state"]) => {
+                let asset = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("asset="));
+                Response::ok(self.state(asset))
+            }
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    fn strategy(&self, name: &str) -> Option<&dyn Strategy> {
+        self.strategies
+            .iter()
+            .map(|strategy| strategy.as_ref())
+            .find(|strategy| strategy.name() == name)
+    }
+
+    fn list_strategies(&self) -> Response {
+        let strategies: Vec<Value> = self
+            .strategies
+            .iter()
+            .map(|strategy| {
+                let name = strategy.name();
+                json!({
+                    "name": name,
+                    "core": is_core(name),
+                    "paused": self.poly_state.is_strategy_paused(name),
+                    "open_orders": self.open_orders_of(name),
+                    "params": strategy.params(),
+                })
+            })
+            .collect();
+        Response::ok(Value::Array(strategies))
+    }
+
+    fn open_orders_of(&self, strategy: &str) -> usize {
+        self.poly_state
+            .open_orders
+            .iter()
+            .flat_map(|orders| {
+                let bids: Vec<_> = orders.bids.iter().map(|e| Arc::clone(e.value())).collect();
+                let asks: Vec<_> = orders.asks.iter().map(|e| Arc::clone(e.value())).collect();
+                bids.into_iter().chain(asks)
+            })
+            .filter(|order| {
+                order
+                    .lock()
+                    .map(|order| order.strategy() == Some(strategy))
+                    .unwrap_or(false)
+            })
+            .count()
+    }
+
+    fn pause(&self, strategy: &dyn Strategy) -> Response {
+        let name = strategy.name();
+        if is_core(name) {
+            return Response::error(
+                409,
+                format!("{} places no orders and cannot be paused", name),
+            );
+        }
+        if self.poly_state.pause_strategy(name) {
+            warn!("[Control] Paused {}", name);
+        }
+        Response::ok(json!({ "name": name, "paused": true }))
+    }
+
+    fn resume(&self, strategy: &dyn Strategy) -> Response {
+        let name = strategy.name();
+        if self.poly_state.resume_strategy(name) {
+            info!("[Control] Resumed {}", name);
+        }
+        Response::ok(json!({ "name": name, "paused": false }))
+    }
+
+    fn set_params(strategy: &dyn Strategy, body: &[u8]) -> Response {
+        let params: Value = match serde_json::from_slice(body) {
+            Ok(params) => params,
+            Err(e) => return Response::error(400, format!("body is not JSON: {}", e)),
+        };
+        match strategy.reload_params(params) {
+            Ok(()) => {
+                info!(
+                    "[Control] Set params of {}: {}",
+                    strategy.name(),
+                    strategy.params()
+                );
+                Response::ok(json!({ "name": strategy.name(), "params": strategy.params() }))
+            }
+            Err(e) => Response::error(400, format!("{:#}", e)),
+        }
+    }
+
+    async fn cancel(&self, matches: impl Fn(&str, &OpenOrder) -> bool) -> Response {
+        let result =
+            PolyClient::cancel_matching_orders(Arc::clone(&self.poly_state), matches).await;
+        match cancel_outcome(result) {
+            Ok(sent) => Response::ok(json!({ "cancels_sent": sent })),
+            Err(response) => response,
+        }
+    }
+
+    /// Cancels the orders of `asset_id` and sells the whole position at the best bid.
+    async fn flatten(&self, asset_id: &str) -> Response {
+        let position = self
+            .poly_state
+            .positions
+            .get(asset_id)
+            .and_then(|position| position.read().ok().map(|position| position.size))
+            .unwrap_or(0);
+        if position == 0 {
+            return Response::error(409, format!("no position in {}", asset_id));
+        }
+        let owned = asset_id.to_string();
+        let result =
+            PolyClient::cancel_matching_orders(Arc::clone(&self.poly_state), move |asset, _| {
+                asset == owned
+            })
+            .await;
+        if let Err(response) = cancel_outcome(result) {
+            return response;
+        }
+
+        let book = self
+            .poly_state
+            .orderbooks
+            .get(asset_id)
+            .map(|book| Arc::clone(book.value()));
+        let Some((bid, tick_size)) = book.and_then(|book| {
+            let book = book.read().ok()?;
+            Some((book.best_bid()?.0, book.get_tick_size().to_string()))
+        }) else {
+            return Response::error(409, format!("no bid to sell {} into", asset_id));
+        };
+        let neg_risk = self
+            .poly_state
+            .markets
+            .get(asset_id)
+            .and_then(|market| market.negRisk)
+            .unwrap_or(false);
+        match PolyClient::place_limit_order(
+            Arc::clone(&self.poly_state),
+            None,
+            asset_id,
+            OrderSide::Sell,
+            bid,
+            position,
+            &tick_size,
+            neg_risk,
+        ) {
+            Ok(()) => {
+                warn!(
+                    "[Control] Flattening {}: selling {} at {}",
+                    asset_id,
+                    position as f64 / 1000.0,
+                    bid as f64 / 1000.0
+                );
+                Response::ok(json!({
+                    "asset_id": asset_id,
+                    "side": OrderSide::Sell.as_str(),
+                    "price": bid as f64 / 1000.0,
+                    "size": position as f64 / 1000.0,
+                }))
+            }
+            Err(e) => Response::error(409, format!("sell rejected: {}", e)),
+        }
+    }
+
+    async fn trip_kill_switch(&self) -> Response {
+        if self.poly_state.trip_kill_switch() {
+            error!("[Control] Kill switch tripped; strategies may no longer place orders");
+        }
+        match cancel_outcome(PolyClient::cancel_open_orders(Arc::clone(&self.poly_state)).await) {
+            Ok(sent) => Response::ok(json!({ "kill_switch": true, "cancels_sent": sent })),
+            Err(mut response) => {
+                response.body["kill_switch"] = json!(true);
+                response
+            }
+        }
+    }
+
+    /// Books, open orders and positions, of `asset` only if given, with the flags that
+    /// gate order placement. Prices are in dollars and sizes in shares.
+    fn state(&self, asset: Option<&str>) -> Value {
+        let state = &self.poly_state;
+        let wanted = |asset_id: &str| asset.is_none_or(|asset| asset == asset_id);
+        let levels = |mut levels: Vec<(u32, u32)>, descending: bool| -> Value {
+            levels.sort_unstable();
+            if descending {
+                levels.reverse();
+            }
+            levels
+                .into_iter()
+                .map(|(price, size)| json!([price as f64 / 1000.0, size as f64 / 1000.0]))
+                .collect()
+        };
+
+        let mut books = Map::new();
+        for entry in state.orderbooks.iter().filter(|e| wanted(e.key())) {
+            let Ok(book) = entry.value().read() else {
+                continue;
+            };
+            let snapshot = book.snapshot();
+            books.insert(
+                entry.key().clone(),
+                json!({
+                    "tick_size": snapshot.tick_size,
+                    "timestamp": snapshot.timestamp,
+                    "stale": state.is_book_stale(entry.key()),
+                    "bids": levels(snapshot.bids, true),
+                    "asks": levels(snapshot.asks, false),
+                }),
+            );
+        }
+
+        let mut orders = Map::new();
+        for entry in state.open_orders.iter().filter(|e| wanted(e.key())) {
+            let sides = [
+                (OrderSide::Buy, &entry.bids),
+                (OrderSide::Sell, &entry.asks),
+            ];
+            let mut asset_orders = Vec::new();
+            for (side, book) in sides {
+                for order in book.iter() {
+                    let Ok(order) = order.value().lock() else {
+                        continue;
+                    };
+                    asset_orders.push(json!({
+                        "id": order.id(),
+                        "side": side.as_str(),
+                        "price": order.price() as f64 / 1000.0,
+                        "size": order.size() as f64 / 1000.0,
+                        "size_filled": order.size_filled() as f64 / 1000.0,
+                        "state": format!("{:?}", order.state()),
+                        "strategy": order.strategy(),
+                    }));
+                }
+            }
+            orders.insert(entry.key().clone(), Value::Array(asset_orders));
+        }
+
+        let mut positions = Map::new();
+        for entry in state.positions.iter().filter(|e| wanted(e.key())) {
+            if let Ok(position) = entry.value().read() {
+                positions.insert(entry.key().clone(), json!(position.size as f64 / 1000.0));
+            }
+        }
+
+        let lagging_lanes: Map<String, Value> = state
+            .lagging_lanes
+            .iter()
+            .map(|e| (e.key().clone(), json!(e.value().as_millis() as u64)))
+            .collect();
+        let paused: Vec<&str> = state.paused_strategies.iter().map(|name| *name).collect();
+        json!({
+            "books": books,
+            "orders": orders,
+            "positions": positions,
+            "paused_strategies": paused,
+            "lagging_lanes_ms": lagging_lanes,
+            "kill_switch": state.is_kill_switch_tripped(),
+            "shutting_down": state.is_shutting_down(),
+            "paper_trading": state.paper_trading,
+        })
+    }
+}
+
+/// Cancels sent, or a 502 if the request failed or the exchange did not cancel every
+/// order, so a partial cancel is never reported as done.
+fn cancel_outcome(result: Result<usize, Box<dyn Error + Send + Sync>>) -> Result<usize, Response> {
+    result.map_err(|e| Response::error(502, format!("cancel failed: {}", e)))
+}
+
+fn is_core(name: &str) -> bool {
+    registry::find(name).is_some_and(|entry| entry.core)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::orderbooks::poly_orderbook::OrderBook;
+    use crate::exchange_listeners::poly_client::partial_cancel_error;
+    use crate::exchange_listeners::poly_models::{AggOrderbook, OrderbookEntry, Position};
+    use crate::strategies::params::{LiveParams, StrategyParams};
+    use anyhow::{ensure, Result};
+    use serde::{Deserialize, Serialize};
+    use std::sync::RwLock;
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct Spread {
+        max_spread: f64,
+    }
+
+    impl StrategyParams for Spread {
+        fn validate(&self) -> Result<()> {
+            ensure!(self.max_spread >= 0.0, "max_spread must not be negative");
+            Ok(())
+        }
+    }
+
+    struct Quoter(LiveParams<Spread>);
+
+    impl Strategy for Quoter {
+        fn name(&self) -> &'static str {
+            "Quoter"
+        }
+
+        fn params(&self) -> Value {
+            self.0.to_value()
+        }
+
+        fn reload_params(&self, params: Value) -> Result<()> {
+            self.0.reload(params)
+        }
+    }
+
+    fn plane(poly_state: &Arc<PolyMarketState>) -> ControlPlane {
+        ControlPlane::new(
+            Arc::clone(poly_state),
+            vec![Arc::new(Quoter(LiveParams::new(Spread::default())))],
+        )
+    }
+
+    /// Paper state, so orders are placed and canceled without an exchange.
+    fn paper_state() -> Arc<PolyMarketState> {
+        Arc::new(PolyMarketState {
+            paper_trading: true,
+            ..Default::default()
+        })
+    }
+
+    fn place(
+        poly_state: &Arc<PolyMarketState>,
+        strategy: Option<&'static str>,
+        asset_id: &str,
+        price: u32,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        PolyClient::place_limit_order(
+            Arc::clone(poly_state),
+            strategy,
+            asset_id,
+            OrderSide::Buy,
+            price,
+            10_000,
+            "0.01",
+            false,
+        )
+    }
+
+    #[tokio::test]
+    async fn pauses_strategies_and_sets_their_params() {
+        let poly_state = Arc::new(PolyMarketState::default());
+        let plane = plane(&poly_state);
+
+        let paused = plane.handle("POST", "/strategies/Quoter/pause", b"").await;
+        assert_eq!(paused.status, 200);
+        assert!(poly_state.is_strategy_paused("Quoter"));
+        assert_eq!(
+            plane
+                .handle("POST", "/strategies/Missing/pause", b"")
+                .await
+                .status,
+            404
+        );
+
+        let set = plane
+            .handle(
+                "PUT",
+                "/strategies/Quoter/params",
+                br#"{"max_spread": 0.02}"#,
+            )
+            .await;
+        assert_eq!(set.body["params"]["max_spread"], 0.02);
+        let rejected = plane
+            .handle("PUT", "/strategies/Quoter/params", br#"{"max_spread": -1}"#)
+            .await;
+        assert_eq!(rejected.status, 400);
+
+        let listed = plane.handle("GET", "/strategies", b"").await;
+        assert_eq!(listed.body[0]["paused"], true);
+        assert_eq!(listed.body[0]["params"]["max_spread"], 0.02);
+
+        plane.handle("POST", "/strategies/Quoter/resume", b"").await;
+        let state = plane.handle("GET", "/state?asset=1", b"").await;
+        assert_eq!(state.body["paused_strategies"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn rejects_the_orders_of_a_paused_strategy() {
+        let poly_state = paper_state();
+        let plane = plane(&poly_state);
+        plane.handle("POST", "/strategies/Quoter/pause", b"").await;
+
+        let refused = place(&poly_state, Some("Quoter"), "1", 400).unwrap_err();
+        assert_eq!(refused.to_string(), "Quoter is paused");
+        // The operator is not held back by a paused strategy.
+        place(&poly_state, None, "1", 400).unwrap();
+
+        plane.handle("POST", "/strategies/Quoter/resume", b"").await;
+        place(&poly_state, Some("Quoter"), "1", 410).unwrap();
+        assert_eq!(poly_state.open_order_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn cancels_the_orders_of_one_strategy() {
+        let poly_state = paper_state();
+        let plane = plane(&poly_state);
+        place(&poly_state, Some("Quoter"), "1", 400).unwrap();
+        place(&poly_state, Some("Quoter"), "2", 400).unwrap();
+        place(&poly_state, None, "1", 410).unwrap();
+
+        let canceled = plane.handle("POST", "/strategies/Quoter/cancel", b"").await;
+        assert_eq!(canceled.status, 200);
+        assert_eq!(canceled.body["cancels_sent"], 2);
+        assert_eq!(plane.open_orders_of("Quoter"), 0);
+        assert_eq!(poly_state.open_order_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn flattens_a_position_into_the_best_bid() {
+        let poly_state = paper_state();
+        let plane = plane(&poly_state);
+        let book = AggOrderbook {
+            asset_id: "1".to_string(),
+            bids: vec![OrderbookEntry {
+                price: "0.45".to_string(),
+                size: "100".to_string(),
+            }],
+            asks: Vec::new(),
+            timestamp: "0".to_string(),
+            hash: String::new(),
+        };
+        poly_state.orderbooks.insert(
+            "1".to_string(),
+            Arc::new(RwLock::new(OrderBook::new(&book, "0.01".to_string()))),
+        );
+        poly_state.positions.insert(
+            "1".to_string(),
+            Arc::new(RwLock::new(Position::new("1", 5_000))),
+        );
+        place(&poly_state, Some("Quoter"), "1", 400).unwrap();
+
+        let flattened = plane.handle("POST", "/assets/1/flatten", b"").await;
+        assert_eq!(flattened.status, 200);
+        assert_eq!(flattened.body["price"], 0.45);
+        assert_eq!(flattened.body["size"], 5.0);
+        let orders = poly_state.open_orders.get("1").unwrap();
+        assert!(orders.bids.is_empty());
+        assert!(orders.asks.contains_key(&(450, 5_000)));
+        drop(orders);
+
+        let flat = plane.handle("POST", "/assets/2/flatten", b"").await;
+        assert_eq!(flat.status, 409);
+    }
+
+    #[tokio::test]
+    async fn kill_switch_stops_strategies_and_cancels_every_order() {
+        let poly_state = paper_state();
+        let plane = plane(&poly_state);
+        place(&poly_state, Some("Quoter"), "1", 400).unwrap();
+        place(&poly_state, None, "2", 400).unwrap();
+
+        let killed = plane.handle("POST", "/kill-switch", b"").await;
+        assert_eq!(killed.status, 200);
+        assert_eq!(killed.body["cancels_sent"], 2);
+        assert_eq!(poly_state.open_order_count(), 0);
+        assert!(place(&poly_state, Some("Quoter"), "1", 400).is_err());
+        let state = plane.handle("GET", "/state", b"").await;
+        assert_eq!(state.body["kill_switch"], true);
+    }
+
+    #[test]
+    fn partial_cancels_are_reported_as_failures() {
+        let not_canceled = vec![("0xb".to_string(), "order already matched".to_string())];
+        let partial = partial_cancel_error(&not_canceled, 2);
+
+        let response = cancel_outcome(Err(partial)).unwrap_err();
+        assert_eq!(response.status, 502);
+        assert_eq!(
+            response.body["error"],
+            "cancel failed: 1 of 2 orders not canceled (0xb: order already matched)"
+        );
+        assert_eq!(cancel_outcome(Ok(3)), Ok(3));
+    }
+}
@@ -0,0 +1,218 @@
+use log::{error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+use super::{ControlPlane, Response};
+
+/// Bearer token every control request must carry; the API is not served without it.
+pub const CONTROL_TOKEN_ENV: &str = "POLY_CONTROL_TOKEN";
+
+/// Largest request accepted, head and body; params are a small JSON object.
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+/// For a request to arrive in full, so a stalled client cannot hold a connection open.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves the control API on `addr` until the process exits, to requests carrying
+/// `Authorization: Bearer <token>`.
+pub async fn serve_control(addr: &'static str, token: String, plane: Arc<ControlPlane>) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("[Control] Failed to bind {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("[Control] Serving http://{}", addr);
+
+    let token: Arc<str> = token.into();
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("[Control] Accept failed: {}", e);
+                continue;
+            }
+        };
+        let plane = Arc::clone(&plane);
+        let token = Arc::clone(&token);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &token, &plane, READ_TIMEOUT).await {
+                warn!("[Control] Request failed: {}", e);
+            }
+        });
+    }
+}
+
+/// What came in on a connection.
+enum Incoming {
+    Request { head: String, body: Vec<u8> },
+    Rejected(Response),
+    Closed,
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    token: &str,
+    plane: &ControlPlane,
+    read_timeout: Duration,
+) -> std::io::Result<()> {
+    let (head, body) = match timeout(read_timeout, read_request(&mut stream)).await {
+        Ok(Ok(Incoming::Request { head, body })) => (head, body),
+        Ok(Ok(Incoming::Rejected(response))) => {
+            return write_response(&mut stream, &response).await
+        }
+        Ok(Ok(Incoming::Closed)) => return Ok(()),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            return write_response(&mut stream, &Response::error(408, "request timed out")).await
+        }
+    };
+
+    if !is_authorized(&head, token) {
+        warn!("[Control] Rejected a request without a valid token");
+        let response = Response::error(401, "missing or invalid bearer token");
+        return write_response(&mut stream, &response).await;
+    }
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let response = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(target)) => plane.handle(method, target, &body).await,
+        _ => Response::error(400, "malformed request line"),
+    };
+    write_response(&mut stream, &response).await
+}
+
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Incoming> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            return Ok(Incoming::Closed);
+        }
+        request.extend_from_slice(&buf[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+    let content_length = header(&head, "content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if head_end + content_length > MAX_REQUEST_BYTES {
+        return Ok(Incoming::Rejected(Response::error(
+            413,
+            "request too large",
+        )));
+    }
+    while request.len() < head_end + content_length {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(Incoming::Closed);
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    let body = request[head_end..head_end + content_length].to_vec();
+    Ok(Incoming::Request { head, body })
+}
+
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Whether `head` carries `Authorization: Bearer <token>`, compared in constant time.
+fn is_authorized(head: &str, token: &str) -> bool {
+    let Some(given) = header(head, "authorization").and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn write_response(stream: &mut TcpStream, response: &Response) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        502 => "Bad Gateway",
+        _ => "",
+    };
+    let mut body = response.body.to_string();
+    body.push('\n');
+    let message = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(message.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::states::PolyMarketState;
+
+    /// Sends `request` to a connection handled with token `secret` and returns the
+    /// status line of the response.
+    async fn exchange(request: &'static [u8], read_timeout: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let plane = ControlPlane::new(Arc::new(PolyMarketState::default()), Vec::new());
+            handle_connection(stream, "secret", &plane, read_timeout).await
+        });
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(request).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        server.await.unwrap().unwrap();
+        response.lines().next().unwrap_or("").to_string()
+    }
+
+    #[tokio::test]
+    async fn requires_the_bearer_token() {
+        let authorized = b"GET /state HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n";
+        assert_eq!(exchange(authorized, READ_TIMEOUT).await, "HTTP/1.1 200 OK");
+        let wrong = b"POST /kill-switch HTTP/1.1\r\nAuthorization: Bearer secreT\r\n\r\n";
+        assert_eq!(
+            exchange(wrong, READ_TIMEOUT).await,
+            "HTTP/1.1 401 Unauthorized"
+        );
+        let missing = b"POST /kill-switch HTTP/1.1\r\n\r\n";
+        assert_eq!(
+            exchange(missing, READ_TIMEOUT).await,
+            "HTTP/1.1 401 Unauthorized"
+        );
+    }
+
+    #[tokio::test]
+    async fn times_out_incomplete_requests() {
+        // The body announced by Content-Length never arrives.
+        let stalled =
+            b"PUT /strategies/Quoter/params HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 10\r\n\r\n{";
+        assert_eq!(
+            exchange(stalled, Duration::from_millis(50)).await,
+            "HTTP/1.1 408 Request Timeout"
+        );
+    }
+}
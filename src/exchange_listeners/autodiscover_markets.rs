@@ -1,39 +1,93 @@
 use anyhow::{anyhow, Context, Result};
-use chrono::{Datelike, Duration, Timelike};
-use chrono_tz::US::Eastern;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::{Tz, US::Eastern};
 use log::{error, info};
 use reqwest::Client;
 use serde_json::Value;
+use std::sync::Arc;
+
+use crate::exchange_listeners::Crypto;
+use crate::marketmaking::poly_market_struct::Market;
+
+/// How a binary crypto market resolves relative to the underlying price at expiry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarketKind {
+    /// YES ("Up") if the close is at or above `strike_price`, the open of the period.
+    UpOrDown,
+    /// YES if the price at expiry is above `strike_price`.
+    Above,
+    /// YES if the price at expiry lies in `[lower, upper)`; a missing bound is open-ended.
+    Range {
+        lower: Option<f64>,
+        upper: Option<f64>,
+    },
+    /// YES if the price reaches `strike_price` before expiry: rising to it when `up`,
+    /// falling to it otherwise.
+    Hit { up: bool },
+}
 
 /// Configuration describing the discovered Polymarket market.
 #[derive(Debug, Clone)]
 pub struct MarketConfig {
     pub name: String,
+    pub crypto: Crypto,
+    pub kind: MarketKind,
     pub yes_token_id: String,
     pub no_token_id: String,
     pub end_time_et: String,
     pub binance_symbol: String,
+    /// Resolution threshold; `0.0` while an up-or-down period has not opened yet and for ranges.
     pub strike_price: f64,
+    /// Gamma metadata of the market (tick size, minimum order size), if it parsed.
+    pub metadata: Option<Arc<Market>>,
 }
 
-/// Attempts to discover the active hourly `{CRYPTO}/USD` market and accompanying metadata.
-pub async fn autodiscover_market_config(
-    auto_market: &str,
-    crypto: &str,
-) -> Result<Option<MarketConfig>> {
-    info!("--- Autodiscovering current crypto market ---");
+impl MarketConfig {
+    /// `end_time_et` as a UTC timestamp.
+    pub fn end_time_utc(&self) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(&self.end_time_et, "%Y-%m-%d %H:%M:%S").ok()?;
+        Eastern
+            .from_local_datetime(&naive)
+            .single()
+            .map(|end| end.with_timezone(&Utc))
+    }
 
-    let client = Client::new();
+    /// Metadata of the YES and NO tokens, keyed by token id as in `PolyMarketState::markets`.
+    pub fn token_metadata(&self) -> Vec<(String, Arc<Market>)> {
+        let Some(market) = &self.metadata else {
+            return Vec::new();
+        };
+        [(&self.yes_token_id, true), (&self.no_token_id, false)]
+            .into_iter()
+            .map(|(token_id, is_yes)| {
+                let mut side = Market::clone(market);
+                side.is_yes_market = Some(is_yes);
+                (token_id.clone(), Arc::new(side))
+            })
+            .collect()
+    }
+}
+
+/// Up/Down token IDs of an hourly event and the gamma market listing them.
+#[derive(Debug, Clone)]
+pub struct UpDownTokens {
+    pub yes_token_id: String,
+    pub no_token_id: String,
+    pub metadata: Option<Arc<Market>>,
+}
 
-    // 1. Determine the market slug from the current time (US/Eastern).
-    let now_et = chrono::Utc::now().with_timezone(&Eastern);
-    let start_hour_et = now_et
+/// Start of the US/Eastern hour containing `at`.
+pub fn hour_start_et(at: DateTime<Utc>) -> Result<DateTime<Tz>> {
+    at.with_timezone(&Eastern)
         .with_minute(0)
         .and_then(|dt| dt.with_second(0))
         .and_then(|dt| dt.with_nanosecond(0))
-        .ok_or_else(|| anyhow!("failed to normalize Eastern time to start of hour"))?;
-    let end_hour_et = start_hour_et + Duration::hours(1);
+        .ok_or_else(|| anyhow!("failed to normalize Eastern time to start of hour"))
+}
 
+/// Slug of the hourly up-or-down event starting at `start_hour_et`,
+/// e.g. `bitcoin-up-or-down-october-17-3pm-et`.
+pub fn up_or_down_slug(auto_market: &str, start_hour_et: DateTime<Tz>) -> String {
     let hour_str = start_hour_et.format("%I%p").to_string();
     let hour_str = hour_str.trim_start_matches('0').to_lowercase();
     let raw_day = start_hour_et.format("%d").to_string();
@@ -45,35 +99,132 @@ pub async fn autodiscover_market_config(
     }
     .to_string();
 
-    let market_slug = start_hour_et
+    start_hour_et
         .format(&format!(
             "{}-up-or-down-%B-{}-{}-et",
             auto_market, day_str, hour_str
         ))
         .to_string()
         .to_lowercase()
-        .replace(' ', "-");
+        .replace(' ', "-")
+}
+
+/// Attempts to discover the active hourly `{CRYPTO}/USD` market and accompanying metadata.
+pub async fn autodiscover_market_config(
+    auto_market: &str,
+    crypto: &str,
+) -> Result<Option<MarketConfig>> {
+    let start_hour_et = hour_start_et(chrono::Utc::now())?;
+    autodiscover_market_config_at(auto_market, crypto, start_hour_et).await
+}
+
+/// Discovers the hourly market starting at `start_hour_et`. Its strike is only known once
+/// the hour has started.
+pub async fn autodiscover_market_config_at(
+    auto_market: &str,
+    crypto: &str,
+    start_hour_et: DateTime<Tz>,
+) -> Result<Option<MarketConfig>> {
+    info!("--- Autodiscovering current crypto market ---");
+
+    let client = Client::new();
+    let underlying: Crypto = crypto.parse().map_err(|e: String| anyhow!(e))?;
+
+    // 1. Determine the market slug from the start hour (US/Eastern).
+    let market_slug = up_or_down_slug(auto_market, start_hour_et);
     info!("--> Target market slug: {}", market_slug);
 
     // 2. Fetch markets from Polymarket to find token IDs.
+    let tokens = match discover_up_down_tokens(&client, &market_slug).await? {
+        Some(tokens) => tokens,
+        None => return Ok(None),
+    };
+
+    // 3. Fetch the strike price (open price of the 1H candle) from Binance.
+    let binance_symbol = format!("{}USDT", crypto.to_uppercase());
+    let strike_price =
+        fetch_hourly_open(&client, &binance_symbol, start_hour_et.timestamp_millis()).await?;
+
+    let end_hour_et = start_hour_et + Duration::hours(1);
+    let config = MarketConfig {
+        name: market_slug.clone(),
+        crypto: underlying,
+        kind: MarketKind::UpOrDown,
+        yes_token_id: tokens.yes_token_id,
+        no_token_id: tokens.no_token_id,
+        end_time_et: end_hour_et.format("%Y-%m-%d %H:%M:%S").to_string(),
+        binance_symbol,
+        strike_price,
+        metadata: tokens.metadata,
+    };
+
+    info!("--- Market autodiscovery successful ---");
+    Ok(Some(config))
+}
+
+pub(crate) const EVENTS_PAGE_SIZE: usize = 100;
+
+/// One page of active gamma events, most traded first. `filter` is appended to the query
+/// string, e.g. `&tag_slug=crypto`.
+pub(crate) async fn fetch_events_page(
+    client: &Client,
+    offset: usize,
+    filter: &str,
+) -> Result<Vec<Value>> {
+    let url = format!("https://gamma-api.polymarket.com/events?limit={}&active=true&archived=false&closed=false&order=volume24hr&ascending=false&offset={}{}", EVENTS_PAGE_SIZE, offset, filter);
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("failed to fetch events from {}", url))?;
+    let events: Value = response
+        .json()
+        .await
+        .context("failed to parse events response JSON")?;
+    match events {
+        Value::Array(events) => Ok(events),
+        _ => Err(anyhow!("events response is not an array")),
+    }
+}
+
+/// `(outcome, token_id)` pairs of a gamma market, or `None` if it lists no tokens.
+pub(crate) fn outcome_tokens(market: &Value) -> Result<Option<Vec<(String, String)>>> {
+    let token_ids: Vec<String> = match market.get("clobTokenIds") {
+        Some(Value::String(s)) => {
+            serde_json::from_str(s).context("failed to parse clobTokenIds JSON string")?
+        }
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        _ => return Ok(None),
+    };
+
+    let outcomes: Vec<String> = match market.get("outcomes") {
+        Some(Value::String(s)) => {
+            serde_json::from_str(s).context("failed to parse outcomes JSON string")?
+        }
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(outcomes.into_iter().zip(token_ids).collect()))
+}
+
+/// Looks up the Up/Down token IDs of the event with slug `market_slug`.
+pub async fn discover_up_down_tokens(
+    client: &Client,
+    market_slug: &str,
+) -> Result<Option<UpDownTokens>> {
     let mut offset = 0usize;
     let mut target_event: Option<Value> = None;
-
     loop {
-        let url = format!("https://gamma-api.polymarket.com/events?limit=100&active=true&archived=false&closed=false&order=volume24hr&ascending=false&offset={}", offset);
-        let response = client
-            .get(&url)
-            .send()
-            .await
-            .with_context(|| format!("failed to fetch events from {}", url))?;
-        let events: Value = response
-            .json()
-            .await
-            .context("failed to parse events response JSON")?;
-        let events = events
-            .as_array()
-            .ok_or_else(|| anyhow!("events response is not an array"))?;
-
+        let events = fetch_events_page(client, offset, "").await?;
         if events.is_empty() {
             break;
         }
@@ -82,7 +233,7 @@ pub async fn autodiscover_market_config(
             event
                 .get("slug")
                 .and_then(Value::as_str)
-                .map(|slug| slug.eq_ignore_ascii_case(&market_slug))
+                .map(|slug| slug.eq_ignore_ascii_case(market_slug))
                 .unwrap_or(false)
         }) {
             target_event = Some(event.clone());
@@ -90,7 +241,7 @@ pub async fn autodiscover_market_config(
         }
 
         offset += events.len();
-        if events.len() < 100 {
+        if events.len() < EVENTS_PAGE_SIZE {
             break;
         }
     }
@@ -113,36 +264,18 @@ pub async fn autodiscover_market_config(
 
     let mut yes_token_id: Option<String> = None;
     let mut no_token_id: Option<String> = None;
+    let mut metadata: Option<Arc<Market>> = None;
 
     for market in markets {
-        let token_ids: Vec<String> = match market.get("clobTokenIds") {
-            Some(Value::String(s)) => {
-                serde_json::from_str(s).context("failed to parse clobTokenIds JSON string")?
-            }
-            Some(Value::Array(arr)) => arr
-                .iter()
-                .filter_map(Value::as_str)
-                .map(ToString::to_string)
-                .collect(),
-            _ => continue,
-        };
-
-        let outcomes: Vec<String> = match market.get("outcomes") {
-            Some(Value::String(s)) => {
-                serde_json::from_str(s).context("failed to parse outcomes JSON string")?
-            }
-            Some(Value::Array(arr)) => arr
-                .iter()
-                .filter_map(Value::as_str)
-                .map(ToString::to_string)
-                .collect(),
-            _ => continue,
+        let Some(tokens) = outcome_tokens(market)? else {
+            continue;
         };
 
-        for (token_id, outcome) in token_ids.into_iter().zip(outcomes.into_iter()) {
+        for (outcome, token_id) in tokens {
             match outcome.to_lowercase().as_str() {
                 "up" => {
                     yes_token_id = Some(token_id);
+                    metadata = serde_json::from_value(market.clone()).ok().map(Arc::new);
                 }
                 "down" => {
                     no_token_id = Some(token_id);
@@ -180,12 +313,32 @@ pub async fn autodiscover_market_config(
     info!("--> Found YES Token ID: {}", yes_token_id);
     info!("--> Found NO Token ID: {}", no_token_id);
 
-    // 3. Fetch the strike price (open price of the 1H candle) from Binance.
-    let start_timestamp_ms = start_hour_et.timestamp_millis();
-    let binance_symbol = format!("{}USDT", crypto.to_uppercase());
+    Ok(Some(UpDownTokens {
+        yes_token_id,
+        no_token_id,
+        metadata,
+    }))
+}
+
+/// Open of the Binance 1H candle starting at `start_timestamp_ms`.
+pub async fn fetch_hourly_open(
+    client: &Client,
+    binance_symbol: &str,
+    start_timestamp_ms: i64,
+) -> Result<f64> {
+    fetch_candle_open(client, binance_symbol, "1h", start_timestamp_ms).await
+}
+
+/// Open of the Binance `interval` candle starting at `start_timestamp_ms`.
+pub async fn fetch_candle_open(
+    client: &Client,
+    binance_symbol: &str,
+    interval: &str,
+    start_timestamp_ms: i64,
+) -> Result<f64> {
     let binance_url = format!(
-        "https://api.binance.com/api/v3/klines?symbol={}&interval=1h&startTime={}&limit=1",
-        binance_symbol, start_timestamp_ms
+        "https://api.binance.com/api/v3/klines?symbol={}&interval={}&startTime={}&limit=1",
+        binance_symbol, interval, start_timestamp_ms
     );
 
     let response = client
@@ -201,27 +354,18 @@ pub async fn autodiscover_market_config(
     let strike_price = data
         .as_array()
         .and_then(|arr| arr.first())
+        .filter(|entry| entry.get(0).and_then(Value::as_i64) == Some(start_timestamp_ms))
         .and_then(|entry| entry.get(1))
         .and_then(Value::as_str)
         .and_then(|price| price.parse::<f64>().ok())
         .ok_or_else(|| {
             anyhow!(
-                "could not fetch 1H candle data from Binance for timestamp {}",
+                "could not fetch {} candle data from Binance for timestamp {}",
+                interval,
                 start_timestamp_ms
             )
         })?;
 
     info!("--> Found Strike Price: {}", strike_price);
-
-    let config = MarketConfig {
-        name: market_slug.clone(),
-        yes_token_id,
-        no_token_id,
-        end_time_et: end_hour_et.format("%Y-%m-%d %H:%M:%S").to_string(),
-        binance_symbol,
-        strike_price,
-    };
-
-    info!("--- Market autodiscovery successful ---");
-    Ok(Some(config))
+    Ok(strike_price)
 }
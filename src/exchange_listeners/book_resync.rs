@@ -0,0 +1,84 @@
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use reqwest::Client;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time;
+
+use super::event_processor::{CountingSender, SocketEvent};
+use super::poly_listeners::SubscriptionCommand;
+use super::poly_models::Listener;
+
+const BOOK_URL: &str = "https://clob.polymarket.com/book";
+const SNAPSHOT_ATTEMPTS: u32 = 3;
+
+/// Fetches the REST `/book` snapshot of `asset_id` as a legacy `book` message.
+async fn fetch_book_message(client: &Client, asset_id: &str) -> Result<Vec<u8>> {
+    let response = client
+        .get(BOOK_URL)
+        .query(&[("token_id", asset_id)])
+        .send()
+        .await
+        .with_context(|| format!("failed to fetch book of {}", asset_id))?
+        .error_for_status()?;
+    let mut book: Value = response
+        .json()
+        .await
+        .context("failed to parse book response JSON")?;
+    let fields = book
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("book response is not an object"))?;
+    fields.insert("event_type".to_string(), Value::from("book"));
+    Ok(serde_json::to_vec(&book)?)
+}
+
+/// Replaces the book of `asset_id` with a REST snapshot, falling back to resubscribing
+/// it, which makes the websocket send a fresh snapshot.
+async fn resync_book(
+    client: Client,
+    asset_id: String,
+    subscriptions: UnboundedSender<SubscriptionCommand>,
+    event_tx: Arc<CountingSender>,
+) {
+    for attempt in 1..=SNAPSHOT_ATTEMPTS {
+        match fetch_book_message(&client, &asset_id).await {
+            Ok(payload) => {
+                info!("[BookResync] Snapshot fetched for {}", asset_id);
+                let _ = event_tx.send(SocketEvent::Market {
+                    listener: Listener::PolyMarketLegacy,
+                    payload,
+                });
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    "[BookResync] Attempt {} for {} failed: {:#}",
+                    attempt, asset_id, e
+                );
+                time::sleep(Duration::from_secs(attempt as u64)).await;
+            }
+        }
+    }
+    warn!("[BookResync] Resubscribing {} instead", asset_id);
+    let _ = subscriptions.send(SubscriptionCommand::Resubscribe(vec![asset_id]));
+}
+
+/// Resyncs every asset ID received on `requests`. Requests are sent once per stale
+/// episode, so no deduplication happens here.
+pub async fn run_book_resync(
+    mut requests: UnboundedReceiver<String>,
+    subscriptions: UnboundedSender<SubscriptionCommand>,
+    event_tx: Arc<CountingSender>,
+) {
+    let client = Client::new();
+    while let Some(asset_id) = requests.recv().await {
+        tokio::spawn(resync_book(
+            client.clone(),
+            asset_id,
+            subscriptions.clone(),
+            Arc::clone(&event_tx),
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::exchange_listeners::crypto_models::*;
 use crate::exchange_listeners::event_processor::{CountingSender, SocketEvent};
+use crate::exchange_listeners::feed_health::FEED_HEALTH;
 use crate::exchange_listeners::orderbooks::{OrderbookDepth, OrderbookLevel};
 use anyhow::Result;
 use bstr::BString;
@@ -32,9 +33,42 @@ async fn websocket_handler<F>(
         crypto,
     };
 
+    websocket_event_loop(
+        name,
+        url,
+        subscribe_msg,
+        clear_event,
+        event_tx,
+        move |bytes| {
+            Ok(on_message(bytes)?.map(|price_update| SocketEvent::Price {
+                exchange,
+                instrument,
+                crypto,
+                depth: OrderbookDepth::L1,
+                price_update,
+            }))
+        },
+    )
+    .await;
+}
+
+/// Reconnecting subscribe-and-listen loop forwarding every event produced by `on_message`,
+/// and `clear_event` whenever the connection drops.
+async fn websocket_event_loop<F>(
+    name: &'static str,
+    url: &'static str,
+    subscribe_msg: Option<String>,
+    clear_event: SocketEvent,
+    event_tx: Arc<CountingSender>,
+    mut on_message: F,
+) where
+    F: FnMut(&[u8]) -> Result<Option<SocketEvent>>,
+{
+    let health = FEED_HEALTH.feed(name);
     loop {
         if let Ok((ws_stream, _)) = connect_async(url).await {
             let (mut write, mut read) = ws_stream.split();
+            health.on_connected();
 
             if let Some(msg) = &subscribe_msg {
                 if let Err(e) = write.send(Message::Text(msg.clone())).await {
@@ -63,23 +97,15 @@ async fn websocket_handler<F>(
 
                         match msg {
                             Message::Text(text) => {
+                                health.on_message(text.as_bytes());
                                 match on_message(text.as_bytes()) {
-                                    Ok(Some(price_update)) => {
-                                        if event_tx
-                                            .send(SocketEvent::Price {
-                                                exchange,
-                                                instrument,
-                                                crypto,
-                                                depth: OrderbookDepth::L1,
-                                                price_update,
-                                            })
-                                            .is_err()
-                                        {
-                                            error!("[{}] Failed to forward price event. Stopping listener loop.", name);
+                                    Ok(Some(event)) => {
+                                        if event_tx.send(event).is_err() {
+                                            error!("[{}] Failed to forward event. Stopping listener loop.", name);
                                             return;
                                         }
                                     }
-                                    Ok(None) => { /* Message did not produce a price update */ }
+                                    Ok(None) => { /* Message did not produce an event */ }
                                     Err(_) => { /* Harmless processing error, e.g., on confirmation msg */ }
                                 }
                             }
@@ -108,14 +134,74 @@ async fn websocket_handler<F>(
         }
 
         let _ = event_tx.send(clear_event.clone());
+        health.on_disconnected();
         warn!(
-            "[{}] Listener DOWN. Clearing price and reconnecting in 5s...",
+            "[{}] Listener DOWN. Clearing and reconnecting in 5s...",
             name
         );
         time::sleep(Duration::from_secs(5)).await;
     }
 }
 
+// --- Stablecoin Rate Listeners ---
+pub async fn binance_usdc_usdt_listener(event_tx: Arc<CountingSender>) {
+    websocket_event_loop(
+        "Binance_USDCUSDT",
+        "wss://stream.binance.com:9443/ws/usdcusdt@bookTicker",
+        None,
+        SocketEvent::ClearRate {
+            kind: RateKind::UsdcUsdtBinance,
+        },
+        event_tx,
+        |bytes| {
+            let mut vec = bytes.to_vec();
+            let ticker: BinanceBookTicker = simd_json::from_slice(&mut vec)?;
+            let bid = ticker.best_bid.parse::<f64>()?;
+            let ask = ticker.best_ask.parse::<f64>()?;
+            Ok(Some(SocketEvent::Rate {
+                source: "Binance_USDCUSDT",
+                kind: RateKind::UsdcUsdtBinance,
+                value: (bid + ask) / 2.0,
+            }))
+        },
+    )
+    .await;
+}
+
+pub async fn coinbase_usdt_usd_listener(event_tx: Arc<CountingSender>) {
+    let subscribe_msg = json!({
+        "type": "subscribe",
+        "product_ids": ["USDT-USD"],
+        "channels": ["ticker", "heartbeat"]
+    })
+    .to_string();
+
+    websocket_event_loop(
+        "Coinbase_USDTUSD",
+        "wss://ws-feed.exchange.coinbase.com",
+        Some(subscribe_msg),
+        SocketEvent::ClearRate {
+            kind: RateKind::UsdUsdtCoinbase,
+        },
+        event_tx,
+        |bytes| {
+            let mut vec = bytes.to_vec();
+            match simd_json::from_slice::<CoinbaseLegacyMsg>(&mut vec)? {
+                CoinbaseLegacyMsg::Ticker(CoinbaseTicker {
+                    product_id: "USDT-USD",
+                    price: Some(price),
+                }) => Ok(Some(SocketEvent::Rate {
+                    source: "Coinbase_USDTUSD",
+                    kind: RateKind::UsdUsdtCoinbase,
+                    value: price.parse::<f64>()?,
+                })),
+                _ => Ok(None),
+            }
+        },
+    )
+    .await;
+}
+
 // --- Specific Listener Implementations ---
 // Price listeners use the generic handler where possible.
 pub async fn binance_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<CountingSender>) {
@@ -214,7 +300,7 @@ pub async fn coinbase_legacy_listener(crypto: Crypto, event_tx: Arc<CountingSend
                     }
                     Err(anyhow::anyhow!("Could not process ticker"))
                 }
-                CoinbaseLegacyMsg::Heartbeat(_) => {
+                CoinbaseLegacyMsg::Heartbeat => {
                     // Acknowledged heartbeat. This is not a price update, so we return Err to signal the handler to continue listening.
                     Err(anyhow::anyhow!("Heartbeat received"))
                 }
@@ -363,11 +449,19 @@ pub async fn bitmex_listener(crypto: Crypto, event_tx: Arc<CountingSender>) {
 }
 
 // Listeners with custom loops for unique handshakes/heartbeats.
+/// Deribit lists BTC and ETH as inverse perpetuals, the rest as USDC-margined linear ones.
+fn deribit_perpetual(crypto: Crypto) -> String {
+    match crypto {
+        Crypto::BTC | Crypto::ETH => format!("{}-PERPETUAL", crypto),
+        _ => format!("{}_USDC-PERPETUAL", crypto),
+    }
+}
+
 pub async fn deribit_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<CountingSender>) {
     let (name_prefix, channel_str, instrument) = if is_perp {
         (
             "Perp",
-            format!("book.{}-PERPETUAL.raw", crypto),
+            format!("book.{}.raw", deribit_perpetual(crypto)),
             Instrument::Perpetual,
         )
     } else {
@@ -387,9 +481,11 @@ pub async fn deribit_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<Count
     let test_response_msg =
         json!({ "jsonrpc": "2.0", "id": 8008, "method": "public/test", "params": {} }).to_string();
 
+    let health = FEED_HEALTH.feed(&name);
     loop {
         if let Ok((ws_stream, _)) = connect_async(url).await {
             let (mut write, mut read) = ws_stream.split();
+            health.on_connected();
             if write.send(Message::Text(auth_msg.clone())).await.is_err()
                 || write
                     .send(Message::Text(subscribe_msg.clone()))
@@ -411,6 +507,7 @@ pub async fn deribit_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<Count
                     continue;
                 }
                 let mut text = msg.into_text().unwrap();
+                health.on_message(text.as_bytes());
 
                 match unsafe { simd_json::from_str::<DeribitMsg>(&mut text) } {
                     Ok(DeribitMsg::Heartbeat(hb)) if hb.params.heartbeat_type == "test_request" => {
@@ -483,8 +580,9 @@ pub async fn deribit_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<Count
             error!("[{}] Connection failed", name);
         }
 
+        health.on_disconnected();
         warn!(
-            "[{}] Listener DOWN. Clearing price and reconnecting in 5s...",
+            "[{}] Listener DOWN. Clearing and reconnecting in 5s...",
             name
         );
         let _ = event_tx.send(clear_event.clone());
@@ -505,9 +603,11 @@ pub async fn coinbase_advanced_listener(crypto: Crypto, event_tx: Arc<CountingSe
         crypto,
     };
 
+    let health = FEED_HEALTH.feed(&name);
     loop {
         if let Ok((ws_stream, _)) = connect_async(url).await {
             let (mut write, mut read) = ws_stream.split();
+            health.on_connected();
             if write
                 .send(Message::Text(heartbeat_sub_msg.clone()))
                 .await
@@ -542,6 +642,7 @@ pub async fn coinbase_advanced_listener(crypto: Crypto, event_tx: Arc<CountingSe
                             Ok(text) => text.into_bytes(),
                             Err(_) => continue,
                         };
+                        health.on_message(&vec);
 
 
                         match simd_json::from_slice::<CoinbaseAdvancedMsg>(&mut vec) {
@@ -595,8 +696,9 @@ pub async fn coinbase_advanced_listener(crypto: Crypto, event_tx: Arc<CountingSe
                 }
             }
         }
+        health.on_disconnected();
         warn!(
-            "[{}] Listener DOWN. Clearing price and reconnecting in 5s...",
+            "[{}] Listener DOWN. Clearing and reconnecting in 5s...",
             name
         );
         let _ = event_tx.send(clear_event.clone());
@@ -624,9 +726,11 @@ pub async fn okx_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<CountingS
         crypto,
     };
 
+    let health = FEED_HEALTH.feed(&name);
     loop {
         if let Ok((ws_stream, _)) = connect_async(url).await {
             let (mut write, mut read) = ws_stream.split();
+            health.on_connected();
             if write
                 .send(Message::Text(subscribe_msg.clone()))
                 .await
@@ -643,6 +747,7 @@ pub async fn okx_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<CountingS
                     Some(Ok(msg)) = read.next() => {
                         if !msg.is_text() { continue; }
                         let text = msg.into_text().unwrap();
+                        health.on_message(text.as_bytes());
                         if text == "pong" { continue; }
                         let mut v = text.into_bytes();
                         if let Ok(OkxMsg::Data(msg_data)) = simd_json::from_slice::<OkxMsg>(&mut v) {
@@ -677,8 +782,9 @@ pub async fn okx_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<CountingS
                 }
             }
         }
+        health.on_disconnected();
         warn!(
-            "[{}] Listener DOWN. Clearing price and reconnecting in 5s...",
+            "[{}] Listener DOWN. Clearing and reconnecting in 5s...",
             name
         );
         let _ = event_tx.send(clear_event.clone());
@@ -719,9 +825,11 @@ pub async fn kraken_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<Counti
         crypto,
     };
 
+    let health = FEED_HEALTH.feed(&name);
     loop {
         if let Ok((ws_stream, _)) = connect_async(url).await {
             let (mut write, mut read) = ws_stream.split();
+            health.on_connected();
             info!("[{}] Connected.", name);
 
             if let Err(e) = write.send(Message::Text(subscribe_msg.clone())).await {
@@ -736,6 +844,7 @@ pub async fn kraken_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<Counti
                     continue;
                 }
                 let mut text = msg.into_text().unwrap();
+                health.on_message(text.as_bytes());
 
                 match unsafe { simd_json::from_str::<KrakenMsg>(&mut text) } {
                     Ok(KrakenMsg::BookSnapshot { feed, data }) if feed == "book_snapshot" => {
@@ -811,8 +920,9 @@ pub async fn kraken_listener(crypto: Crypto, is_perp: bool, event_tx: Arc<Counti
             error!("[{}] Connection failed", name);
         }
 
+        health.on_disconnected();
         warn!(
-            "[{}] Listener DOWN. Clearing price and reconnecting in 5s...",
+            "[{}] Listener DOWN. Clearing and reconnecting in 5s...",
             name
         );
         let _ = event_tx.send(clear_event.clone());
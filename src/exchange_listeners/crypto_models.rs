@@ -2,48 +2,104 @@ use crate::exchange_listeners::orderbooks::{CryptoOrderbook, OrderbookDepth};
 use crate::exchange_listeners::AppState;
 use atomic_float::AtomicF64;
 use dashmap::DashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use simd_json;
 use std::fmt;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Instant;
 
 pub fn get_crypto_orderbook_map(
     app_state: Arc<AppState>,
     crypto: Crypto,
 ) -> Arc<dashmap::DashMap<(Exchange, Instrument, OrderbookDepth), CryptoOrderbook>> {
-    match crypto {
-        Crypto::BTC => app_state.btc_orderbooks.clone(),
-        // Crypto::ETH => &self.app_state.eth_orderbooks,
-        // Crypto::XRP => &self.app_state.xrp_orderbooks,
-        // Crypto::SOL => &self.app_state.sol_orderbooks,
-        _ => app_state.btc_orderbooks.clone(),
-    }
+    app_state.orderbooks_for(crypto)
 }
 
 pub fn get_crypto_prices_map(
     app_state: Arc<AppState>,
     crypto: Crypto,
 ) -> Arc<dashmap::DashMap<(Exchange, Instrument, OrderbookDepth), CryptoPrice>> {
-    match crypto {
-        Crypto::BTC => app_state.btc_prices.clone(),
-        // Crypto::ETH => &self.app_state.eth_orderbooks,
-        // Crypto::XRP => &self.app_state.xrp_orderbooks,
-        // Crypto::SOL => &self.app_state.sol_orderbooks,
-        _ => app_state.btc_prices.clone(),
-    }
+    app_state.prices_for(crypto)
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum RateKind {
+    /// Binance USDCUSDT mid: USDT per USDC.
     UsdcUsdtBinance,
+    /// Coinbase USDT-USD last trade: USD per USDT.
     UsdUsdtCoinbase,
 }
 
+/// Currency a venue quotes its crypto prices in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Quote {
+    Usd,
+    Usdt,
+    Usdc,
+}
+
+/// Latest stablecoin conversion rates; 0.0 means the feed is down.
+#[derive(Debug, Default)]
+pub struct Rates {
+    usdc_usdt_binance: AtomicF64,
+    usd_usdt_coinbase: AtomicF64,
+}
+
+impl Rates {
+    pub fn set(&self, kind: RateKind, value: f64) {
+        let value = if value.is_finite() && value > 0.0 {
+            value
+        } else {
+            0.0
+        };
+        self.slot(kind).store(value, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self, kind: RateKind) {
+        self.slot(kind).store(0.0, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, kind: RateKind) -> Option<f64> {
+        let value = self.slot(kind).load(Ordering::Relaxed);
+        (value > 0.0).then_some(value)
+    }
+
+    /// USD value of one unit of `quote`. USDT prefers the direct Coinbase rate and
+    /// otherwise goes through Binance USDC at par; USDC needs both rates. `None` while
+    /// the rates needed are unknown, so a stale peg is never assumed.
+    pub fn usd_per(&self, quote: Quote) -> Option<f64> {
+        let usd_per_usdt = self.get(RateKind::UsdUsdtCoinbase);
+        let usdt_per_usdc = self.get(RateKind::UsdcUsdtBinance);
+        match quote {
+            Quote::Usd => Some(1.0),
+            Quote::Usdt => usd_per_usdt.or_else(|| usdt_per_usdc.map(|rate| 1.0 / rate)),
+            Quote::Usdc => Some(usd_per_usdt? * usdt_per_usdc?),
+        }
+    }
+
+    fn slot(&self, kind: RateKind) -> &AtomicF64 {
+        match kind {
+            RateKind::UsdcUsdtBinance => &self.usdc_usdt_binance,
+            RateKind::UsdUsdtCoinbase => &self.usd_usdt_coinbase,
+        }
+    }
+}
+
+/// Venue price in its native quote currency, plus the rate converting it to USD.
 #[derive(Debug, Clone, Copy)]
 pub struct CryptoPrice {
     pub midpoint: f64,
     pub price: f64,
+    /// USD per unit of the venue's quote currency; `None` until the rate is known.
+    pub usd_rate: Option<f64>,
+    pub updated_at: Instant,
+}
+
+impl Default for CryptoPrice {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CryptoPrice {
@@ -51,8 +107,35 @@ impl CryptoPrice {
         Self {
             midpoint: 0.0,
             price: 0.0,
+            usd_rate: None,
+            updated_at: Instant::now(),
         }
     }
+
+    pub fn set(&mut self, midpoint: f64, price: f64, usd_rate: Option<f64>) {
+        self.midpoint = midpoint;
+        self.price = price;
+        self.usd_rate = usd_rate;
+        self.updated_at = Instant::now();
+    }
+
+    pub fn usd_midpoint(&self) -> Option<f64> {
+        self.usd_rate.map(|rate| self.midpoint * rate)
+    }
+
+    pub fn usd_price(&self) -> Option<f64> {
+        self.usd_rate.map(|rate| self.price * rate)
+    }
+}
+
+/// Composite fair price of an underlying across all live venues, with a confidence band.
+#[derive(Debug, Clone, Copy)]
+pub struct FairValue {
+    pub price: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub sources: usize,
+    pub updated_at: Instant,
 }
 
 // --- Core Data Structures ---
@@ -64,13 +147,31 @@ pub enum Crypto {
     SOL,
 }
 
+impl Crypto {
+    pub const ALL: [Crypto; 4] = [Crypto::BTC, Crypto::ETH, Crypto::XRP, Crypto::SOL];
+}
+
+impl std::str::FromStr for Crypto {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "BTC" => Ok(Crypto::BTC),
+            "ETH" => Ok(Crypto::ETH),
+            "XRP" => Ok(Crypto::XRP),
+            "SOL" => Ok(Crypto::SOL),
+            other => Err(format!("unknown crypto '{}'", other)),
+        }
+    }
+}
+
 impl fmt::Display for Crypto {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Exchange {
     Binance,
     CoinbaseLegacy,
@@ -97,6 +198,17 @@ impl Exchange {
             Exchange::Kraken => "Kraken",
         }
     }
+
+    /// Quote currency of the market we subscribe to on this venue.
+    pub const fn quote(self, instrument: Instrument, crypto: Crypto) -> Quote {
+        match (self, instrument, crypto) {
+            (Exchange::Binance | Exchange::Bybit | Exchange::Okx, _, _) => Quote::Usdt,
+            // BTC and ETH perpetuals are inverse USD contracts, the rest are linear USDC.
+            (Exchange::Deribit, Instrument::Perpetual, Crypto::BTC | Crypto::ETH) => Quote::Usd,
+            (Exchange::Deribit, _, _) => Quote::Usdc,
+            _ => Quote::Usd,
+        }
+    }
 }
 
 impl std::fmt::Display for Exchange {
@@ -105,7 +217,7 @@ impl std::fmt::Display for Exchange {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Instrument {
     Spot,
     Perpetual,
@@ -144,20 +256,12 @@ pub struct CoinbaseTicker<'a> {
     pub price: Option<&'a str>,
 }
 #[derive(Deserialize)]
-#[allow(dead_code)]
-#[serde(bound(deserialize = "'de: 'a"))]
-pub struct CoinbaseHeartbeat<'a> {
-    pub product_id: &'a str,
-    pub sequence: i64,
-    pub time: &'a str,
-}
-#[derive(Deserialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 #[serde(bound(deserialize = "'de: 'a"))]
 pub enum CoinbaseLegacyMsg<'a> {
     Ticker(CoinbaseTicker<'a>),
-    Heartbeat(CoinbaseHeartbeat<'a>),
+    Heartbeat,
     #[serde(other)]
     Other,
 }
@@ -361,3 +465,65 @@ pub enum KrakenMsg {
     // Fallback for any other message shapes
     Other(serde_json::Value),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_ignore_invalid_values_and_clear() {
+        let rates = Rates::default();
+        assert_eq!(rates.get(RateKind::UsdUsdtCoinbase), None);
+        assert_eq!(rates.usd_per(Quote::Usdt), None);
+        assert_eq!(rates.usd_per(Quote::Usdc), None);
+
+        rates.set(RateKind::UsdUsdtCoinbase, 1.001);
+        rates.set(RateKind::UsdcUsdtBinance, 0.998);
+        assert_eq!(rates.usd_per(Quote::Usdt), Some(1.001));
+        assert_eq!(rates.usd_per(Quote::Usdc), Some(1.001 * 0.998));
+
+        for invalid in [f64::NAN, f64::INFINITY, 0.0, -1.0] {
+            rates.set(RateKind::UsdUsdtCoinbase, invalid);
+            assert_eq!(rates.get(RateKind::UsdUsdtCoinbase), None);
+        }
+        assert_eq!(rates.usd_per(Quote::Usdt), Some(1.0 / 0.998));
+        assert_eq!(rates.usd_per(Quote::Usdc), None);
+
+        rates.clear(RateKind::UsdcUsdtBinance);
+        assert_eq!(rates.get(RateKind::UsdcUsdtBinance), None);
+        assert_eq!(rates.usd_per(Quote::Usdt), None);
+        assert_eq!(rates.usd_per(Quote::Usd), Some(1.0));
+    }
+
+    #[test]
+    fn stablecoins_have_no_usd_value_without_their_rates() {
+        let rates = Rates::default();
+        rates.set(RateKind::UsdcUsdtBinance, 0.998);
+        assert_eq!(rates.usd_per(Quote::Usdc), None);
+
+        rates.clear(RateKind::UsdcUsdtBinance);
+        rates.set(RateKind::UsdUsdtCoinbase, 1.001);
+        assert_eq!(rates.usd_per(Quote::Usdt), Some(1.001));
+        assert_eq!(rates.usd_per(Quote::Usdc), None);
+    }
+
+    #[test]
+    fn parses_coinbase_ticker_and_heartbeat() {
+        let mut ticker = br#"{"type":"ticker","product_id":"USDT-USD","price":"1.0002"}"#.to_vec();
+        match simd_json::from_slice::<CoinbaseLegacyMsg>(&mut ticker).unwrap() {
+            CoinbaseLegacyMsg::Ticker(ticker) => {
+                assert_eq!(
+                    (ticker.product_id, ticker.price),
+                    ("USDT-USD", Some("1.0002"))
+                )
+            }
+            _ => panic!("expected a ticker"),
+        }
+
+        let mut heartbeat = br#"{"type":"heartbeat","product_id":"USDT-USD","sequence":7,"last_trade_id":1,"time":"2025-10-17T16:00:00Z"}"#.to_vec();
+        assert!(matches!(
+            simd_json::from_slice::<CoinbaseLegacyMsg>(&mut heartbeat).unwrap(),
+            CoinbaseLegacyMsg::Heartbeat
+        ));
+    }
+}
@@ -1,29 +1,36 @@
+use crate::config::{EVENT_LAG_THRESHOLD_MS, EVENT_QUEUE_CAPACITY};
 use crate::credentials;
+use crate::exchange_listeners::autodiscover_markets::MarketConfig;
 use crate::exchange_listeners::crypto_models::{
     get_crypto_orderbook_map, Crypto, CryptoPriceUpdate, Exchange, Instrument, RateKind,
 };
+use crate::exchange_listeners::event_queue::{CloseOnDrop, EventQueue, PushOutcome};
+use crate::exchange_listeners::event_routing;
 use crate::exchange_listeners::orderbooks::poly_orderbook::OrderBook;
 use crate::exchange_listeners::orderbooks::{CryptoOrderbook, OrderbookDepth, OrderbookLevel};
 use crate::exchange_listeners::poly_models::{
     AggOrderbook, Listener, OrderPayload, PolymarketMessageWrapper, PolymarketMessageWrapperOld,
     Position, PriceChange, PriceChangePayload, TickSizeChangePayload, TradePayload,
 };
+use crate::metrics::METRICS;
 
-use crate::exchange_listeners::states::{AppState, PolyMarketState};
+use crate::exchange_listeners::states::{AppState, PolyMarketState, StaleReason};
+use crate::strategies::profiler::STRATEGY_PROFILE;
+use crate::strategies::timers::{run_strategy_timers, TimerHandle};
 use crate::strategies::{Strategy, StrategyContext};
 use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
 use log::{debug, error, info, warn};
 use simd_json::{
     prelude::{ValueAsScalar, ValueObjectAccess},
     value::owned::Value as OwnedValue,
 };
 use simd_json::{to_borrowed_value, BorrowedValue};
+use std::collections::HashMap;
 use std::str;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc::{self, UnboundedSender};
-
-pub type SocketEventSender = UnboundedSender<SocketEvent>;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 #[derive(Debug, Clone)]
 pub enum SocketEvent {
@@ -69,56 +76,262 @@ pub enum SocketEvent {
         instrument: Instrument,
         crypto: Crypto,
     },
+    MarketRollover {
+        crypto: Crypto,
+        previous: Option<Box<MarketConfig>>,
+        next: Box<MarketConfig>,
+    },
+    /// A market connection dropped; its books may miss updates until resubscribed.
+    MarketDisconnected {
+        asset_ids: Vec<String>,
+    },
+    /// Assets newly subscribed to the market feed.
+    MarketAdded {
+        asset_ids: Vec<String>,
+    },
+    /// Assets of a market that expired or resolved.
+    MarketClosed {
+        asset_ids: Vec<String>,
+    },
+}
+
+/// One queue of the processor, drained by its own worker.
+#[derive(Clone)]
+struct Lane {
+    queue: Arc<EventQueue>,
+}
+
+impl Lane {
+    fn send(&self, event: SocketEvent) -> Result<(), mpsc::error::SendError<SocketEvent>> {
+        self.push(event).map(|_| ())
+    }
+
+    fn push(&self, event: SocketEvent) -> Result<PushOutcome, mpsc::error::SendError<SocketEvent>> {
+        self.queue.push(event).map_err(mpsc::error::SendError)
+    }
+
+    fn pending(&self) -> usize {
+        self.queue.pending()
+    }
 }
 
+/// Routes events to the processor's lanes. User events take the priority lane so fills
+/// never wait behind market data. Market events go to the shard of their market and
+/// crypto events to the shard of their crypto, which keeps each asset's events in order
+/// while independent markets are processed concurrently. Lifecycle events of a market
+/// (added, disconnected, rolled over, closed) go to the shard of its frames as well.
 #[derive(Clone)]
 pub struct CountingSender {
-    event_tx: SocketEventSender,
-    pending: Arc<AtomicUsize>,
+    priority: Lane,
+    shards: Vec<Lane>,
+    /// Shard of every asset seen in a market frame, for routing lifecycle events.
+    asset_shards: Arc<DashMap<String, usize>>,
+    processor: Arc<EventProcessor>,
 }
 
 impl CountingSender {
     pub fn send(&self, event: SocketEvent) -> Result<(), mpsc::error::SendError<SocketEvent>> {
-        self.pending.fetch_add(1, Ordering::SeqCst);
-        match self.event_tx.send(event) {
-            Ok(()) => Ok(()),
-            Err(err) => {
-                self.pending.fetch_sub(1, Ordering::SeqCst);
-                Err(err)
+        match event {
+            SocketEvent::User { .. } => self.priority.send(event),
+            SocketEvent::Market { listener, payload } => {
+                match event_routing::split_batch(&payload) {
+                    Some(frames) => frames
+                        .into_iter()
+                        .try_for_each(|payload| self.send_market(listener, payload)),
+                    None => self.send_market(listener, payload),
+                }
+            }
+            SocketEvent::MarketDisconnected { asset_ids } => self
+                .send_per_asset_shard(asset_ids, |asset_ids| SocketEvent::MarketDisconnected {
+                    asset_ids,
+                }),
+            SocketEvent::MarketAdded { asset_ids } => self
+                .send_per_asset_shard(asset_ids, |asset_ids| SocketEvent::MarketAdded {
+                    asset_ids,
+                }),
+            SocketEvent::MarketClosed { asset_ids } => self
+                .send_per_asset_shard(asset_ids, |asset_ids| SocketEvent::MarketClosed {
+                    asset_ids,
+                }),
+            // With the market it retires, so that market's closure comes after it.
+            SocketEvent::MarketRollover {
+                ref previous,
+                ref next,
+                ..
+            } => {
+                let market = previous.as_deref().unwrap_or(next);
+                self.shards[self.asset_shard(&market.yes_token_id)].send(event)
+            }
+            SocketEvent::Price { crypto, .. }
+            | SocketEvent::L2Snapshot { crypto, .. }
+            | SocketEvent::L2Update { crypto, .. }
+            | SocketEvent::ClearPrice { crypto, .. } => {
+                self.shards[event_routing::shard_of(crypto, self.shards.len())].send(event)
+            }
+            SocketEvent::Rate { .. } | SocketEvent::ClearRate { .. } => self.shards[0].send(event),
+        }
+    }
+
+    /// Shard of the frames of `asset_id`: that of its market, known from its frames or
+    /// its metadata, or of the asset itself for unknown assets.
+    fn asset_shard(&self, asset_id: &str) -> usize {
+        if let Some(shard) = self.asset_shards.get(asset_id) {
+            return *shard;
+        }
+        let condition_id = self
+            .processor
+            .poly_state
+            .markets
+            .get(asset_id)
+            .and_then(|market| market.conditionId.clone());
+        match condition_id {
+            Some(market) => event_routing::shard_of(market.as_str(), self.shards.len()),
+            None => event_routing::shard_of(asset_id, self.shards.len()),
+        }
+    }
+
+    /// Sends `event` of the assets on each shard to that shard.
+    fn send_per_asset_shard(
+        &self,
+        asset_ids: Vec<String>,
+        event: fn(Vec<String>) -> SocketEvent,
+    ) -> Result<(), mpsc::error::SendError<SocketEvent>> {
+        let mut per_shard: HashMap<usize, Vec<String>> = HashMap::new();
+        for asset_id in asset_ids {
+            per_shard
+                .entry(self.asset_shard(&asset_id))
+                .or_default()
+                .push(asset_id);
+        }
+        per_shard
+            .into_iter()
+            .try_for_each(|(shard, asset_ids)| self.shards[shard].send(event(asset_ids)))
+    }
+
+    fn send_market(
+        &self,
+        listener: Listener,
+        payload: Vec<u8>,
+    ) -> Result<(), mpsc::error::SendError<SocketEvent>> {
+        let shard = event_routing::market_key(&payload).map_or(0, |market| {
+            event_routing::shard_of(market, self.shards.len())
+        });
+        if let Some(asset_id) = event_routing::json_string_field(&payload, "asset_id") {
+            if !self.asset_shards.contains_key(asset_id) {
+                self.asset_shards.insert(asset_id.to_string(), shard);
             }
         }
+        let outcome = self.shards[shard].push(SocketEvent::Market { listener, payload })?;
+        if let PushOutcome::Overflowed { asset_ids } = outcome {
+            let resynced = asset_ids
+                .iter()
+                .filter(|id| {
+                    self.processor
+                        .poly_state
+                        .resync_book(id.as_str(), StaleReason::Overflow)
+                })
+                .count();
+            if resynced > 0 {
+                warn!(
+                    "[EventProcessor] shard{} is full; resyncing {} books.",
+                    shard, resynced
+                );
+            }
+        }
+        Ok(())
     }
 
+    /// Events sent and not yet handled, over all lanes.
     pub fn pending(&self) -> usize {
-        self.pending.load(Ordering::SeqCst)
+        self.lane_pending()
+            .into_iter()
+            .map(|(_, pending)| pending)
+            .sum()
+    }
+
+    /// Calls `on_stop` of every strategy. Events keep flowing, so cancels sent from the
+    /// hooks are still confirmed.
+    pub fn stop(&self) {
+        self.processor.stop();
+    }
+
+    /// Refuses further events on every lane; the workers still handle what is queued.
+    pub fn close(&self) {
+        self.priority.queue.close();
+        for lane in &self.shards {
+            lane.queue.close();
+        }
+    }
+
+    /// Events not yet handled per lane: `priority`, then `shard0`, `shard1`, ...
+    pub fn lane_pending(&self) -> Vec<(String, usize)> {
+        std::iter::once(("priority".to_string(), self.priority.pending()))
+            .chain(
+                self.shards
+                    .iter()
+                    .enumerate()
+                    .map(|(i, lane)| (format!("shard{}", i), lane.pending())),
+            )
+            .collect()
     }
 }
 
+/// Starts the event processor with `shards` market-data workers and one priority worker
+/// for user events, plus the timers of the strategies that implement `AsyncStrategy`.
 pub fn spawn_event_processor(
     app_state: Arc<AppState>,
     poly_state: Arc<PolyMarketState>,
     strategies: Vec<Arc<dyn Strategy>>,
+    shards: usize,
 ) -> Arc<CountingSender> {
-    let (tx, mut rx) = mpsc::unbounded_channel();
-    let pending = Arc::new(AtomicUsize::new(0));
-
-    let processor = EventProcessor {
+    let (timers, schedule_rx) = TimerHandle::new();
+    let async_strategies = strategies
+        .iter()
+        .filter_map(|strategy| Arc::clone(strategy).as_async())
+        .collect();
+    let processor = Arc::new(EventProcessor {
         poly_state,
         app_state,
         strategies,
-    };
-    let pending_clone = Arc::clone(&pending);
-
-    tokio::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            processor.handle_event(event);
-            pending_clone.fetch_sub(1, Ordering::SeqCst);
-        }
+        timers,
     });
+    processor.start();
+    tokio::spawn(run_strategy_timers(
+        async_strategies,
+        processor.strategy_context(),
+        schedule_rx,
+    ));
+    let spawn_lane = |name: String| {
+        let queue = Arc::new(EventQueue::new(&name, EVENT_QUEUE_CAPACITY));
+        let processor = Arc::clone(&processor);
+        let worker_queue = Arc::clone(&queue);
+        tokio::spawn(async move {
+            // Closes the queue if a handler panics, so senders see the lane is gone.
+            let _guard = CloseOnDrop(Arc::clone(&worker_queue));
+            let lag_gauge = METRICS.event_lag_seconds.with(&[&name]);
+            loop {
+                let (event, lag) = worker_queue.pop().await;
+                lag_gauge.set(lag.as_secs_f64());
+                processor.track_lag(&name, lag);
+                processor.handle_event(event);
+                worker_queue.done();
+                if worker_queue.pending() == 0 {
+                    processor.lane_drained(&name);
+                }
+            }
+        });
+        Lane { queue }
+    };
 
+    let priority = spawn_lane("priority".to_string());
+    let shards = (0..shards.max(1))
+        .map(|i| spawn_lane(format!("shard{}", i)))
+        .collect();
     Arc::new(CountingSender {
-        event_tx: tx,
-        pending,
+        priority,
+        shards,
+        asset_shards: Arc::new(DashMap::new()),
+        processor,
     })
 }
 
@@ -126,9 +339,39 @@ struct EventProcessor {
     poly_state: Arc<PolyMarketState>,
     app_state: Arc<AppState>,
     strategies: Vec<Arc<dyn Strategy>>,
+    timers: TimerHandle,
 }
 
 impl EventProcessor {
+    /// Pauses order placement while events of `lane` wait longer than the lag
+    /// threshold, and resumes once they wait less than half of it.
+    fn track_lag(&self, lane: &str, lag: Duration) {
+        let threshold = Duration::from_millis(EVENT_LAG_THRESHOLD_MS);
+        if lag > threshold {
+            if self.poly_state.mark_lane_lagging(lane, lag) {
+                error!(
+                    "[EventProcessor] {} is {:?} behind; order placement paused.",
+                    lane, lag
+                );
+            }
+        } else if lag < threshold / 2 && self.poly_state.mark_lane_caught_up(lane) {
+            info!(
+                "[EventProcessor] {} caught up; order placement resumed.",
+                lane
+            );
+        }
+    }
+
+    /// A lane with nothing queued is caught up, however long its last events waited.
+    fn lane_drained(&self, lane: &str) {
+        if self.poly_state.mark_lane_caught_up(lane) {
+            info!(
+                "[EventProcessor] {} drained; order placement resumed.",
+                lane
+            );
+        }
+    }
+
     fn handle_event(&self, event: SocketEvent) {
         match event {
             SocketEvent::Market { listener, payload } => {
@@ -139,8 +382,8 @@ impl EventProcessor {
                 source,
                 kind,
                 value,
-            } => {}
-            SocketEvent::ClearRate { kind } => {}
+            } => self.handle_rate_update(source, kind, value),
+            SocketEvent::ClearRate { kind } => self.handle_rate_clear(kind),
             SocketEvent::Price {
                 exchange,
                 instrument,
@@ -167,6 +410,44 @@ impl EventProcessor {
                 instrument,
                 crypto,
             } => self.handle_price_clear(exchange, instrument, crypto),
+            SocketEvent::MarketRollover {
+                crypto,
+                previous,
+                next,
+            } => self.handle_market_rollover(crypto, previous.map(|p| *p), *next),
+            SocketEvent::MarketDisconnected { asset_ids } => {
+                self.handle_market_disconnected(asset_ids)
+            }
+            SocketEvent::MarketAdded { asset_ids } => {
+                let ctx = self.strategy_context();
+                self.for_each_strategy("on_market_added", |strategy| {
+                    strategy.on_market_added(Arc::clone(&ctx), &asset_ids);
+                });
+            }
+            SocketEvent::MarketClosed { asset_ids } => self.handle_market_closed(asset_ids),
+        }
+    }
+
+    fn start(&self) {
+        let ctx = self.strategy_context();
+        self.for_each_strategy("on_start", |strategy| strategy.on_start(Arc::clone(&ctx)));
+    }
+
+    fn stop(&self) {
+        let ctx = self.strategy_context();
+        self.for_each_strategy("on_stop", |strategy| strategy.on_stop(Arc::clone(&ctx)));
+    }
+
+    /// Calls `hook` on every strategy through `call`, profiling each strategy. Paused
+    /// strategies are called too, so their state stays current; only their orders are
+    /// refused.
+    fn for_each_strategy(&self, hook: &'static str, mut call: impl FnMut(&dyn Strategy)) {
+        for strategy in &self.strategies {
+            let started = Instant::now();
+            call(strategy.as_ref());
+            STRATEGY_PROFILE
+                .handler(strategy.name(), hook)
+                .record(started.elapsed());
         }
     }
 
@@ -174,6 +455,7 @@ impl EventProcessor {
         Arc::new(StrategyContext::new(
             Arc::clone(&self.app_state),
             Arc::clone(&self.poly_state),
+            self.timers.clone(),
         ))
     }
 
@@ -187,7 +469,7 @@ impl EventProcessor {
     ) {
         let ctx = self.strategy_context();
 
-        for strategy in &self.strategies {
+        self.for_each_strategy("crypto_handle_price_update", |strategy| {
             strategy.crypto_handle_price_update(
                 Arc::clone(&ctx),
                 exchange,
@@ -196,7 +478,7 @@ impl EventProcessor {
                 depth,
                 price_update,
             );
-        }
+        });
     }
 
     fn handle_l2_snapshot(
@@ -208,7 +490,7 @@ impl EventProcessor {
         asks: &[OrderbookLevel],
     ) {
         let ctx = self.strategy_context();
-        for strategy in &self.strategies {
+        self.for_each_strategy("crypto_handle_l2_snapshot", |strategy| {
             strategy.crypto_handle_l2_snapshot(
                 Arc::clone(&ctx),
                 exchange,
@@ -217,7 +499,7 @@ impl EventProcessor {
                 bids,
                 asks,
             );
-        }
+        });
     }
 
     fn handle_l2_update(
@@ -229,7 +511,7 @@ impl EventProcessor {
         asks: &[OrderbookLevel],
     ) {
         let ctx = self.strategy_context();
-        for strategy in &self.strategies {
+        self.for_each_strategy("crypto_handle_l2_update", |strategy| {
             strategy.crypto_handle_l2_update(
                 Arc::clone(&ctx),
                 exchange,
@@ -238,7 +520,7 @@ impl EventProcessor {
                 bids,
                 asks,
             );
-        }
+        });
     }
 
     fn handle_price_clear(&self, exchange: Exchange, instrument: Instrument, crypto: Crypto) {
@@ -260,7 +542,7 @@ impl EventProcessor {
         if !depths.is_empty() {
             let ctx = self.strategy_context();
             for depth in depths {
-                for strategy in &self.strategies {
+                self.for_each_strategy("crypto_handle_price_clear", |strategy| {
                     strategy.crypto_handle_price_clear(
                         Arc::clone(&ctx),
                         exchange,
@@ -268,7 +550,7 @@ impl EventProcessor {
                         crypto,
                         depth,
                     );
-                }
+                });
             }
         }
     }
@@ -282,9 +564,9 @@ impl EventProcessor {
                 let t = s.trim();
                 if t.eq_ignore_ascii_case("PONG") {
                     let ctx = self.strategy_context();
-                    for strategy in &self.strategies {
+                    self.for_each_strategy("poly_handle_market_pong", |strategy| {
                         strategy.poly_handle_market_pong(Arc::clone(&ctx), listener);
-                    }
+                    });
                     return;
                 }
             }
@@ -346,9 +628,9 @@ impl EventProcessor {
             let t = s.trim();
             if t.eq_ignore_ascii_case("PONG") {
                 let ctx = self.strategy_context();
-                for strategy in &self.strategies {
+                self.for_each_strategy("poly_handle_user_pong", |strategy| {
                     strategy.poly_handle_user_pong(Arc::clone(&ctx), listener);
-                }
+                });
                 return;
             }
         }
@@ -380,9 +662,9 @@ impl EventProcessor {
             "tick_size_change" => self.handle_tick_size_change(listener, wrapper.payload),
             "pong" => {
                 let ctx = self.strategy_context();
-                for strategy in &self.strategies {
+                self.for_each_strategy("poly_handle_market_pong", |strategy| {
                     strategy.poly_handle_market_pong(Arc::clone(&ctx), listener);
-                }
+                });
             }
             unknown_type => warn!(
                 "[{}] Unhandled market message type '{}': {:?}",
@@ -398,11 +680,15 @@ impl EventProcessor {
         listener: Listener,
         wrapper: PolymarketMessageWrapperOld,
     ) {
+        if let Some(timestamp_ms) = wrapper.timestamp.as_deref().and_then(|t| t.parse().ok()) {
+            self.poly_state.advance_replay_clock(timestamp_ms);
+        }
         match wrapper.event_type.as_str() {
             "price_change" => self.handle_price_change_legacy(listener, wrapper),
             "book" => self.handle_book_legacy(listener, wrapper),
             "tick_size_change" => self.handle_tick_size_change_legacy(listener, wrapper),
-            "last_trade_price" => {}
+            "market_resolved" => self.handle_market_closed(wrapper.assets_ids),
+            "last_trade_price" | "best_bid_ask" | "new_market" => {}
             other => warn!(
                 "[{}] Unhandled legacy market message type '{}': {:?}",
                 listener.as_str(),
@@ -448,22 +734,22 @@ impl EventProcessor {
             for snapshot in snapshots {
                 // self.ensure_poly_orderbook(&snapshot);
 
-                for strategy in &self.strategies {
+                self.for_each_strategy("poly_handle_market_agg_orderbook", |strategy| {
                     strategy.poly_handle_market_agg_orderbook(
                         Arc::clone(&ctx),
                         listener,
                         &snapshot,
                     );
-                }
+                });
             }
         } else if let Ok(snapshot) =
             simd_json::serde::from_owned_value::<AggOrderbook>(payload.clone())
         {
             // self.ensure_poly_orderbook(&snapshot);
 
-            for strategy in &self.strategies {
+            self.for_each_strategy("poly_handle_market_agg_orderbook", |strategy| {
                 strategy.poly_handle_market_agg_orderbook(Arc::clone(&ctx), listener, &snapshot);
-            }
+            });
         } else {
             warn!(
                 "[PolyMarket] Failed to parse agg_orderbook payload. Raw: {}",
@@ -519,19 +805,21 @@ impl EventProcessor {
                 price: change.price.clone(),
                 size: change.size.clone(),
                 side: change.side.clone(),
+                best_bid: change.best_bid.clone(),
+                best_ask: change.best_ask.clone(),
             };
-            for strategy in &self.strategies {
+            self.for_each_strategy("poly_handle_market_price_change", |strategy| {
                 strategy.poly_handle_market_price_change(Arc::clone(&ctx), listener, &pc);
-            }
+            });
         }
     }
 
     fn process_price_change_payload(&self, listener: Listener, payload_data: PriceChangePayload) {
         let ctx = self.strategy_context();
         for change in &payload_data.pc {
-            for strategy in &self.strategies {
+            self.for_each_strategy("poly_handle_market_price_change", |strategy| {
                 strategy.poly_handle_market_price_change(Arc::clone(&ctx), listener, change);
-            }
+            });
         }
     }
 
@@ -561,9 +849,9 @@ impl EventProcessor {
         };
 
         let ctx = self.strategy_context();
-        for strategy in &self.strategies {
+        self.for_each_strategy("poly_handle_market_agg_orderbook", |strategy| {
             strategy.poly_handle_market_agg_orderbook(Arc::clone(&ctx), listener, &snapshot);
-        }
+        });
     }
 
     fn handle_tick_size_change(&self, listener: Listener, payload: OwnedValue) {
@@ -577,13 +865,13 @@ impl EventProcessor {
             }
 
             let ctx = self.strategy_context();
-            for strategy in &self.strategies {
+            self.for_each_strategy("poly_handle_market_tick_size_change", |strategy| {
                 strategy.poly_handle_market_tick_size_change(
                     Arc::clone(&ctx),
                     listener,
                     &payload_data,
                 );
-            }
+            });
         } else {
             warn!(
                 "[PolyMarket] Failed to parse tick_size_change payload. Raw: {}",
@@ -603,17 +891,17 @@ impl EventProcessor {
         };
 
         let ctx = self.strategy_context();
-        for strategy in &self.strategies {
+        self.for_each_strategy("poly_handle_market_tick_size_change", |strategy| {
             strategy.poly_handle_market_tick_size_change(Arc::clone(&ctx), listener, &ticksize_pl);
-        }
+        });
     }
 
     fn handle_trade(&self, listener: Listener, payload: OwnedValue) {
         if let Ok(trade) = simd_json::serde::from_owned_value::<TradePayload>(payload.clone()) {
             let ctx = self.strategy_context();
-            for strategy in &self.strategies {
+            self.for_each_strategy("poly_handle_user_trade", |strategy| {
                 strategy.poly_handle_user_trade(Arc::clone(&ctx), listener, &trade);
-            }
+            });
         } else {
             warn!(
                 "[{}] Failed to parse trade payload. Raw: {}",
@@ -626,35 +914,254 @@ impl EventProcessor {
     fn handle_order(&self, listener: Listener, payload: OwnedValue) {
         if let Ok(order) = simd_json::serde::from_owned_value::<OrderPayload>(payload.clone()) {
             let ctx = self.strategy_context();
-            for strategy in &self.strategies {
+            self.for_each_strategy("poly_handle_user_order", |strategy| {
                 strategy.poly_handle_user_order(Arc::clone(&ctx), listener, &order);
-            }
+            });
         } else {
             warn!("[PolyUser] Failed to parse order payload. Raw: {}", payload);
         }
     }
 
-    // fn handle_rate_update(&self, _source: &'static str, kind: RateKind, value: f64) {
-    //     match kind {
-    //         RateKind::UsdcUsdtBinance => self.rates.set_usdc_usdt_binance(value),
-    //         RateKind::UsdUsdtCoinbase => self.rates.set_usd_usdt_coinbase(value),
-    //     }
+    fn handle_market_rollover(
+        &self,
+        crypto: Crypto,
+        previous: Option<MarketConfig>,
+        next: MarketConfig,
+    ) {
+        info!(
+            "[Rollover] {} market: {} -> {}",
+            crypto,
+            previous.as_ref().map_or("none", |m| m.name.as_str()),
+            next.name
+        );
+        self.poly_state.up_down_markets.insert(crypto, next.clone());
 
-    //     let ctx = self.strategy_context();
-    //     for strategy in &self.strategies {
-    //         strategy.conversion_handle_rate_update(&ctx, kind, value);
-    //     }
-    // }
+        let ctx = self.strategy_context();
+        self.for_each_strategy("market_handle_rollover", |strategy| {
+            strategy.market_handle_rollover(Arc::clone(&ctx), crypto, previous.as_ref(), &next);
+        });
+    }
 
-    // fn handle_rate_clear(&self, kind: RateKind) {
-    //     match kind {
-    //         RateKind::UsdcUsdtBinance => self.rates.set_usdc_usdt_binance(0.0),
-    //         RateKind::UsdUsdtCoinbase => self.rates.set_usd_usdt_coinbase(0.0),
-    //     }
+    /// Assets of a market that expired or resolved.
+    fn handle_market_closed(&self, asset_ids: Vec<String>) {
+        if asset_ids.is_empty() {
+            return;
+        }
+        self.poly_state
+            .up_down_markets
+            .retain(|_, market| !asset_ids.contains(&market.yes_token_id));
+        let ctx = self.strategy_context();
+        self.for_each_strategy("on_market_closed", |strategy| {
+            strategy.on_market_closed(Arc::clone(&ctx), &asset_ids);
+        });
+    }
 
-    //     let ctx = self.strategy_context();
-    //     for strategy in &self.strategies {
-    //         strategy.conversion_handle_rate_clear(&ctx, kind);
-    //     }
-    // }
+    fn handle_market_disconnected(&self, asset_ids: Vec<String>) {
+        let stale = asset_ids
+            .iter()
+            .filter(|id| {
+                self.poly_state
+                    .mark_book_stale(id.as_str(), StaleReason::Disconnected)
+            })
+            .count();
+        warn!(
+            "[PolyMarket] {} books stale until the reconnect snapshot arrives.",
+            stale
+        );
+    }
+
+    fn handle_rate_update(&self, source: &'static str, kind: RateKind, value: f64) {
+        debug!("[{}] {:?} = {}", source, kind, value);
+        self.app_state.rates.set(kind, value);
+        self.app_state.refresh_usd_rates();
+
+        let ctx = self.strategy_context();
+        self.for_each_strategy("conversion_handle_rate_update", |strategy| {
+            strategy.conversion_handle_rate_update(Arc::clone(&ctx), kind, value);
+        });
+    }
+
+    fn handle_rate_clear(&self, kind: RateKind) {
+        self.app_state.rates.clear(kind);
+        self.app_state.refresh_usd_rates();
+
+        let ctx = self.strategy_context();
+        self.for_each_strategy("conversion_handle_rate_clear", |strategy| {
+            strategy.conversion_handle_rate_clear(Arc::clone(&ctx), kind);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Blocks on the first rate update and records whether order placement was paused
+    /// when each update arrived.
+    #[derive(Default)]
+    struct SlowRateHandler(Mutex<Vec<bool>>);
+
+    impl Strategy for SlowRateHandler {
+        fn name(&self) -> &'static str {
+            "SlowRateHandler"
+        }
+
+        fn conversion_handle_rate_update(
+            &self,
+            ctx: Arc<StrategyContext>,
+            _kind: RateKind,
+            _value: f64,
+        ) {
+            let first = {
+                let mut seen = self.0.lock().unwrap();
+                seen.push(ctx.poly_state.is_processing_lagging());
+                seen.len() == 1
+            };
+            if first {
+                std::thread::sleep(Duration::from_millis(EVENT_LAG_THRESHOLD_MS * 2));
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn lag_flag_clears_once_the_lane_drains() {
+        let handler = Arc::new(SlowRateHandler::default());
+        let poly_state = Arc::new(PolyMarketState::default());
+        let events = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            vec![handler.clone() as Arc<dyn Strategy>],
+            1,
+        );
+        for value in [1.0, 2.0] {
+            events
+                .send(SocketEvent::Rate {
+                    source: "test",
+                    kind: RateKind::UsdUsdtCoinbase,
+                    value,
+                })
+                .unwrap();
+        }
+        for _ in 0..100 {
+            if events.pending() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The second update waited behind the slow first one and paused placement,
+        // which resumes with the lane idle rather than on some later event.
+        assert_eq!(*handler.0.lock().unwrap(), vec![false, true]);
+        assert!(!poly_state.is_processing_lagging());
+    }
+
+    /// Records the market lifecycle callbacks it receives.
+    #[derive(Default)]
+    struct LifecycleRecorder(Mutex<Vec<String>>);
+
+    impl Strategy for LifecycleRecorder {
+        fn name(&self) -> &'static str {
+            "LifecycleRecorder"
+        }
+
+        fn on_market_added(&self, _ctx: Arc<StrategyContext>, asset_ids: &[String]) {
+            let event = format!("added {}", asset_ids.join(","));
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_market_closed(&self, _ctx: Arc<StrategyContext>, asset_ids: &[String]) {
+            let event = format!("closed {}", asset_ids.join(","));
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn resolution_closes_the_market_for_strategies() {
+        let recorder = Arc::new(LifecycleRecorder::default());
+        let poly_state = Arc::new(PolyMarketState::default());
+        let metadata: Arc<crate::marketmaking::poly_market_struct::Market> =
+            Arc::new(serde_json::from_value(serde_json::json!({ "conditionId": "0xa" })).unwrap());
+        for asset_id in ["1", "2"] {
+            poly_state
+                .markets
+                .insert(asset_id.to_string(), Arc::clone(&metadata));
+        }
+        poly_state.up_down_markets.insert(
+            Crypto::BTC,
+            MarketConfig {
+                name: "bitcoin-up-or-down".to_string(),
+                crypto: Crypto::BTC,
+                kind: crate::exchange_listeners::autodiscover_markets::MarketKind::UpOrDown,
+                yes_token_id: "1".to_string(),
+                no_token_id: "2".to_string(),
+                end_time_et: "2025-10-17 16:00:00".to_string(),
+                binance_symbol: "BTCUSDT".to_string(),
+                strike_price: 100_000.0,
+                metadata: None,
+            },
+        );
+        let events = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            vec![recorder.clone() as Arc<dyn Strategy>],
+            4,
+        );
+        let market_frame = |payload: &str| SocketEvent::Market {
+            listener: Listener::PolyMarketLegacy,
+            payload: payload.as_bytes().to_vec(),
+        };
+        events
+            .send(SocketEvent::MarketAdded {
+                asset_ids: vec!["1".to_string(), "2".to_string()],
+            })
+            .unwrap();
+        // Frames of the market itself do not close it.
+        events
+            .send(market_frame(
+                r#"{"event_type":"last_trade_price","market":"0xa","asset_id":"1"}"#,
+            ))
+            .unwrap();
+        events
+            .send(market_frame(
+                r#"{"event_type":"market_resolved","market":"0xa","assets_ids":["1","2"],"winning_asset_id":"1"}"#,
+            ))
+            .unwrap();
+        for _ in 0..100 {
+            if recorder.0.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The addition goes to the shard of the market's frames, ahead of them.
+        assert_eq!(*recorder.0.lock().unwrap(), vec!["added 1,2", "closed 1,2"]);
+        assert!(poly_state.up_down_markets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn paused_strategies_still_get_events() {
+        let recorder = Arc::new(LifecycleRecorder::default());
+        let poly_state = Arc::new(PolyMarketState::default());
+        poly_state.pause_strategy("LifecycleRecorder");
+        let events = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            vec![recorder.clone() as Arc<dyn Strategy>],
+            1,
+        );
+        events
+            .send(SocketEvent::MarketAdded {
+                asset_ids: vec!["1".to_string()],
+            })
+            .unwrap();
+        for _ in 0..100 {
+            if !recorder.0.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(*recorder.0.lock().unwrap(), vec!["added 1"]);
+    }
 }
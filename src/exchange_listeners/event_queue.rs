@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use super::event_processor::SocketEvent;
+use super::event_routing;
+use crate::exchange_listeners::{Crypto, Exchange, Instrument};
+use crate::metrics::{Counter, METRICS};
+
+/// What `EventQueue::push` did with an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushOutcome {
+    Queued,
+    /// Merged into an event already queued for the same instrument.
+    Coalesced,
+    /// Dropped because the queue was full and the event is superseded by the next one.
+    Dropped,
+    /// A market frame dropped because the queue was full. The books of these assets
+    /// missed an update and must be resynced.
+    Overflowed {
+        asset_ids: Vec<String>,
+    },
+}
+
+struct Queued {
+    enqueued: Instant,
+    event: SocketEvent,
+}
+
+/// Queue of one processor lane. Consecutive prices and L2 deltas of the same instrument
+/// are coalesced. At `capacity`, top-of-book prices are dropped, since the next one
+/// supersedes them, L2 deltas are merged into the latest queued delta of their
+/// instrument, and Polymarket price changes are dropped so their books get resynced.
+/// Book snapshots, user events and control events are kept regardless: they are rare,
+/// and losing a fill or order update would corrupt state.
+pub struct EventQueue {
+    capacity: usize,
+    queue: Mutex<VecDeque<Queued>>,
+    notify: Notify,
+    /// Events queued or being handled.
+    pending: AtomicUsize,
+    closed: AtomicBool,
+    coalesced: Arc<Counter>,
+    dropped: Arc<Counter>,
+}
+
+impl EventQueue {
+    pub fn new(lane: &str, capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            queue: Mutex::new(VecDeque::new()),
+            notify: Notify::new(),
+            pending: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            coalesced: METRICS.events_coalesced_total.with(&[lane]),
+            dropped: METRICS.events_dropped_total.with(&[lane]),
+        }
+    }
+
+    /// Queues `event`, handing it back if the worker of this queue has stopped.
+    pub fn push(&self, event: SocketEvent) -> Result<PushOutcome, SocketEvent> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(event);
+        }
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let event = match queue.back_mut() {
+            Some(back) => match coalesce(&mut back.event, event) {
+                Ok(()) => {
+                    self.coalesced.inc();
+                    return Ok(PushOutcome::Coalesced);
+                }
+                Err(event) => event,
+            },
+            None => event,
+        };
+        let event = if queue.len() >= self.capacity {
+            match self.shed(&mut queue, event) {
+                Ok(outcome) => return Ok(outcome),
+                Err(event) => event,
+            }
+        } else {
+            event
+        };
+        queue.push_back(Queued {
+            enqueued: Instant::now(),
+            event,
+        });
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        drop(queue);
+        self.notify.notify_one();
+        Ok(PushOutcome::Queued)
+    }
+
+    /// Disposes of `event` without growing a full queue, handing it back if it has to be
+    /// queued anyway.
+    fn shed(
+        &self,
+        queue: &mut VecDeque<Queued>,
+        event: SocketEvent,
+    ) -> Result<PushOutcome, SocketEvent> {
+        match event {
+            SocketEvent::Price { .. } => {
+                self.dropped.inc();
+                Ok(PushOutcome::Dropped)
+            }
+            SocketEvent::Market { ref payload, .. }
+                if event_routing::json_string_field(payload, "event_type") != Some("book") =>
+            {
+                self.dropped.inc();
+                Ok(PushOutcome::Overflowed {
+                    asset_ids: event_routing::asset_ids(payload),
+                })
+            }
+            SocketEvent::L2Update { .. } => merge_into_latest(queue, event).map(|()| {
+                self.coalesced.inc();
+                PushOutcome::Coalesced
+            }),
+            event => Err(event),
+        }
+    }
+
+    /// Next event with the time it waited in the queue.
+    pub async fn pop(&self) -> (SocketEvent, Duration) {
+        loop {
+            let next = self
+                .queue
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .pop_front();
+            if let Some(queued) = next {
+                return (queued.event, queued.enqueued.elapsed());
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    /// Marks the last popped event as handled.
+    pub fn done(&self) {
+        self.pending.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Refuses further events. Events already queued are still handed out.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+}
+
+/// Closes the queue when dropped, held by the worker that drains it.
+pub struct CloseOnDrop(pub Arc<EventQueue>);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Instrument a crypto feed event updates.
+fn instrument_of(event: &SocketEvent) -> Option<(Exchange, Instrument, Crypto)> {
+    match event {
+        SocketEvent::Price {
+            exchange,
+            instrument,
+            crypto,
+            ..
+        }
+        | SocketEvent::L2Snapshot {
+            exchange,
+            instrument,
+            crypto,
+            ..
+        }
+        | SocketEvent::L2Update {
+            exchange,
+            instrument,
+            crypto,
+            ..
+        }
+        | SocketEvent::ClearPrice {
+            exchange,
+            instrument,
+            crypto,
+        } => Some((*exchange, *instrument, *crypto)),
+        _ => None,
+    }
+}
+
+/// Merges the L2 delta `event` into the latest queued delta of its instrument, unless a
+/// snapshot or another event of that instrument was queued after it.
+fn merge_into_latest(queue: &mut VecDeque<Queued>, event: SocketEvent) -> Result<(), SocketEvent> {
+    let instrument = instrument_of(&event);
+    let latest = queue
+        .iter_mut()
+        .rev()
+        .find(|queued| instrument_of(&queued.event) == instrument);
+    match latest {
+        Some(queued) => coalesce(&mut queued.event, event),
+        None => Err(event),
+    }
+}
+
+/// Merges `event` into `back` if both update the same instrument, handing it back
+/// otherwise. L2 deltas are concatenated, which applies them in the same order.
+fn coalesce(back: &mut SocketEvent, event: SocketEvent) -> Result<(), SocketEvent> {
+    match (back, event) {
+        (
+            SocketEvent::Price {
+                exchange,
+                instrument,
+                crypto,
+                depth,
+                price_update,
+            },
+            SocketEvent::Price {
+                exchange: next_exchange,
+                instrument: next_instrument,
+                crypto: next_crypto,
+                depth: next_depth,
+                price_update: next_update,
+            },
+        ) if (*exchange, *instrument, *crypto, *depth)
+            == (next_exchange, next_instrument, next_crypto, next_depth) =>
+        {
+            *price_update = next_update;
+            Ok(())
+        }
+        (
+            SocketEvent::L2Update {
+                exchange,
+                instrument,
+                crypto,
+                bids,
+                asks,
+            },
+            SocketEvent::L2Update {
+                exchange: next_exchange,
+                instrument: next_instrument,
+                crypto: next_crypto,
+                bids: next_bids,
+                asks: next_asks,
+            },
+        ) if (*exchange, *instrument, *crypto) == (next_exchange, next_instrument, next_crypto) => {
+            bids.extend(next_bids);
+            asks.extend(next_asks);
+            Ok(())
+        }
+        (_, event) => Err(event),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::orderbooks::OrderbookLevel;
+    use crate::exchange_listeners::{Crypto, Exchange, Instrument};
+
+    fn l2_update(exchange: Exchange, price: f64) -> SocketEvent {
+        SocketEvent::L2Update {
+            exchange,
+            instrument: Instrument::Perpetual,
+            crypto: Crypto::BTC,
+            bids: vec![OrderbookLevel::new(price, 1.0)],
+            asks: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn coalesces_consecutive_deltas_and_keeps_critical_events() {
+        let queue = EventQueue::new("test", 1);
+        assert_eq!(
+            queue.push(l2_update(Exchange::Deribit, 1.0)).ok(),
+            Some(PushOutcome::Queued)
+        );
+        assert_eq!(
+            queue.push(l2_update(Exchange::Deribit, 2.0)).ok(),
+            Some(PushOutcome::Coalesced)
+        );
+        // A different instrument is not merged, and deltas are kept past capacity.
+        assert_eq!(
+            queue.push(l2_update(Exchange::Binance, 3.0)).ok(),
+            Some(PushOutcome::Queued)
+        );
+        assert_eq!(queue.pending(), 2);
+
+        let (event, _) = queue.pop().await;
+        match event {
+            SocketEvent::L2Update { bids, .. } => {
+                let prices: Vec<f64> = bids.iter().map(|level| level.price).collect();
+                assert_eq!(prices, vec![1.0, 2.0]);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        queue.done();
+        assert_eq!(queue.pending(), 1);
+
+        // A closed queue refuses new events but still hands out the queued ones.
+        queue.close();
+        assert!(queue.push(l2_update(Exchange::Deribit, 4.0)).is_err());
+        let (event, _) = queue.pop().await;
+        assert!(matches!(
+            event,
+            SocketEvent::L2Update {
+                exchange: Exchange::Binance,
+                ..
+            }
+        ));
+    }
+
+    fn market(payload: &str) -> SocketEvent {
+        SocketEvent::Market {
+            listener: crate::exchange_listeners::poly_models::Listener::PolyMarketLegacy,
+            payload: payload.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn full_queue_sheds_market_frames_and_merges_deltas() {
+        let queue = EventQueue::new("test", 2);
+        queue.push(l2_update(Exchange::Deribit, 1.0)).unwrap();
+        queue
+            .push(market(r#"{"event_type":"book","asset_id":"1"}"#))
+            .unwrap();
+
+        // Price changes are dropped with the assets whose books missed them.
+        assert_eq!(
+            queue
+                .push(market(
+                    r#"{"event_type":"price_change","price_changes":[{"asset_id":"1"},{"asset_id":"2"}]}"#
+                ))
+                .ok(),
+            Some(PushOutcome::Overflowed {
+                asset_ids: vec!["1".to_string(), "2".to_string()]
+            })
+        );
+        // Snapshots repair books, so they are kept.
+        assert_eq!(
+            queue
+                .push(market(r#"{"event_type":"book","asset_id":"2"}"#))
+                .ok(),
+            Some(PushOutcome::Queued)
+        );
+        // A delta joins the latest delta of its instrument, even behind other events.
+        assert_eq!(
+            queue.push(l2_update(Exchange::Deribit, 2.0)).ok(),
+            Some(PushOutcome::Coalesced)
+        );
+        assert_eq!(queue.pending(), 3);
+
+        // After a snapshot of the instrument, deltas are kept in order instead.
+        queue
+            .push(SocketEvent::L2Snapshot {
+                exchange: Exchange::Deribit,
+                instrument: Instrument::Perpetual,
+                crypto: Crypto::BTC,
+                bids: Vec::new(),
+                asks: Vec::new(),
+            })
+            .unwrap();
+        assert_eq!(
+            queue.push(l2_update(Exchange::Deribit, 3.0)).ok(),
+            Some(PushOutcome::Queued)
+        );
+        assert_eq!(queue.pending(), 5);
+    }
+}
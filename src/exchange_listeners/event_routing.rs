@@ -0,0 +1,87 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Value of the first `"field":"..."` string in a JSON frame, found by scanning rather
+/// than parsing the whole message.
+pub fn json_string_field<'a>(payload: &'a [u8], field: &str) -> Option<&'a str> {
+    let text = std::str::from_utf8(payload).ok()?;
+    let key = format!("\"{}\":", field);
+    let start = text.find(&key)? + key.len();
+    let rest = text[start..].trim_start().strip_prefix('"')?;
+    Some(&rest[..rest.find('"')?])
+}
+
+/// Condition ID a market frame belongs to. Both outcome tokens of a market share it.
+pub fn market_key(payload: &[u8]) -> Option<&str> {
+    json_string_field(payload, "market")
+}
+
+/// Every distinct `asset_id` in a market frame, in order of appearance.
+pub fn asset_ids(payload: &[u8]) -> Vec<String> {
+    let Ok(text) = std::str::from_utf8(payload) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = Vec::new();
+    for (i, _) in text.match_indices("\"asset_id\":") {
+        if let Some(id) = json_string_field(&payload[i..], "asset_id") {
+            if !ids.iter().any(|known| known == id) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+/// Shard in `0..shards` that owns `key`. Stable for the life of the process.
+pub fn shard_of(key: impl Hash, shards: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % shards.max(1) as u64) as usize
+}
+
+/// Splits a JSON array frame that spans several markets into one frame per element,
+/// so each element can go to the shard of its own market. Frames of a single market
+/// are left whole (`None`).
+pub fn split_batch(payload: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let first = payload.iter().find(|b| !b.is_ascii_whitespace())?;
+    if *first != b'[' {
+        return None;
+    }
+    let text = std::str::from_utf8(payload).ok()?;
+    let first_market = market_key(payload)?;
+    let single_market = text
+        .match_indices("\"market\":")
+        .filter_map(|(i, _)| market_key(&payload[i..]))
+        .all(|market| market == first_market);
+    if single_market {
+        return None;
+    }
+    let elements: Vec<serde_json::Value> = serde_json::from_slice(payload).ok()?;
+    elements
+        .iter()
+        .map(|element| serde_json::to_vec(element).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_only_batches_spanning_markets() {
+        let single = br#"[{"event_type":"book","market":"0xa","asset_id":"1"},{"event_type":"book","market":"0xa","asset_id":"2"}]"#;
+        assert_eq!(market_key(single), Some("0xa"));
+        assert!(split_batch(single).is_none());
+
+        let mixed = br#"[{"event_type":"book","market":"0xa","asset_id":"1"},{"event_type":"book","market": "0xb","asset_id":"3"}]"#;
+        let frames = split_batch(mixed).expect("batch spans two markets");
+        assert_eq!(frames.len(), 2);
+        assert_eq!(market_key(&frames[1]), Some("0xb"));
+        assert_eq!(json_string_field(&frames[1], "asset_id"), Some("3"));
+        assert_eq!(asset_ids(single), vec!["1", "2"]);
+        assert_eq!(asset_ids(&frames[1]), vec!["3"]);
+
+        assert!(split_batch(br#"{"event_type":"book","market":"0xa"}"#).is_none());
+        assert_eq!(shard_of("0xa", 4), shard_of("0xa", 4));
+    }
+}
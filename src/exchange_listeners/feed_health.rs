@@ -0,0 +1,280 @@
+use atomic_float::AtomicF64;
+use chrono::DateTime;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+/// Length of the window over which message rates are measured.
+const RATE_WINDOW_MS: i64 = 10_000;
+/// EWMA weight of one latency sample.
+const LATENCY_ALPHA: f64 = 0.05;
+/// Fields that carry the exchange's event time, in order of preference.
+const TIMESTAMP_KEYS: [&str; 7] = [
+    "\"E\":",
+    "\"T\":",
+    "\"ts\":",
+    "\"microtimestamp\":",
+    "\"timestamp\":",
+    "\"time\":",
+    "\"creationTime\":",
+];
+
+lazy_static! {
+    /// Health of every websocket feed, keyed by listener name.
+    pub static ref FEED_HEALTH: HealthRegistry = HealthRegistry::default();
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Counters of a single feed, updated lock-free from its listener loop.
+#[derive(Debug, Default)]
+pub struct FeedHealth {
+    connected: AtomicBool,
+    connections: AtomicU64,
+    messages: AtomicU64,
+    /// Local receive time of the last message, epoch ms (0 = never).
+    last_message_ms: AtomicI64,
+    /// EWMA of local receive time minus exchange event time.
+    latency_ms: AtomicF64,
+    latency_samples: AtomicU64,
+    window_start_ms: AtomicI64,
+    window_messages: AtomicU64,
+    /// Messages per second over the last complete window.
+    message_rate: AtomicF64,
+}
+
+impl FeedHealth {
+    pub fn on_connected(&self) {
+        self.connected.store(true, Ordering::Release);
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn on_disconnected(&self) {
+        self.connected.store(false, Ordering::Release);
+    }
+
+    /// Records a received frame, taking the latency from its exchange timestamp if any.
+    pub fn on_message(&self, payload: &[u8]) {
+        let now = now_ms();
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.last_message_ms.store(now, Ordering::Release);
+
+        let window_start = self.window_start_ms.load(Ordering::Acquire);
+        if now - window_start >= RATE_WINDOW_MS {
+            let count = self.window_messages.swap(1, Ordering::AcqRel);
+            self.window_start_ms.store(now, Ordering::Release);
+            if window_start > 0 {
+                let seconds = (now - window_start) as f64 / 1000.0;
+                self.message_rate
+                    .store(count as f64 / seconds, Ordering::Release);
+            }
+        } else {
+            self.window_messages.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(exchange_ms) = exchange_timestamp_ms(payload) {
+            let sample = (now - exchange_ms) as f64;
+            let latency = if self.latency_samples.fetch_add(1, Ordering::Relaxed) == 0 {
+                sample
+            } else {
+                let previous = self.latency_ms.load(Ordering::Acquire);
+                previous + LATENCY_ALPHA * (sample - previous)
+            };
+            self.latency_ms.store(latency, Ordering::Release);
+        }
+    }
+
+    fn status(&self, name: &str) -> FeedStatus {
+        let last = self.last_message_ms.load(Ordering::Acquire);
+        FeedStatus {
+            name: name.to_string(),
+            connected: self.connected.load(Ordering::Acquire),
+            reconnects: self.connections.load(Ordering::Relaxed).saturating_sub(1),
+            messages: self.messages.load(Ordering::Relaxed),
+            last_message_age: (last > 0)
+                .then(|| Duration::from_millis((now_ms() - last).max(0) as u64)),
+            message_rate: self.message_rate.load(Ordering::Acquire),
+            latency_ms: (self.latency_samples.load(Ordering::Relaxed) > 0)
+                .then(|| self.latency_ms.load(Ordering::Acquire)),
+        }
+    }
+}
+
+/// Point-in-time view of a feed.
+#[derive(Debug, Clone)]
+pub struct FeedStatus {
+    pub name: String,
+    pub connected: bool,
+    pub reconnects: u64,
+    pub messages: u64,
+    pub last_message_age: Option<Duration>,
+    pub message_rate: f64,
+    /// Exchange-to-local latency; `None` for feeds without event timestamps.
+    pub latency_ms: Option<f64>,
+}
+
+impl FeedStatus {
+    /// Connected and heard from within `max_age`.
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.connected && self.last_message_age.is_some_and(|age| age <= max_age)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HealthRegistry {
+    feeds: DashMap<String, Arc<FeedHealth>>,
+}
+
+impl HealthRegistry {
+    /// Counters of feed `name`, registered on first use.
+    pub fn feed(&self, name: &str) -> Arc<FeedHealth> {
+        if let Some(feed) = self.feeds.get(name) {
+            return Arc::clone(feed.value());
+        }
+        Arc::clone(self.feeds.entry(name.to_string()).or_default().value())
+    }
+
+    /// Forgets a feed whose listener stopped for good.
+    pub fn remove(&self, name: &str) {
+        self.feeds.remove(name);
+    }
+
+    pub fn status(&self, name: &str) -> Option<FeedStatus> {
+        self.feeds.get(name).map(|feed| feed.status(name))
+    }
+
+    pub fn statuses(&self) -> Vec<FeedStatus> {
+        let mut statuses: Vec<FeedStatus> = self
+            .feeds
+            .iter()
+            .map(|feed| feed.status(feed.key()))
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// Whether feed `name` has a live connection. A feed that never connected counts as
+    /// disconnected, so gates on it stay closed until its listener is up.
+    pub fn is_connected(&self, name: &str) -> bool {
+        self.status(name).is_some_and(|status| status.connected)
+    }
+}
+
+/// Logs every feed's health each `every`, as warnings for feeds that went quiet.
+pub async fn log_feed_health(every: Duration) {
+    let mut interval = time::interval(every);
+    loop {
+        interval.tick().await;
+        for status in FEED_HEALTH.statuses() {
+            let age = status
+                .last_message_age
+                .map_or("never".to_string(), |age| format!("{}ms", age.as_millis()));
+            let latency = status
+                .latency_ms
+                .map_or("n/a".to_string(), |latency| format!("{:.0}ms", latency));
+            let line = format!(
+                "[Health] {} connected={} reconnects={} msgs={} rate={:.1}/s last={} latency={}",
+                status.name,
+                status.connected,
+                status.reconnects,
+                status.messages,
+                status.message_rate,
+                age,
+                latency
+            );
+            if status.is_fresh(every) {
+                info!("{}", line);
+            } else {
+                warn!("{}", line);
+            }
+        }
+    }
+}
+
+/// Exchange event time of a JSON frame in epoch ms, found by scanning for the usual
+/// timestamp fields rather than parsing the whole message. Numbers are scaled from
+/// seconds, micro- or nanoseconds by magnitude; strings may also be RFC 3339.
+pub fn exchange_timestamp_ms(payload: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(payload).ok()?;
+    TIMESTAMP_KEYS.iter().find_map(|key| {
+        let start = text.find(key)? + key.len();
+        let rest = text[start..].trim_start();
+        let (raw, quoted) = match rest.strip_prefix('"') {
+            Some(quoted) => (&quoted[..quoted.find('"')?], true),
+            None => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(rest.len());
+                (&rest[..end], false)
+            }
+        };
+        match raw.parse::<f64>() {
+            Ok(value) => scale_to_ms(value),
+            Err(_) if quoted => DateTime::parse_from_rfc3339(raw)
+                .ok()
+                .map(|t| t.timestamp_millis()),
+            Err(_) => None,
+        }
+    })
+}
+
+fn scale_to_ms(value: f64) -> Option<i64> {
+    let ms = if value > 1e17 {
+        value / 1e6
+    } else if value > 1e14 {
+        value / 1e3
+    } else if value > 1e11 {
+        value
+    } else if value > 1e8 {
+        value * 1e3
+    } else {
+        return None;
+    };
+    Some(ms as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_exchange_timestamps() {
+        let ms = 1_760_700_000_123;
+        assert_eq!(
+            exchange_timestamp_ms(br#"{"e":"bookTicker","E":1760700000123,"b":"1"}"#),
+            Some(ms)
+        );
+        assert_eq!(
+            exchange_timestamp_ms(br#"{"event_type":"book","timestamp":"1760700000123"}"#),
+            Some(ms)
+        );
+        assert_eq!(
+            exchange_timestamp_ms(br#"{"type":"ticker","time":"2025-10-17T11:20:00.123Z"}"#),
+            Some(ms)
+        );
+        assert_eq!(
+            exchange_timestamp_ms(br#"{"data":{"microtimestamp":"1760700000123456"}}"#),
+            Some(ms)
+        );
+        assert_eq!(exchange_timestamp_ms(br#"{"type":"pong"}"#), None);
+    }
+
+    #[test]
+    fn feeds_are_connected_only_between_connect_and_disconnect() {
+        let registry = HealthRegistry::default();
+        assert!(!registry.is_connected("user"));
+
+        let feed = registry.feed("user");
+        assert!(!registry.is_connected("user"));
+        feed.on_connected();
+        assert!(registry.is_connected("user"));
+        feed.on_disconnected();
+        assert!(!registry.is_connected("user"));
+    }
+}
@@ -0,0 +1,655 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::US::Eastern;
+use log::{debug, error, info, warn};
+use reqwest::Client;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use tokio::{sync::mpsc::UnboundedSender, time};
+
+use crate::config::MARKET_DISCOVERY_INTERVAL_SECS;
+use crate::exchange_listeners::{
+    autodiscover_markets::{
+        fetch_candle_open, fetch_events_page, outcome_tokens, MarketConfig, MarketKind,
+        EVENTS_PAGE_SIZE,
+    },
+    poly_listeners::SubscriptionCommand,
+    states::PolyMarketState,
+    Crypto,
+};
+
+/// Upper bound on gamma pages scanned per discovery run.
+const MAX_EVENT_PAGES: usize = 20;
+
+/// Recurring crypto market families listed on Polymarket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarketFamily {
+    /// `bitcoin-up-or-down-october-17-3pm-et`
+    HourlyUpOrDown,
+    /// `bitcoin-up-or-down-on-october-17`
+    DailyUpOrDown,
+    /// `bitcoin-above-on-october-17`: one market per strike.
+    Above,
+    /// `bitcoin-price-on-october-17`, weekly and monthly ranges: one market per bucket.
+    PriceRange,
+    /// `what-price-will-bitcoin-hit-in-october`, weekly and monthly: one market per level.
+    PriceHit,
+}
+
+impl MarketFamily {
+    /// Family of a gamma event from its slug, if it is one we understand.
+    pub fn classify(slug: &str) -> Option<Self> {
+        let slug = slug.to_lowercase();
+        if slug.contains("-up-or-down-") {
+            let hourly = slug
+                .strip_suffix("-et")
+                .and_then(|rest| rest.rsplit('-').next())
+                .map(|hour| hour.ends_with("am") || hour.ends_with("pm"))
+                .unwrap_or(false);
+            Some(if hourly {
+                MarketFamily::HourlyUpOrDown
+            } else {
+                MarketFamily::DailyUpOrDown
+            })
+        } else if slug.contains("-above-") {
+            Some(MarketFamily::Above)
+        } else if slug.contains("-price-on-") || slug.contains("-price-range") {
+            Some(MarketFamily::PriceRange)
+        } else if slug.starts_with("what-price-will-") && slug.contains("-hit-") {
+            Some(MarketFamily::PriceHit)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_up_or_down(&self) -> bool {
+        matches!(
+            self,
+            MarketFamily::HourlyUpOrDown | MarketFamily::DailyUpOrDown
+        )
+    }
+
+    /// Length of the period whose open is the strike of an up-or-down market.
+    fn period(&self) -> Option<Duration> {
+        match self {
+            MarketFamily::HourlyUpOrDown => Some(Duration::hours(1)),
+            MarketFamily::DailyUpOrDown => Some(Duration::days(1)),
+            MarketFamily::Above | MarketFamily::PriceRange | MarketFamily::PriceHit => None,
+        }
+    }
+}
+
+/// A market found by `discover_crypto_markets`.
+#[derive(Debug, Clone)]
+pub struct DiscoveredMarket {
+    pub family: MarketFamily,
+    pub event_slug: String,
+    pub config: MarketConfig,
+}
+
+/// Underlying of an event, from the first word of its slug or the one after
+/// `what-price-will-`.
+pub fn underlying_of(slug: &str) -> Option<Crypto> {
+    let slug = slug.to_lowercase();
+    let subject = slug.strip_prefix("what-price-will-").unwrap_or(&slug);
+    match subject.split('-').next()? {
+        "bitcoin" | "btc" => Some(Crypto::BTC),
+        "ethereum" | "eth" => Some(Crypto::ETH),
+        "solana" | "sol" => Some(Crypto::SOL),
+        "xrp" | "ripple" => Some(Crypto::XRP),
+        _ => None,
+    }
+}
+
+/// Parses `$110,000`, `110000`, `110k` or `1.2m`.
+pub fn parse_price(text: &str) -> Option<f64> {
+    let cleaned: String = text
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, '$' | ',' | ' '))
+        .collect();
+    let lower = cleaned.to_lowercase();
+    let (number, multiplier) = if let Some(n) = lower.strip_suffix('k') {
+        (n, 1_000.0)
+    } else if let Some(n) = lower.strip_suffix('m') {
+        (n, 1_000_000.0)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|p| p.is_finite() && *p > 0.0)
+        .map(|p| p * multiplier)
+}
+
+/// First price in `text` that follows a `$`, e.g. the strike in
+/// "Will the price of Bitcoin be above $110,000 on October 17?".
+fn price_after_dollar(text: &str) -> Option<f64> {
+    let (_, rest) = text.split_once('$')?;
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, ',' | '.' | 'k' | 'K')))
+        .unwrap_or(rest.len());
+    parse_price(rest[..end].trim_end_matches('.'))
+}
+
+/// Strike of an "above" market from its group title (`110,000`) or question.
+pub fn parse_above_strike(group_title: Option<&str>, question: Option<&str>) -> Option<f64> {
+    group_title
+        .and_then(|t| parse_price(t.trim_start_matches('>').trim_start_matches('↑')))
+        .or_else(|| question.and_then(price_after_dollar))
+}
+
+/// Level and direction of a "what price will X hit" market from its group title
+/// (`↑ 130,000`, `↓ 100,000`) or question ("Will Bitcoin reach $130,000 in October?").
+pub fn parse_hit(group_title: Option<&str>, question: Option<&str>) -> Option<(f64, bool)> {
+    let from_title = group_title.and_then(|title| {
+        let title = title.trim();
+        let (up, level) = if let Some(level) = title.strip_prefix('↑') {
+            (true, level)
+        } else {
+            (false, title.strip_prefix('↓')?)
+        };
+        Some((parse_price(level)?, up))
+    });
+    from_title.or_else(|| {
+        let question = question?;
+        let lower = question.to_lowercase();
+        let up = if ["reach", "hit", "rise"].iter().any(|w| lower.contains(w)) {
+            true
+        } else if ["dip", "drop", "fall"].iter().any(|w| lower.contains(w)) {
+            false
+        } else {
+            return None;
+        };
+        Some((price_after_dollar(question)?, up))
+    })
+}
+
+/// Bounds of a range bucket: `108,000-110,000`, `<100k`, `>120,000`, `120k+`,
+/// `below $100,000` or `above $120,000`.
+pub fn parse_range(text: &str) -> Option<(Option<f64>, Option<f64>)> {
+    let text = text.trim().to_lowercase();
+    for prefix in ["<", "below", "under", "less than"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return parse_price(rest).map(|upper| (None, Some(upper)));
+        }
+    }
+    for prefix in [">", "above", "over", "more than"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return parse_price(rest).map(|lower| (Some(lower), None));
+        }
+    }
+    if let Some(rest) = text.strip_suffix('+') {
+        return parse_price(rest).map(|lower| (Some(lower), None));
+    }
+    let (low, high) = text
+        .split_once('-')
+        .or_else(|| text.split_once('–'))
+        .or_else(|| text.split_once(" to "))?;
+    let (low, high) = (parse_price(low)?, parse_price(high)?);
+    (low < high).then_some((Some(low), Some(high)))
+}
+
+/// Gamma `endDate` (RFC 3339) in the `MarketConfig::end_time_et` format.
+fn end_time_et(end_date: &str) -> Option<String> {
+    let end = DateTime::parse_from_rfc3339(end_date).ok()?;
+    Some(
+        end.with_timezone(&Eastern)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+    )
+}
+
+/// Turns one gamma event into markets. Up-or-down strikes are left at `0.0` until
+/// `resolve_up_or_down_strikes` sets them. Markets that cannot be parsed are skipped with
+/// a warning.
+pub fn parse_event(event: &Value) -> Vec<DiscoveredMarket> {
+    let Some(slug) = event.get("slug").and_then(Value::as_str) else {
+        return Vec::new();
+    };
+    let (Some(family), Some(crypto)) = (MarketFamily::classify(slug), underlying_of(slug)) else {
+        return Vec::new();
+    };
+    let Some(markets) = event.get("markets").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let event_end = event.get("endDate").and_then(Value::as_str);
+
+    markets
+        .iter()
+        .filter(|market| market.get("closed").and_then(Value::as_bool) != Some(true))
+        .filter_map(
+            |market| match parse_market(family, crypto, market, event_end) {
+                Ok(config) => Some(DiscoveredMarket {
+                    family,
+                    event_slug: slug.to_string(),
+                    config,
+                }),
+                Err(e) => {
+                    warn!("[Discovery] Skipping a market of {}: {:#}", slug, e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+fn parse_market(
+    family: MarketFamily,
+    crypto: Crypto,
+    market: &Value,
+    event_end: Option<&str>,
+) -> Result<MarketConfig> {
+    let name = market
+        .get("slug")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("market has no slug"))?;
+    let tokens = outcome_tokens(market)?.ok_or_else(|| anyhow!("{} lists no tokens", name))?;
+    let (yes, no) = if family.is_up_or_down() {
+        ("up", "down")
+    } else {
+        ("yes", "no")
+    };
+    let token = |outcome: &str| {
+        tokens
+            .iter()
+            .find(|(o, _)| o.eq_ignore_ascii_case(outcome))
+            .map(|(_, token_id)| token_id.clone())
+            .ok_or_else(|| anyhow!("{} has no '{}' outcome", name, outcome))
+    };
+    let (yes_token_id, no_token_id) = (token(yes)?, token(no)?);
+
+    let end_time_et = market
+        .get("endDate")
+        .and_then(Value::as_str)
+        .or(event_end)
+        .and_then(end_time_et)
+        .ok_or_else(|| anyhow!("{} has no valid endDate", name))?;
+
+    let group_title = market.get("groupItemTitle").and_then(Value::as_str);
+    let question = market.get("question").and_then(Value::as_str);
+    let (kind, strike_price) = match family {
+        MarketFamily::HourlyUpOrDown | MarketFamily::DailyUpOrDown => (MarketKind::UpOrDown, 0.0),
+        MarketFamily::Above => {
+            let strike = parse_above_strike(group_title, question)
+                .ok_or_else(|| anyhow!("{} has no parsable strike", name))?;
+            (MarketKind::Above, strike)
+        }
+        MarketFamily::PriceRange => {
+            let (lower, upper) = group_title
+                .and_then(parse_range)
+                .ok_or_else(|| anyhow!("{} has no parsable range", name))?;
+            (MarketKind::Range { lower, upper }, 0.0)
+        }
+        MarketFamily::PriceHit => {
+            let (level, up) = parse_hit(group_title, question)
+                .ok_or_else(|| anyhow!("{} has no parsable level", name))?;
+            (MarketKind::Hit { up }, level)
+        }
+    };
+
+    Ok(MarketConfig {
+        name: name.to_string(),
+        crypto,
+        kind,
+        yes_token_id,
+        no_token_id,
+        end_time_et,
+        binance_symbol: format!("{}USDT", crypto),
+        strike_price,
+        metadata: serde_json::from_value(market.clone()).ok().map(Arc::new),
+    })
+}
+
+/// Sets the strike of up-or-down markets to the Binance open of their period. Those
+/// whose period has not opened yet, or whose open cannot be fetched, are left out until
+/// a later run knows their strike.
+pub async fn resolve_up_or_down_strikes(
+    client: &Client,
+    markets: Vec<DiscoveredMarket>,
+) -> Vec<DiscoveredMarket> {
+    let now = Utc::now();
+    let mut resolved = Vec::with_capacity(markets.len());
+    for mut market in markets {
+        let Some(period) = market.family.period() else {
+            resolved.push(market);
+            continue;
+        };
+        let Some(start) = market.config.end_time_utc().map(|end| end - period) else {
+            continue;
+        };
+        if start > now {
+            debug!("[Discovery] {} has not opened yet", market.config.name);
+            continue;
+        }
+        match fetch_candle_open(
+            client,
+            &market.config.binance_symbol,
+            "1m",
+            start.timestamp_millis(),
+        )
+        .await
+        {
+            Ok(open) => {
+                market.config.strike_price = open;
+                resolved.push(market);
+            }
+            Err(e) => warn!(
+                "[Discovery] No strike yet for {}: {:#}",
+                market.config.name, e
+            ),
+        }
+    }
+    resolved
+}
+
+/// Scans active crypto events on gamma and returns the markets of `families` on `cryptos`.
+pub async fn discover_crypto_markets(
+    client: &Client,
+    cryptos: &[Crypto],
+    families: &[MarketFamily],
+) -> Result<Vec<DiscoveredMarket>> {
+    let mut discovered = Vec::new();
+    let mut offset = 0usize;
+    for _ in 0..MAX_EVENT_PAGES {
+        let events = fetch_events_page(client, offset, "&tag_slug=crypto").await?;
+        discovered.extend(
+            events
+                .iter()
+                .flat_map(parse_event)
+                .filter(|m| families.contains(&m.family) && cryptos.contains(&m.config.crypto)),
+        );
+        offset += events.len();
+        if events.len() < EVENTS_PAGE_SIZE {
+            break;
+        }
+    }
+    Ok(resolve_up_or_down_strikes(client, discovered).await)
+}
+
+/// Tokens of the markets listed by the latest discovery run.
+#[derive(Debug, Default)]
+pub struct DiscoveredTokens(HashSet<String>);
+
+impl DiscoveredTokens {
+    /// Replaces the known tokens with those of `markets` and returns the subscription
+    /// changes: tokens of new markets, then tokens of markets no longer listed.
+    pub fn update(&mut self, markets: &[DiscoveredMarket]) -> Vec<SubscriptionCommand> {
+        let current: HashSet<String> = markets
+            .iter()
+            .flat_map(|m| [m.config.yes_token_id.clone(), m.config.no_token_id.clone()])
+            .collect();
+        let mut added: Vec<String> = current.difference(&self.0).cloned().collect();
+        let mut removed: Vec<String> = self.0.difference(&current).cloned().collect();
+        added.sort();
+        removed.sort();
+        self.0 = current;
+
+        let mut commands = Vec::new();
+        if !added.is_empty() {
+            commands.push(SubscriptionCommand::Subscribe(added));
+        }
+        if !removed.is_empty() {
+            commands.push(SubscriptionCommand::Unsubscribe(removed));
+        }
+        commands
+    }
+}
+
+/// Rediscovers the non-hourly crypto markets every `MARKET_DISCOVERY_INTERVAL_SECS`,
+/// registers their metadata and subscribes new markets while unsubscribing those that
+/// are no longer listed. A failed run leaves the subscriptions as they are.
+pub async fn run_crypto_market_discovery(
+    cryptos: &'static [Crypto],
+    poly_state: Arc<PolyMarketState>,
+    subscriptions: UnboundedSender<SubscriptionCommand>,
+) {
+    let families = [
+        MarketFamily::DailyUpOrDown,
+        MarketFamily::Above,
+        MarketFamily::PriceRange,
+        MarketFamily::PriceHit,
+    ];
+    let client = Client::new();
+    let mut known = DiscoveredTokens::default();
+    let mut interval = time::interval(StdDuration::from_secs(MARKET_DISCOVERY_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let markets = match discover_crypto_markets(&client, cryptos, &families).await {
+            Ok(markets) => markets,
+            Err(e) => {
+                error!("[Discovery] Crypto market discovery failed: {:#}", e);
+                continue;
+            }
+        };
+        for market in &markets {
+            debug!(
+                "[Discovery] {} ({:?}) {} {:?} strike {} ends {}",
+                market.event_slug,
+                market.family,
+                market.config.crypto,
+                market.config.kind,
+                market.config.strike_price,
+                market.config.end_time_et
+            );
+            poly_state.register_market(&market.config);
+        }
+        info!("[Discovery] {} crypto markets listed.", markets.len());
+        for command in known.update(&markets) {
+            if subscriptions.send(command).is_err() {
+                error!("[Discovery] Subscription manager is gone. Stopping discovery.");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn classifies_event_slugs() {
+        let cases = [
+            (
+                "bitcoin-up-or-down-october-17-3pm-et",
+                Some(MarketFamily::HourlyUpOrDown),
+            ),
+            (
+                "ethereum-up-or-down-on-october-17",
+                Some(MarketFamily::DailyUpOrDown),
+            ),
+            ("bitcoin-above-on-october-17", Some(MarketFamily::Above)),
+            ("solana-price-on-october-17", Some(MarketFamily::PriceRange)),
+            (
+                "what-price-will-bitcoin-hit-in-october",
+                Some(MarketFamily::PriceHit),
+            ),
+            (
+                "what-price-will-ethereum-hit-october-13-19",
+                Some(MarketFamily::PriceHit),
+            ),
+            ("bitcoin-etf-flows-on-october-17", None),
+        ];
+        for (slug, family) in cases {
+            assert_eq!(MarketFamily::classify(slug), family, "{}", slug);
+        }
+        assert_eq!(underlying_of("xrp-above-on-october-17"), Some(Crypto::XRP));
+        assert_eq!(
+            underlying_of("what-price-will-solana-hit-in-october"),
+            Some(Crypto::SOL)
+        );
+    }
+
+    #[test]
+    fn parses_strikes_and_ranges() {
+        assert_eq!(parse_price("$110,000"), Some(110_000.0));
+        assert_eq!(parse_price("2.5k"), Some(2_500.0));
+        assert_eq!(
+            parse_above_strike(None, Some("Will Bitcoin be above $108,500 on October 17?")),
+            Some(108_500.0)
+        );
+        assert_eq!(
+            parse_range("108,000-110,000"),
+            Some((Some(108_000.0), Some(110_000.0)))
+        );
+        assert_eq!(parse_range("<100k"), Some((None, Some(100_000.0))));
+        assert_eq!(parse_range("120,000+"), Some((Some(120_000.0), None)));
+        assert_eq!(parse_range("110,000-108,000"), None);
+        assert_eq!(parse_hit(Some("↑ 130,000"), None), Some((130_000.0, true)));
+        assert_eq!(parse_hit(Some("↓ 100k"), None), Some((100_000.0, false)));
+        assert_eq!(
+            parse_hit(None, Some("Will Bitcoin dip to $95,000 in October?")),
+            Some((95_000.0, false))
+        );
+        assert_eq!(parse_hit(Some("130,000"), None), None);
+    }
+
+    #[test]
+    fn parses_above_ladder_event() {
+        let event = json!({
+            "slug": "bitcoin-above-on-october-17",
+            "endDate": "2025-10-17T16:00:00Z",
+            "markets": [
+                {
+                    "slug": "bitcoin-above-110k-on-october-17",
+                    "groupItemTitle": "110,000",
+                    "outcomes": "[\"Yes\", \"No\"]",
+                    "clobTokenIds": "[\"1\", \"2\"]"
+                },
+                {
+                    "slug": "bitcoin-above-100k-on-october-17",
+                    "groupItemTitle": "100,000",
+                    "closed": true,
+                    "outcomes": "[\"Yes\", \"No\"]",
+                    "clobTokenIds": "[\"3\", \"4\"]"
+                }
+            ]
+        });
+        let markets = parse_event(&event);
+        assert_eq!(markets.len(), 1);
+        let config = &markets[0].config;
+        assert_eq!(config.crypto, Crypto::BTC);
+        assert_eq!(config.kind, MarketKind::Above);
+        assert_eq!(config.strike_price, 110_000.0);
+        assert_eq!(
+            (config.yes_token_id.as_str(), config.no_token_id.as_str()),
+            ("1", "2")
+        );
+        assert_eq!(config.end_time_et, "2025-10-17 12:00:00");
+    }
+
+    #[test]
+    fn parses_hit_ladder_event() {
+        let event = json!({
+            "slug": "what-price-will-bitcoin-hit-in-october",
+            "endDate": "2025-11-01T04:00:00Z",
+            "markets": [
+                {
+                    "slug": "will-bitcoin-reach-130k-in-october",
+                    "groupItemTitle": "↑ 130,000",
+                    "outcomes": "[\"Yes\", \"No\"]",
+                    "clobTokenIds": "[\"1\", \"2\"]"
+                },
+                {
+                    "slug": "will-bitcoin-dip-to-100k-in-october",
+                    "question": "Will Bitcoin dip to $100,000 in October?",
+                    "outcomes": "[\"Yes\", \"No\"]",
+                    "clobTokenIds": "[\"3\", \"4\"]"
+                }
+            ]
+        });
+        let levels: Vec<_> = parse_event(&event)
+            .iter()
+            .map(|m| (m.family, m.config.kind, m.config.strike_price))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                (
+                    MarketFamily::PriceHit,
+                    MarketKind::Hit { up: true },
+                    130_000.0
+                ),
+                (
+                    MarketFamily::PriceHit,
+                    MarketKind::Hit { up: false },
+                    100_000.0
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn up_or_down_markets_wait_for_their_period_to_open() {
+        // Ends in two days, so the day whose open is the strike starts tomorrow.
+        let end = (Utc::now() + Duration::days(2)).to_rfc3339();
+        let event = |slug: &str, outcomes: &str| {
+            json!({
+                "slug": slug,
+                "endDate": end,
+                "markets": [{
+                    "slug": slug,
+                    "groupItemTitle": "110,000",
+                    "outcomes": outcomes,
+                    "clobTokenIds": "[\"1\", \"2\"]"
+                }]
+            })
+        };
+        let markets: Vec<_> = [
+            event("bitcoin-up-or-down-on-october-17", "[\"Up\", \"Down\"]"),
+            event("bitcoin-above-on-october-17", "[\"Yes\", \"No\"]"),
+        ]
+        .iter()
+        .flat_map(parse_event)
+        .collect();
+        assert_eq!(markets.len(), 2);
+
+        let resolved = resolve_up_or_down_strikes(&Client::new(), markets).await;
+        let families: Vec<_> = resolved.iter().map(|m| m.family).collect();
+        assert_eq!(families, vec![MarketFamily::Above]);
+    }
+
+    #[test]
+    fn rediscovery_subscribes_new_and_unsubscribes_delisted_markets() {
+        let market = |tokens: [&str; 2]| DiscoveredMarket {
+            family: MarketFamily::Above,
+            event_slug: "bitcoin-above-on-october-17".to_string(),
+            config: MarketConfig {
+                name: tokens.join("-"),
+                crypto: Crypto::BTC,
+                kind: MarketKind::Above,
+                yes_token_id: tokens[0].to_string(),
+                no_token_id: tokens[1].to_string(),
+                end_time_et: "2025-10-17 12:00:00".to_string(),
+                binance_symbol: "BTCUSDT".to_string(),
+                strike_price: 110_000.0,
+                metadata: None,
+            },
+        };
+        let strings = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        let mut known = DiscoveredTokens::default();
+
+        assert_eq!(
+            known.update(&[market(["1", "2"]), market(["3", "4"])]),
+            vec![SubscriptionCommand::Subscribe(strings(&[
+                "1", "2", "3", "4"
+            ]))]
+        );
+        // Unchanged listing: nothing to do.
+        assert!(known
+            .update(&[market(["3", "4"]), market(["1", "2"])])
+            .is_empty());
+        assert_eq!(
+            known.update(&[market(["3", "4"]), market(["5", "6"])]),
+            vec![
+                SubscriptionCommand::Subscribe(strings(&["5", "6"])),
+                SubscriptionCommand::Unsubscribe(strings(&["1", "2"])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use log::{error, info, warn};
+use reqwest::Client;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time;
+
+use crate::exchange_listeners::{
+    autodiscover_markets::{
+        autodiscover_market_config, discover_up_down_tokens, fetch_hourly_open, hour_start_et,
+        up_or_down_slug, MarketConfig, MarketKind,
+    },
+    event_processor::{CountingSender, SocketEvent},
+    poly_listeners::SubscriptionCommand,
+    states::PolyMarketState,
+    Crypto,
+};
+
+/// Which hourly up-or-down series to follow and how early to prepare the next market.
+#[derive(Debug, Clone)]
+pub struct RolloverSchedule {
+    pub crypto: Crypto,
+    /// Slug prefix, e.g. `bitcoin`.
+    pub auto_market: &'static str,
+    /// Ticker used for the Binance strike symbol, e.g. `btc`.
+    pub crypto_ticker: &'static str,
+    /// How long before the hour the next market's tokens are subscribed.
+    pub lead: Duration,
+    /// How long past the hour discovery and strike fetching keep retrying.
+    pub grace: Duration,
+    pub retry_every: Duration,
+}
+
+impl RolloverSchedule {
+    pub fn new(crypto: Crypto, auto_market: &'static str, crypto_ticker: &'static str) -> Self {
+        Self {
+            crypto,
+            auto_market,
+            crypto_ticker,
+            lead: Duration::seconds(90),
+            grace: Duration::minutes(5),
+            retry_every: Duration::seconds(5),
+        }
+    }
+}
+
+async fn sleep_until(at: DateTime<Utc>) {
+    if let Ok(wait) = (at - Utc::now()).to_std() {
+        time::sleep(wait).await;
+    }
+}
+
+/// Retries `attempt` every `retry_every` until it yields a value or `deadline` passes.
+async fn retry_until<T, F, Fut>(
+    name: &str,
+    deadline: DateTime<Utc>,
+    retry_every: Duration,
+    mut attempt: F,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
+{
+    loop {
+        match attempt().await {
+            Ok(Some(value)) => return Some(value),
+            Ok(None) => {}
+            Err(e) => warn!("[Rollover] {} failed: {:#}", name, e),
+        }
+        if Utc::now() >= deadline {
+            return None;
+        }
+        time::sleep(retry_every.to_std().unwrap_or_default()).await;
+    }
+}
+
+/// Follows an hourly up-or-down series: discovers the current market, then ahead of every
+/// US/Eastern hour subscribes the next market's tokens, fetches its strike once the hour
+/// opens, emits `SocketEvent::MarketRollover` and unsubscribes the expired tokens.
+/// Token metadata is registered in `poly_state.markets` before the tokens are subscribed.
+pub async fn run_hourly_rollover(
+    schedule: RolloverSchedule,
+    poly_state: Arc<PolyMarketState>,
+    subscriptions: UnboundedSender<SubscriptionCommand>,
+    event_tx: Arc<CountingSender>,
+) {
+    let client = Client::new();
+    let mut current: Option<MarketConfig> = None;
+
+    if let Ok(start) = hour_start_et(Utc::now()) {
+        let deadline = start.with_timezone(&Utc) + Duration::hours(1) - schedule.lead;
+        current = retry_until("discovery", deadline, schedule.retry_every, || {
+            autodiscover_market_config(schedule.auto_market, schedule.crypto_ticker)
+        })
+        .await;
+        if let Some(market) = &current {
+            poly_state.register_market(market);
+            let _ = subscriptions.send(SubscriptionCommand::Subscribe(vec![
+                market.yes_token_id.clone(),
+                market.no_token_id.clone(),
+            ]));
+            if !emit(&event_tx, schedule.crypto, None, market.clone()) {
+                return;
+            }
+        }
+    }
+
+    loop {
+        let next_start = match hour_start_et(Utc::now()) {
+            Ok(start) => start + Duration::hours(1),
+            Err(e) => {
+                error!("[Rollover] {:#}", e);
+                return;
+            }
+        };
+        sleep_until(next_start.with_timezone(&Utc) - schedule.lead).await;
+
+        let next = prepare_next(&schedule, &client, &poly_state, &subscriptions, next_start).await;
+        let prepared = next.is_some();
+        if !prepared {
+            warn!(
+                "[Rollover] No {} market for the hour starting {}",
+                schedule.crypto, next_start
+            );
+        }
+        if !roll_over(
+            &mut current,
+            next,
+            schedule.crypto,
+            &subscriptions,
+            &event_tx,
+        ) {
+            return;
+        }
+        if !prepared {
+            sleep_until(next_start.with_timezone(&Utc) + schedule.grace).await;
+        }
+    }
+}
+
+/// Replaces `current` with `next`. The market it replaces has expired whether or not a
+/// `next` market was found: it is reported closed and unsubscribed.
+/// Returns `false` once the event processor is gone.
+fn roll_over(
+    current: &mut Option<MarketConfig>,
+    next: Option<MarketConfig>,
+    crypto: Crypto,
+    subscriptions: &UnboundedSender<SubscriptionCommand>,
+    event_tx: &CountingSender,
+) -> bool {
+    let previous = current.take();
+    let mut delivered = match next {
+        Some(next) => {
+            *current = Some(next.clone());
+            emit(event_tx, crypto, previous.clone(), next)
+        }
+        None => {
+            if let Some(previous) = &previous {
+                info!("[Rollover] {} expired without a successor", previous.name);
+            }
+            true
+        }
+    };
+    delivered = delivered
+        && previous
+            .as_ref()
+            .is_none_or(|previous| emit_closed(event_tx, previous));
+    if let Some(previous) = previous {
+        let _ = subscriptions.send(SubscriptionCommand::Unsubscribe(vec![
+            previous.yes_token_id,
+            previous.no_token_id,
+        ]));
+    }
+    delivered
+}
+
+/// Subscribes the tokens of the market starting at `start` and waits for its strike.
+async fn prepare_next(
+    schedule: &RolloverSchedule,
+    client: &Client,
+    poly_state: &PolyMarketState,
+    subscriptions: &UnboundedSender<SubscriptionCommand>,
+    start: DateTime<Tz>,
+) -> Option<MarketConfig> {
+    let deadline = start.with_timezone(&Utc) + schedule.grace;
+    let slug = up_or_down_slug(schedule.auto_market, start);
+    let tokens = retry_until("token discovery", deadline, schedule.retry_every, || {
+        discover_up_down_tokens(client, &slug)
+    })
+    .await?;
+    let mut market = MarketConfig {
+        name: slug,
+        crypto: schedule.crypto,
+        kind: MarketKind::UpOrDown,
+        yes_token_id: tokens.yes_token_id,
+        no_token_id: tokens.no_token_id,
+        end_time_et: (start + Duration::hours(1))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        binance_symbol: format!("{}USDT", schedule.crypto_ticker.to_uppercase()),
+        // Known once the hour opens.
+        strike_price: 0.0,
+        metadata: tokens.metadata,
+    };
+    poly_state.register_market(&market);
+    let _ = subscriptions.send(SubscriptionCommand::Subscribe(vec![
+        market.yes_token_id.clone(),
+        market.no_token_id.clone(),
+    ]));
+
+    sleep_until(start.with_timezone(&Utc)).await;
+    let start_ms = start.timestamp_millis();
+    let strike_price = retry_until("strike fetch", deadline, schedule.retry_every, || async {
+        fetch_hourly_open(client, &market.binance_symbol, start_ms)
+            .await
+            .map(Some)
+    })
+    .await;
+    let Some(strike_price) = strike_price else {
+        let _ = subscriptions.send(SubscriptionCommand::Unsubscribe(vec![
+            market.yes_token_id,
+            market.no_token_id,
+        ]));
+        return None;
+    };
+    market.strike_price = strike_price;
+    Some(market)
+}
+
+fn emit(
+    event_tx: &CountingSender,
+    crypto: Crypto,
+    previous: Option<MarketConfig>,
+    next: MarketConfig,
+) -> bool {
+    info!("[Rollover] Switching {} to {}", crypto, next.name);
+    if event_tx
+        .send(SocketEvent::MarketRollover {
+            crypto,
+            previous: previous.map(Box::new),
+            next: Box::new(next),
+        })
+        .is_err()
+    {
+        error!("[Rollover] Event processor is gone. Stopping scheduler.");
+        return false;
+    }
+    true
+}
+
+fn emit_closed(event_tx: &CountingSender, market: &MarketConfig) -> bool {
+    if event_tx
+        .send(SocketEvent::MarketClosed {
+            asset_ids: vec![market.yes_token_id.clone(), market.no_token_id.clone()],
+        })
+        .is_err()
+    {
+        error!("[Rollover] Event processor is gone. Stopping scheduler.");
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::{event_processor::spawn_event_processor, AppState};
+    use crate::marketmaking::poly_market_struct::Market;
+    use crate::strategies::{Strategy, StrategyContext};
+    use serde_json::json;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Strategy for Recorder {
+        fn name(&self) -> &'static str {
+            "Recorder"
+        }
+
+        fn on_market_closed(&self, _ctx: Arc<StrategyContext>, asset_ids: &[String]) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("closed {}", asset_ids.join(",")));
+        }
+
+        fn market_handle_rollover(
+            &self,
+            _ctx: Arc<StrategyContext>,
+            _crypto: Crypto,
+            previous: Option<&MarketConfig>,
+            next: &MarketConfig,
+        ) {
+            let previous = previous.map_or("none", |m| m.name.as_str());
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("rollover {} -> {}", previous, next.name));
+        }
+    }
+
+    impl Recorder {
+        async fn wait_for(&self, count: usize) -> Vec<String> {
+            for _ in 0..100 {
+                if self.0.lock().unwrap().len() >= count {
+                    break;
+                }
+                time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn market(name: &str) -> MarketConfig {
+        let metadata: Market = serde_json::from_value(json!({
+            "slug": name,
+            "conditionId": format!("0x{}", name),
+            "orderPriceMinTickSize": 0.01,
+            "orderMinSize": 5,
+        }))
+        .unwrap();
+        MarketConfig {
+            name: name.to_string(),
+            crypto: Crypto::BTC,
+            kind: MarketKind::UpOrDown,
+            yes_token_id: format!("{}-up", name),
+            no_token_id: format!("{}-down", name),
+            end_time_et: "2025-10-17 16:00:00".to_string(),
+            binance_symbol: "BTCUSDT".to_string(),
+            strike_price: 100_000.0,
+            metadata: Some(Arc::new(metadata)),
+        }
+    }
+
+    /// Recorded strategy, state, event sender and both ends of the subscription channel.
+    type Harness = (
+        Arc<Recorder>,
+        Arc<PolyMarketState>,
+        Arc<CountingSender>,
+        mpsc::UnboundedSender<SubscriptionCommand>,
+        mpsc::UnboundedReceiver<SubscriptionCommand>,
+    );
+
+    fn setup() -> Harness {
+        let recorder = Arc::new(Recorder::default());
+        let poly_state = Arc::new(PolyMarketState::default());
+        let event_tx = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            vec![recorder.clone() as Arc<dyn Strategy>],
+            4,
+        );
+        let (sub_tx, sub_rx) = mpsc::unbounded_channel();
+        (recorder, poly_state, event_tx, sub_tx, sub_rx)
+    }
+
+    #[tokio::test]
+    async fn rollover_replaces_and_unsubscribes_the_previous_market() {
+        let (recorder, poly_state, event_tx, sub_tx, mut sub_rx) = setup();
+        let mut current = Some(market("ten"));
+        poly_state.register_market(current.as_ref().unwrap());
+
+        let next = market("eleven");
+        poly_state.register_market(&next);
+        assert!(roll_over(
+            &mut current,
+            Some(next),
+            Crypto::BTC,
+            &sub_tx,
+            &event_tx
+        ));
+
+        assert_eq!(current.as_ref().map(|m| m.name.as_str()), Some("eleven"));
+        assert_eq!(
+            sub_rx.try_recv().unwrap(),
+            SubscriptionCommand::Unsubscribe(vec!["ten-up".into(), "ten-down".into()])
+        );
+        assert_eq!(
+            recorder.wait_for(2).await,
+            vec!["rollover ten -> eleven", "closed ten-up,ten-down"]
+        );
+        assert_eq!(
+            poly_state.up_down_markets.get(&Crypto::BTC).unwrap().name,
+            "eleven"
+        );
+        let up = poly_state.markets.get("eleven-up").unwrap();
+        assert_eq!(
+            (up.is_yes_market, up.orderPriceMinTickSize),
+            (Some(true), Some(0.01))
+        );
+        assert_eq!(
+            poly_state.markets.get("eleven-down").unwrap().is_yes_market,
+            Some(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_rollover_closes_the_expired_market() {
+        let (recorder, poly_state, event_tx, sub_tx, mut sub_rx) = setup();
+        let mut current = None;
+        poly_state.register_market(&market("ten"));
+        assert!(roll_over(
+            &mut current,
+            Some(market("ten")),
+            Crypto::BTC,
+            &sub_tx,
+            &event_tx
+        ));
+        assert_eq!(recorder.wait_for(1).await.len(), 1);
+
+        assert!(roll_over(
+            &mut current,
+            None,
+            Crypto::BTC,
+            &sub_tx,
+            &event_tx
+        ));
+
+        assert!(current.is_none());
+        assert_eq!(
+            sub_rx.try_recv().unwrap(),
+            SubscriptionCommand::Unsubscribe(vec!["ten-up".into(), "ten-down".into()])
+        );
+        assert_eq!(
+            recorder.wait_for(2).await,
+            vec!["rollover none -> ten", "closed ten-up,ten-down"]
+        );
+        assert!(poly_state.up_down_markets.get(&Crypto::BTC).is_none());
+
+        // Nothing left to close.
+        assert!(roll_over(
+            &mut current,
+            None,
+            Crypto::BTC,
+            &sub_tx,
+            &event_tx
+        ));
+        assert!(sub_rx.try_recv().is_err());
+    }
+}
@@ -7,32 +7,41 @@ pub mod crypto_models;
 
 // Add new mods for Polymarket
 pub mod autodiscover_markets;
+pub mod book_resync;
 pub mod event_processor;
+pub mod event_queue;
+pub mod event_routing;
+pub mod feed_health;
+pub mod market_discovery;
+pub mod market_rollover;
 pub mod orderbooks;
+pub mod paper_exchange;
 pub mod poly_client;
 pub mod poly_listeners;
 pub mod poly_models;
 pub mod states;
+pub mod subscription_manager;
 
 pub use crypto_models::{Crypto, Exchange, Instrument};
 // Use the new Polymarket state
 pub use states::{AppState, PolyMarketState};
 
+/// Spawns the spot/perp price listeners of every exchange for each crypto in `cryptos`.
 pub fn spawn_exchange_price_listeners(
     event_tx: Arc<event_processor::CountingSender>,
+    cryptos: &[Crypto],
 ) -> Vec<JoinHandle<()>> {
     let mut tasks: Vec<JoinHandle<()>> = Vec::new();
 
-    // tasks.push(tokio::spawn(
-    //     crypto_exchange_listeners::binance_usdc_usdt_listener(Arc::clone(&rates), event_tx.clone()),
-    // ));
-    // tasks.push(tokio::spawn(listeners::coinbase_usdt_usd_listener(
-    //     Arc::clone(&rates),
-    //     event_tx.clone(),
-    // )));
+    // --- Stablecoin rates for USD normalisation ---
+    tasks.push(tokio::spawn(crypto_listeners::binance_usdc_usdt_listener(
+        event_tx.clone(),
+    )));
+    tasks.push(tokio::spawn(crypto_listeners::coinbase_usdt_usd_listener(
+        event_tx.clone(),
+    )));
 
-    for &crypto in &[Crypto::BTC] {
-        //, Crypto::ETH, Crypto::XRP, Crypto::SOL
+    for &crypto in cryptos {
         tasks.push(tokio::spawn(crypto_listeners::binance_listener(
             crypto,
             false,
@@ -229,6 +229,26 @@ impl OrderBook {
         self.ask_heap.lock().unwrap().peek().map(|r| r.0)
     }
 
+    /// Whether our top of book agrees with the best prices reported by the feed.
+    /// An empty side counts as a bid of 0 and an ask of 1.
+    pub fn matches_top_of_book(&self, best_bid: Option<&str>, best_ask: Option<&str>) -> bool {
+        let to_milli = |price: &str| {
+            price
+                .parse::<f64>()
+                .ok()
+                .map(|p| (p * 1000.0).round() as u32)
+        };
+        let bid_ok = match best_bid.map(to_milli) {
+            Some(Some(expected)) => self.best_bid().map_or(0, |(p, _)| p) == expected,
+            _ => true,
+        };
+        let ask_ok = match best_ask.map(to_milli) {
+            Some(Some(expected)) => self.best_ask().map_or(1000, |(p, _)| p) == expected,
+            _ => true,
+        };
+        bid_ok && ask_ok
+    }
+
     pub fn get_midpoint(&self) -> u32 {
         let best_bid = self.best_bid();
         let best_ask = self.best_ask();
@@ -306,3 +326,43 @@ impl OrderBook {
         self.asks_dirty.store(true, Ordering::Release);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::poly_models::OrderbookEntry;
+
+    fn entry(price: &str, size: &str) -> OrderbookEntry {
+        OrderbookEntry {
+            price: price.to_string(),
+            size: size.to_string(),
+        }
+    }
+
+    #[test]
+    fn verifies_top_of_book_after_price_change() {
+        let snapshot = AggOrderbook {
+            asset_id: "1".to_string(),
+            bids: vec![entry("0.48", "100"), entry("0.47", "50")],
+            asks: vec![entry("0.52", "100")],
+            timestamp: "0".to_string(),
+            hash: String::new(),
+        };
+        let book = OrderBook::new(&snapshot, "0.01".to_string());
+        assert!(book.matches_top_of_book(Some("0.48"), Some("0.52")));
+
+        let change = PriceChange {
+            asset_id: "1".to_string(),
+            price: "0.48".to_string(),
+            size: "0".to_string(),
+            side: "BUY".to_string(),
+            best_bid: Some("0.47".to_string()),
+            best_ask: Some("0.52".to_string()),
+        };
+        book.apply_price_change(&change, "1");
+        assert!(book.matches_top_of_book(change.best_bid.as_deref(), change.best_ask.as_deref()));
+        // A missed update shows up as a disagreement.
+        assert!(!book.matches_top_of_book(Some("0.49"), Some("0.52")));
+        assert!(book.matches_top_of_book(None, None));
+    }
+}
@@ -0,0 +1,337 @@
+use dashmap::DashMap;
+use log::info;
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::exchange_listeners::poly_models::{OpenOrder, OrderSide, OrderState, Position};
+use crate::exchange_listeners::states::PolyMarketState;
+use crate::metrics::{TradedPnl, METRICS};
+
+/// A fill of a paper order, in thousandths like the books.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaperFill {
+    pub timestamp_ms: i64,
+    pub asset_id: String,
+    pub side: OrderSide,
+    pub price: u32,
+    pub size: u32,
+    pub strategy: Option<&'static str>,
+}
+
+/// Ids and fills of the paper orders of a session.
+#[derive(Debug, Default)]
+pub struct PaperLedger {
+    orders: AtomicU64,
+    fills: Mutex<Vec<PaperFill>>,
+    traded: TradedPnl,
+}
+
+impl PaperLedger {
+    /// Id for a new paper order, standing in for the one the exchange would assign.
+    pub fn next_order_id(&self) -> String {
+        format!("paper-{}", self.orders.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    pub fn fills(&self) -> Vec<PaperFill> {
+        self.fills
+            .lock()
+            .map(|fills| fills.clone())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, fill: PaperFill) {
+        self.traded.record_fill(
+            &fill.asset_id,
+            fill.side,
+            fill.price as f64 / 1000.0,
+            fill.size as f64 / 1000.0,
+        );
+        if let Ok(mut fills) = self.fills.lock() {
+            fills.push(fill);
+        }
+    }
+}
+
+/// What paper trading did over a session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperSummary {
+    pub fills: usize,
+    pub filled_shares: f64,
+    /// Cash from the fills plus the shares left, marked at the mid of their book.
+    pub pnl: f64,
+}
+
+impl fmt::Display for PaperSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} paper fills, {:.3} shares filled, PnL {:.2}",
+            self.fills, self.filled_shares, self.pnl
+        )
+    }
+}
+
+/// Sums up the paper fills of `poly_state`. Shares of a book without both sides are
+/// marked at 0.
+pub fn summary(poly_state: &PolyMarketState) -> PaperSummary {
+    let ledger = &poly_state.paper_ledger;
+    let fills = ledger.fills();
+    let pnl = ledger
+        .traded
+        .assets()
+        .iter()
+        .map(|asset_id| {
+            let mark = poly_state
+                .orderbooks
+                .get(asset_id)
+                .and_then(|book| book.read().ok().map(|book| book.get_midpoint()))
+                .unwrap_or(0);
+            ledger.traded.pnl(asset_id, mark as f64 / 1000.0)
+        })
+        .sum();
+    PaperSummary {
+        fills: fills.len(),
+        filled_shares: fills.iter().map(|fill| fill.size as f64 / 1000.0).sum(),
+        pnl,
+    }
+}
+
+/// Fills the live paper orders on `asset_id` that its book crosses, most aggressive
+/// first, each at its own price and up to the size of the levels it crosses. Returns
+/// the number of fills.
+///
+/// Without our orders in the recorded books, levels are not depleted from one update
+/// to the next, and resting orders fill only once the book trades through them.
+pub fn fill_crossed_orders(poly_state: &PolyMarketState, asset_id: &str) -> usize {
+    let Some(asset_orders) = poly_state.open_orders.get(asset_id) else {
+        return 0;
+    };
+    let (bids, asks) = (
+        Arc::clone(&asset_orders.bids),
+        Arc::clone(&asset_orders.asks),
+    );
+    drop(asset_orders);
+
+    let (mut bid_levels, mut ask_levels) = {
+        let Some(book) = poly_state.orderbooks.get(asset_id) else {
+            return 0;
+        };
+        let Ok(book) = book.read() else {
+            return 0;
+        };
+        let levels = |side: &DashMap<u32, u32>| -> Vec<(u32, u32)> {
+            side.iter()
+                .map(|level| (*level.key(), *level.value()))
+                .filter(|(_, size)| *size > 0)
+                .collect()
+        };
+        (levels(book.get_bid_map()), levels(book.get_ask_map()))
+    };
+    bid_levels.sort_by_key(|(price, _)| Reverse(*price));
+    ask_levels.sort_by_key(|(price, _)| *price);
+
+    let timestamp_ms = poly_state.now().timestamp_millis();
+    fill_side(
+        poly_state,
+        asset_id,
+        &bids,
+        OrderSide::Buy,
+        ask_levels,
+        timestamp_ms,
+    ) + fill_side(
+        poly_state,
+        asset_id,
+        &asks,
+        OrderSide::Sell,
+        bid_levels,
+        timestamp_ms,
+    )
+}
+
+/// Fills `orders` of `side` against `levels` of the other side, best first.
+fn fill_side(
+    poly_state: &PolyMarketState,
+    asset_id: &str,
+    orders: &DashMap<(u32, u32), Arc<Mutex<OpenOrder>>>,
+    side: OrderSide,
+    mut levels: Vec<(u32, u32)>,
+    timestamp_ms: i64,
+) -> usize {
+    let crosses = |order_price: u32, level_price: u32| match side {
+        OrderSide::Buy => level_price <= order_price,
+        OrderSide::Sell => level_price >= order_price,
+    };
+    let mut resting: Vec<_> = orders
+        .iter()
+        .map(|entry| (*entry.key(), Arc::clone(entry.value())))
+        .collect();
+    match side {
+        OrderSide::Buy => resting.sort_by_key(|((price, _), _)| Reverse(*price)),
+        OrderSide::Sell => resting.sort_by_key(|((price, _), _)| *price),
+    }
+
+    let mut fills = 0;
+    for (key, order) in resting {
+        let (filled, done, strategy) = {
+            let Ok(mut order) = order.lock() else {
+                continue;
+            };
+            if order.state() != OrderState::Live {
+                continue;
+            }
+            let mut remaining = order.size().saturating_sub(order.size_filled());
+            let mut filled = 0;
+            while remaining > 0 {
+                let Some(level) = levels.first_mut() else {
+                    break;
+                };
+                if !crosses(order.price(), level.0) {
+                    break;
+                }
+                let size = remaining.min(level.1);
+                level.1 -= size;
+                remaining -= size;
+                filled += size;
+                if level.1 == 0 {
+                    levels.remove(0);
+                }
+            }
+            if filled == 0 {
+                continue;
+            }
+            let size_filled = order.size_filled() + filled;
+            order.set_size_filled(size_filled);
+            (filled, remaining == 0, order.strategy())
+        };
+        if done {
+            orders.remove(&key);
+        }
+        apply_to_position(poly_state, asset_id, side, filled);
+        METRICS.fills_total.with(&[side.as_str(), "paper"]).inc();
+        METRICS
+            .filled_shares_total
+            .with(&[side.as_str(), "paper"])
+            .inc_by(filled as f64 / 1000.0);
+        info!(
+            "[Paper] Filled {:?} {} x {} on {}",
+            side,
+            key.0 as f64 / 1000.0,
+            filled as f64 / 1000.0,
+            asset_id
+        );
+        poly_state.paper_ledger.record(PaperFill {
+            timestamp_ms,
+            asset_id: asset_id.to_string(),
+            side,
+            price: key.0,
+            size: filled,
+            strategy,
+        });
+        fills += 1;
+    }
+    fills
+}
+
+fn apply_to_position(poly_state: &PolyMarketState, asset_id: &str, side: OrderSide, size: u32) {
+    let position = Arc::clone(
+        poly_state
+            .positions
+            .entry(asset_id.to_string())
+            .or_insert_with(|| Arc::new(RwLock::new(Position::new(asset_id, 0))))
+            .value(),
+    );
+    let Ok(mut position) = position.write() else {
+        return;
+    };
+    position.size = match side {
+        OrderSide::Buy => position.size.saturating_add(size),
+        OrderSide::Sell => position.size.saturating_sub(size),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::orderbooks::poly_orderbook::OrderBook;
+    use crate::exchange_listeners::poly_models::{AggOrderbook, AssetOrders, OrderbookEntry};
+
+    fn paper_state(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> PolyMarketState {
+        let levels = |side: &[(&str, &str)]| {
+            side.iter()
+                .map(|(price, size)| OrderbookEntry {
+                    price: price.to_string(),
+                    size: size.to_string(),
+                })
+                .collect()
+        };
+        let snapshot = AggOrderbook {
+            asset_id: "1".to_string(),
+            bids: levels(bids),
+            asks: levels(asks),
+            timestamp: "0".to_string(),
+            hash: String::new(),
+        };
+        let state = PolyMarketState {
+            paper_trading: true,
+            ..Default::default()
+        };
+        state.orderbooks.insert(
+            "1".to_string(),
+            Arc::new(RwLock::new(OrderBook::new(&snapshot, "0.01".to_string()))),
+        );
+        state
+    }
+
+    fn rest(state: &PolyMarketState, side: OrderSide, price: u32, size: u32) {
+        let orders = state
+            .open_orders
+            .entry("1".to_string())
+            .or_insert_with(|| AssetOrders {
+                bids: Arc::new(DashMap::new()),
+                asks: Arc::new(DashMap::new()),
+            });
+        let book = match side {
+            OrderSide::Buy => &orders.bids,
+            OrderSide::Sell => &orders.asks,
+        };
+        let id = state.paper_ledger.next_order_id();
+        book.insert(
+            (price, size),
+            Arc::new(Mutex::new(OpenOrder::new(
+                "1".to_string(),
+                price,
+                size,
+                0,
+                Some(id),
+            ))),
+        );
+    }
+
+    #[test]
+    fn crossing_orders_fill_up_to_the_crossed_size() {
+        let state = paper_state(&[("0.45", "100")], &[("0.50", "30"), ("0.52", "50")]);
+        rest(&state, OrderSide::Buy, 510, 50_000);
+        rest(&state, OrderSide::Buy, 400, 10_000);
+        rest(&state, OrderSide::Sell, 450, 20_000);
+
+        assert_eq!(fill_crossed_orders(&state, "1"), 2);
+
+        let orders = state.open_orders.get("1").unwrap();
+        let bid = orders.bids.get(&(510, 50_000)).unwrap();
+        assert_eq!(bid.lock().unwrap().size_filled(), 30_000);
+        assert!(orders.bids.contains_key(&(400, 10_000)));
+        assert!(orders.asks.is_empty());
+        assert_eq!(
+            state.positions.get("1").unwrap().read().unwrap().size,
+            10_000
+        );
+
+        let summary = summary(&state);
+        assert_eq!(summary.fills, 2);
+        assert!((summary.filled_shares - 50.0).abs() < 1e-9);
+        // Bought 30 at 0.51, sold 20 at 0.45, 10 left marked at (0.45 + 0.50) / 2.
+        assert!((summary.pnl - (-15.3 + 9.0 + 4.75)).abs() < 1e-9);
+    }
+}
@@ -5,15 +5,17 @@ use std::{
 
 use dashmap::DashMap;
 use log::{error, info, warn};
-use serde_json::Value;
+use rust_decimal::Decimal;
 
 use crate::{
-    clob_client::clob_types::OrderArgs,
+    clob_client::clob_types::{CancelOrdersResponse, CreateOrderOptions},
     exchange_listeners::{
-        poly_models::{AssetOrders, OpenOrder, OrderSide, OrderState},
+        feed_health::FEED_HEALTH,
+        poly_models::{AssetOrders, Listener, OpenOrder, OrderSide, OrderState},
         states::PolyMarketState,
     },
-    marketmaking::marketmakingclient::CLIENT,
+    marketmaking::marketmakingclient::client,
+    metrics::METRICS,
 };
 
 #[derive(Debug, Default)]
@@ -21,8 +23,11 @@ pub struct PolyClient;
 
 impl PolyClient {
     /// Places a limit order, sends it to the exchange, and records it in `poly_state.open_orders`.
+    /// `strategy` is the strategy placing it, or `None` for the operator.
+    #[allow(clippy::too_many_arguments)]
     pub fn place_limit_order(
         poly_state: Arc<PolyMarketState>,
+        strategy: Option<&'static str>,
         asset_id: &str,
         side: OrderSide,
         price: u32,
@@ -30,15 +35,82 @@ impl PolyClient {
         tick_size: &str,
         neg_risk: bool,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let result = Self::submit_limit_order(
+            poly_state, strategy, asset_id, side, price, size, tick_size, neg_risk,
+        );
+        if result.is_err() {
+            METRICS.order("rejected");
+        }
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn submit_limit_order(
+        poly_state: Arc<PolyMarketState>,
+        strategy: Option<&'static str>,
+        asset_id: &str,
+        side: OrderSide,
+        price: u32,
+        size: u32,
+        tick_size: &str,
+        neg_risk: bool,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if poly_state.is_book_stale(asset_id) {
+            return Err(format!("orderbook of {} is stale", asset_id).into());
+        }
+        if poly_state.is_processing_lagging() {
+            return Err("event processing lags behind the feeds".into());
+        }
+        if poly_state.is_shutting_down() {
+            return Err("shutting down".into());
+        }
+        if let Some(strategy) = strategy {
+            if poly_state.is_kill_switch_tripped() {
+                return Err("kill switch is tripped".into());
+            }
+            if poly_state.is_strategy_paused(strategy) {
+                return Err(format!("{} is paused", strategy).into());
+            }
+        }
+        if poly_state.paper_trading {
+            // Filled by `PaperFills` once the book crosses it.
+            let order_id = poly_state.paper_ledger.next_order_id();
+            let paper_order = Self::record_order(
+                poly_state.as_ref(),
+                asset_id,
+                side,
+                price,
+                size,
+                0,
+                Some(order_id),
+            )
+            .ok_or_else(|| "order already exists".to_string())?;
+            if let Ok(mut order) = paper_order.lock() {
+                order.set_strategy(strategy);
+            }
+            METRICS.order("paper");
+            info!(
+                "[Paper] {:?} {} x {} on {}",
+                side,
+                price as f64 / 1000.0,
+                size as f64 / 1000.0,
+                asset_id
+            );
+            return Ok(());
+        }
+        // Without the user feed fills and cancels would go unseen.
+        if !FEED_HEALTH.is_connected(Listener::PolyUserLegacy.as_str()) {
+            return Err("user feed is disconnected".into());
+        }
         if let Ok(mut rate_limit) = poly_state.rate_limit.write() {
             if rate_limit.should_wait() {
                 return Err("Rate limit has been hit".into());
             }
             rate_limit.update_timestamp();
         }
-        let client = Arc::clone(&CLIENT);
-        let price_dec = price as f64 / 1000.0;
-        let size_dec = size as f64 / 1000.0;
+        let client = client()?;
+        let price_dec = Decimal::new(price as i64, 3);
+        let size_dec = Decimal::new(size as i64, 3);
         // log::info!(
         //     "[PolyClient] preparing order asset={} side={:?} price_int={} size_int={} price_dec={} size_dec={} tick_size={} neg_risk={}",
         //     asset_id,
@@ -54,31 +126,44 @@ impl PolyClient {
         let local_order =
             Self::record_order(poly_state.as_ref(), asset_id, side, price, size, 0, None)
                 .ok_or_else(|| "order already exists".to_string())?;
+        if let Ok(mut order) = local_order.lock() {
+            order.set_strategy(strategy);
+        }
+
+        let min_size = poly_state
+            .markets
+            .get(asset_id)
+            .and_then(|market| market.orderMinSize)
+            .map(Decimal::from);
+        let options = CreateOrderOptions {
+            tick_size,
+            neg_risk,
+            min_size,
+        };
 
-        let order_args = OrderArgs::new(
+        let signed_order = match client.create_order_fast(
             asset_id,
+            side.as_str(),
             price_dec,
             size_dec,
-            side.as_str(),
-            None,
-            None,
-            None,
-            None,
-        );
-
-        let signed_order = client.create_order(&order_args, tick_size, neg_risk);
-        if signed_order.order.maker_amount.is_zero() || signed_order.order.taker_amount.is_zero() {
-            log::error!(
-                "[PolyClient] Computed zero maker/taker amount for asset={} side={:?} price_dec={} size_dec={} tick_size={}",
-                asset_id,
-                side,
-                price_dec,
-                size_dec,
-                tick_size
-            );
-            Self::remove_order_entry(poly_state.as_ref(), asset_id, side, price, size);
-            return Err("Computed zero maker/taker amount when building order".into());
-        }
+            &options,
+            0,
+        ) {
+            Ok(signed_order) => signed_order,
+            Err(e) => {
+                log::error!(
+                    "[PolyClient] Rejected order for asset={} side={:?} price_dec={} size_dec={} tick_size={}: {}",
+                    asset_id,
+                    side,
+                    price_dec,
+                    size_dec,
+                    tick_size,
+                    e
+                );
+                Self::remove_order_entry(poly_state.as_ref(), asset_id, side, price, size);
+                return Err(e);
+            }
+        };
         let client_clone = Arc::clone(&client);
         let poly_state_clone = Arc::clone(&poly_state);
         let asset_id_owned = asset_id.to_string();
@@ -86,18 +171,10 @@ impl PolyClient {
         tokio::spawn(async move {
             match client_clone.post_order(&signed_order, "GTC").await {
                 Ok(posted_order) => {
-                    let order_id = match posted_order
-                        .get("orderID")
-                        .and_then(Value::as_str)
-                        .map(str::to_owned)
-                        .or_else(|| {
-                            posted_order
-                                .get("orderId")
-                                .and_then(Value::as_str)
-                                .map(str::to_owned)
-                        }) {
-                        Some(id) => id,
+                    let order_id = match posted_order.accepted_order_id() {
+                        Some(id) => id.to_string(),
                         None => {
+                            METRICS.order("rejected");
                             Self::remove_order_entry(
                                 poly_state_clone.as_ref(),
                                 &asset_id_owned,
@@ -106,13 +183,14 @@ impl PolyClient {
                                 size,
                             );
                             error!(
-                                "orderID missing from response when placing {}",
-                                asset_id_owned
+                                "orderID missing from response when placing {}: {}",
+                                asset_id_owned, posted_order.error_msg
                             );
                             return;
                         }
                     };
 
+                    METRICS.order("placed");
                     if let Ok(mut order) = local_order_clone.lock() {
                         order.set_id(Some(order_id));
                     } else {
@@ -125,6 +203,7 @@ impl PolyClient {
                     drop(posted_order);
                 }
                 Err(e) => {
+                    METRICS.order("rejected");
                     error!(
                         "[PolyClient] Failed to place order for {} {:?} at {}x{}: {}",
                         asset_id_owned, side, price, size, e
@@ -149,7 +228,6 @@ impl PolyClient {
         price: u32,
         size: u32,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let client = Arc::clone(&CLIENT);
         let order_key = (price, size);
 
         let order_arc = {
@@ -169,6 +247,13 @@ impl PolyClient {
             Arc::clone(entry.value())
         };
 
+        if poly_state.paper_trading {
+            METRICS.order("cancelled");
+            Self::remove_order_entry(poly_state.as_ref(), asset_id, side, price, size);
+            return Ok(());
+        }
+
+        let client = client()?;
         let order_id = {
             let mut order = order_arc
                 .lock()
@@ -189,13 +274,8 @@ impl PolyClient {
             let id_ref = order_id.as_str();
             match client_clone.cancel_orders(&[id_ref]).await {
                 Ok(resp) => {
-                    let canceled_ids = resp
-                        .get("canceled")
-                        .and_then(Value::as_array)
-                        .map(|arr| arr.iter().filter_map(Value::as_str).collect::<Vec<_>>())
-                        .unwrap_or_default();
-
-                    if canceled_ids.iter().any(|&id| id == id_ref) {
+                    if resp.is_canceled(id_ref) {
+                        METRICS.order("cancelled");
                         Self::remove_order_entry(
                             poly_state_clone.as_ref(),
                             &asset_id_owned,
@@ -204,6 +284,7 @@ impl PolyClient {
                             size,
                         );
                     } else {
+                        METRICS.order("cancel_failed");
                         if let Ok(mut order) = order_arc_clone.lock() {
                             order.set_state(OrderState::Live);
                         }
@@ -211,6 +292,7 @@ impl PolyClient {
                     }
                 }
                 Err(e) => {
+                    METRICS.order("cancel_failed");
                     error!(
                         "Failed to cancel order {} for asset {}: {}",
                         id_ref, asset_id_owned, e
@@ -225,6 +307,78 @@ impl PolyClient {
         Ok(())
     }
 
+    /// Cancels every live order in `poly_state.open_orders` in one request. Orders the
+    /// exchange accepts to cancel stay in `open_orders` as `ToBeCanceled` until the user
+    /// feed reports them cancelled; orders still waiting for an id are left for a later
+    /// call. Returns how many orders were sent for cancellation, or an error if the
+    /// request failed or any order was not canceled.
+    pub async fn cancel_open_orders(
+        poly_state: Arc<PolyMarketState>,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        Self::cancel_matching_orders(poly_state, |_, _| true).await
+    }
+
+    /// Same as `cancel_open_orders`, for the orders for which `matches(asset_id, order)`
+    /// holds.
+    pub async fn cancel_matching_orders(
+        poly_state: Arc<PolyMarketState>,
+        matches: impl Fn(&str, &OpenOrder) -> bool,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut to_cancel = Vec::new();
+        let mut paper_orders = Vec::new();
+        for asset_orders in poly_state.open_orders.iter() {
+            for (side, book) in [
+                (OrderSide::Buy, &asset_orders.bids),
+                (OrderSide::Sell, &asset_orders.asks),
+            ] {
+                for entry in book.iter() {
+                    let Ok(mut order) = entry.value().lock() else {
+                        continue;
+                    };
+                    let Some(id) = order.id().cloned() else {
+                        continue;
+                    };
+                    if order.state() != OrderState::Live || !matches(asset_orders.key(), &order) {
+                        continue;
+                    }
+                    if poly_state.paper_trading {
+                        paper_orders.push((asset_orders.key().clone(), side, *entry.key()));
+                        continue;
+                    }
+                    order.set_state(OrderState::ToBeCanceled);
+                    to_cancel.push((id, Arc::clone(entry.value())));
+                }
+            }
+        }
+        // Paper orders are only known here, so they are canceled on the spot.
+        for (asset_id, side, (price, size)) in &paper_orders {
+            METRICS.order("cancelled");
+            Self::remove_order_entry(poly_state.as_ref(), asset_id, *side, *price, *size);
+        }
+        if to_cancel.is_empty() {
+            return Ok(paper_orders.len());
+        }
+
+        let ids: Vec<&str> = to_cancel.iter().map(|(id, _)| id.as_str()).collect();
+        let response = match client() {
+            Ok(client) => client.cancel_orders(&ids).await,
+            Err(e) => Err(e),
+        };
+        let orders: Vec<_> = to_cancel
+            .iter()
+            .map(|(id, order)| (id.as_str(), order))
+            .collect();
+        let not_canceled = match &response {
+            Ok(response) => settle_cancels(&orders, response),
+            Err(_) => settle_cancels(&orders, &CancelOrdersResponse::default()),
+        };
+        response?;
+        if not_canceled.is_empty() {
+            return Ok(to_cancel.len());
+        }
+        Err(partial_cancel_error(&not_canceled, to_cancel.len()))
+    }
+
     fn record_order(
         poly_state: &PolyMarketState,
         asset_id: &str,
@@ -327,3 +481,83 @@ impl PolyClient {
         }
     }
 }
+
+/// Error for a cancel of `sent` orders that left `not_canceled` live, with the reasons.
+pub fn partial_cancel_error(
+    not_canceled: &[(String, String)],
+    sent: usize,
+) -> Box<dyn Error + Send + Sync> {
+    let reasons: Vec<String> = not_canceled
+        .iter()
+        .map(|(id, reason)| format!("{}: {}", id, reason))
+        .collect();
+    format!(
+        "{} of {} orders not canceled ({})",
+        not_canceled.len(),
+        sent,
+        reasons.join("; ")
+    )
+    .into()
+}
+
+/// Applies the response to a cancel of `orders`. Orders the exchange canceled stay
+/// `ToBeCanceled` for the user feed to remove; the others are live again. Returns the
+/// orders that were not canceled with the exchange's reason.
+fn settle_cancels(
+    orders: &[(&str, &Arc<Mutex<OpenOrder>>)],
+    response: &CancelOrdersResponse,
+) -> Vec<(String, String)> {
+    let mut not_canceled = Vec::new();
+    for (id, order) in orders {
+        if response.is_canceled(id) {
+            continue;
+        }
+        METRICS.order("cancel_failed");
+        if let Ok(mut order) = order.lock() {
+            order.set_state(OrderState::Live);
+        }
+        let reason = response
+            .not_canceled
+            .get(*id)
+            .cloned()
+            .unwrap_or_else(|| "no response".to_string());
+        not_canceled.push((id.to_string(), reason));
+    }
+    not_canceled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: &str) -> Arc<Mutex<OpenOrder>> {
+        let mut order = OpenOrder::new("1".to_string(), 500, 10_000, 0, Some(id.to_string()));
+        order.set_state(OrderState::ToBeCanceled);
+        Arc::new(Mutex::new(order))
+    }
+
+    #[test]
+    fn partial_cancels_leave_the_refused_orders_live() {
+        let (a, b, c) = (order("a"), order("b"), order("c"));
+        let response: CancelOrdersResponse = serde_json::from_str(
+            r#"{"canceled":["a"],"not_canceled":{"b":"order is already matched"}}"#,
+        )
+        .unwrap();
+
+        let not_canceled = settle_cancels(&[("a", &a), ("b", &b), ("c", &c)], &response);
+
+        assert_eq!(
+            not_canceled,
+            vec![
+                ("b".to_string(), "order is already matched".to_string()),
+                ("c".to_string(), "no response".to_string()),
+            ]
+        );
+        // The user feed removes the cancelled order; the others can be cancelled again.
+        let state = |order: &Arc<Mutex<OpenOrder>>| order.lock().unwrap().state();
+        assert_eq!(
+            [state(&a), state(&b), state(&c)],
+            [OrderState::ToBeCanceled, OrderState::Live, OrderState::Live]
+        );
+    }
+}
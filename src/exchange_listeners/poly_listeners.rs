@@ -2,15 +2,18 @@ use crate::credentials::{POLY_API_KEY, POLY_API_PASSPHRASE, POLY_API_SECRET};
 use crate::exchange_listeners::poly_models::Listener;
 
 use super::event_processor::{CountingSender, SocketEvent};
+use super::feed_health::FEED_HEALTH;
 use super::poly_models::{ClobAuth, Subscription, SubscriptionRequest};
 use anyhow::{anyhow, Result};
 use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
 use rustls::{OwnedTrustAnchor, RootCertStore};
 use serde_json::json;
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpStream;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::time;
 use tokio_rustls::rustls::{ClientConfig, ServerName};
 use tokio_rustls::TlsConnector;
@@ -24,7 +27,7 @@ const POLY_WEBSOCKET_URL: &str = "wss://ws-live-data.polymarket.com/";
 const POLY_WEBSOCKET_URL_OLD: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
 const POLY_USER_WEBSOCKET_URL_OLD: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/user";
 const PING_INTERVAL_S: u64 = 15;
-const MAX_ASSETS_PER_SUB: usize = 500;
+pub(crate) const MAX_ASSETS_PER_SUB: usize = 500;
 
 /// Establishes a WebSocket connection forcing TLS 1.2.
 async fn connect_with_tls12(
@@ -56,6 +59,24 @@ async fn connect_with_tls12(
     Ok(ws_stream)
 }
 
+/// Hands a text frame to the event processor as a market or user event.
+fn forward_message(listener: Listener, text: String, event_tx: &CountingSender) {
+    let event = if listener.is_market() {
+        SocketEvent::Market {
+            listener,
+            payload: text.into_bytes(),
+        }
+    } else {
+        SocketEvent::User {
+            listener,
+            payload: text.into_bytes(),
+        }
+    };
+    if let Err(e) = event_tx.send(event) {
+        error!("[{}] Failed to forward event: {}", listener, e);
+    }
+}
+
 /// Generic handler for Polymarket WebSocket connections.
 async fn polymarket_websocket_handler(
     listener: Listener,
@@ -73,6 +94,7 @@ async fn polymarket_websocket_handler_with_message(
     subscription_message: String,
     event_tx: Arc<CountingSender>,
 ) {
+    let health = FEED_HEALTH.feed(listener.as_str());
     loop {
         match connect_with_tls12(url).await {
             Ok(ws_stream) => {
@@ -87,28 +109,15 @@ async fn polymarket_websocket_handler_with_message(
                     continue;
                 }
                 info!("[{}] Subscription message sent.", listener);
+                health.on_connected();
                 let mut ping_interval = time::interval(Duration::from_secs(PING_INTERVAL_S));
                 loop {
                     tokio::select! {
                         Some(msg_result) = read.next() => {
                             match msg_result {
                                 Ok(Message::Text(text)) => {
-                                    if listener.is_market() {
-                                        if let Err(e) = event_tx.send(SocketEvent::Market {
-                                            listener,
-                                            payload: text.into_bytes(),
-                                        }) {
-                                            error!("[{}] Failed to forward market event: {}", listener, e);
-                                        }
-                                    }
-                                    else if listener.is_user() {
-                                        if let Err(e) = event_tx.send(SocketEvent::User {
-                                            listener,
-                                            payload: text.into_bytes(),
-                                        }) {
-                                            error!("[{}] Failed to forward market event: {}", listener, e);
-                                        }
-                                    }
+                                    health.on_message(text.as_bytes());
+                                    forward_message(listener, text, &event_tx)
                                 }
                                 Ok(Message::Ping(p)) => { if write.send(Message::Pong(p)).await.is_err() { break; } },
                                 Ok(Message::Close(_)) => { warn!("[{}] Connection closed by server.", listener); break; },
@@ -123,6 +132,7 @@ async fn polymarket_websocket_handler_with_message(
                         }
                     }
                 }
+                health.on_disconnected();
             }
             Err(e) => error!("[{}] Connection failed: {}", listener, e),
         }
@@ -160,31 +170,150 @@ pub async fn polymarket_market_listener(asset_ids: &[String], event_tx: Arc<Coun
     polymarket_websocket_handler(Listener::PolyMarket, sub_request, event_tx.clone()).await;
 }
 
-pub async fn polymarket_market_listener_legacy(
-    asset_ids: &Vec<&str>,
+/// Change to the asset set of a dynamic market listener.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionCommand {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    /// Unsubscribes and subscribes again assets already held, which makes the websocket
+    /// send fresh snapshots of their books.
+    Resubscribe(Vec<String>),
+}
+
+impl SubscriptionCommand {
+    fn apply(&self, asset_ids: &mut BTreeSet<String>) {
+        match self {
+            SubscriptionCommand::Subscribe(ids) => asset_ids.extend(ids.iter().cloned()),
+            SubscriptionCommand::Unsubscribe(ids) => {
+                for id in ids {
+                    asset_ids.remove(id);
+                }
+            }
+            SubscriptionCommand::Resubscribe(_) => {}
+        }
+    }
+
+    fn messages(&self) -> Vec<String> {
+        let message = |ids: &Vec<String>, operation: &str| {
+            json!({ "assets_ids": ids, "operation": operation, "custom_feature_enabled": true })
+                .to_string()
+        };
+        match self {
+            SubscriptionCommand::Subscribe(ids) => vec![message(ids, "subscribe")],
+            SubscriptionCommand::Unsubscribe(ids) => vec![message(ids, "unsubscribe")],
+            SubscriptionCommand::Resubscribe(ids) => {
+                vec![message(ids, "unsubscribe"), message(ids, "subscribe")]
+            }
+        }
+    }
+}
+
+/// Legacy market listener whose asset set changes while connected. The current set is
+/// subscribed in full after every reconnect. `connection` only labels the logs.
+pub async fn polymarket_dynamic_market_listener(
+    connection: usize,
+    initial_asset_ids: Vec<String>,
+    mut commands: UnboundedReceiver<SubscriptionCommand>,
     event_tx: Arc<CountingSender>,
 ) {
-    if asset_ids.is_empty() {
-        warn!(
-            "[{}] No asset IDs provided. Listener will not start.",
-            Listener::PolyMarketLegacy
-        );
-        return;
-    }
+    let listener = format!("{}#{}", Listener::PolyMarketLegacy, connection);
+    let mut asset_ids: BTreeSet<String> = initial_asset_ids.into_iter().collect();
+    let health = FEED_HEALTH.feed(&listener);
 
-    let subscription_msg = json!({
-        "assets_ids": asset_ids,
-        "type": "market"
-    })
-    .to_string();
+    loop {
+        // Nothing to listen to yet: wait for the first subscription.
+        while asset_ids.is_empty() {
+            match commands.recv().await {
+                Some(command) => command.apply(&mut asset_ids),
+                None => {
+                    FEED_HEALTH.remove(&listener);
+                    return;
+                }
+            }
+        }
 
-    polymarket_websocket_handler_with_message(
-        Listener::PolyMarketLegacy,
-        POLY_WEBSOCKET_URL_OLD,
-        subscription_msg,
-        event_tx.clone(),
-    )
-    .await;
+        match connect_with_tls12(POLY_WEBSOCKET_URL_OLD).await {
+            Ok(ws_stream) => {
+                info!("[{}] Dynamic listener connected.", listener);
+                let (mut write, mut read) = ws_stream.split();
+                // Custom features add `market_resolved`, which closes the market's tokens.
+                let subscription_msg = json!({
+                    "assets_ids": asset_ids,
+                    "type": "market",
+                    "custom_feature_enabled": true,
+                })
+                .to_string();
+                if let Err(e) = write.send(Message::Text(subscription_msg)).await {
+                    error!("[{}] Failed to subscribe: {}. Retrying...", listener, e);
+                    time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+                info!("[{}] Subscribed to {} assets.", listener, asset_ids.len());
+                health.on_connected();
+                let mut ping_interval = time::interval(Duration::from_secs(PING_INTERVAL_S));
+                loop {
+                    tokio::select! {
+                        Some(msg_result) = read.next() => {
+                            let connected = match msg_result {
+                                Ok(Message::Text(text)) => {
+                                    health.on_message(text.as_bytes());
+                                    forward_message(Listener::PolyMarketLegacy, text, &event_tx);
+                                    true
+                                }
+                                Ok(Message::Ping(p)) => write.send(Message::Pong(p)).await.is_ok(),
+                                Ok(Message::Close(_)) => {
+                                    warn!("[{}] Connection closed by server.", listener);
+                                    false
+                                }
+                                Err(e) => {
+                                    error!("[{}] WebSocket stream error: {}.", listener, e);
+                                    false
+                                }
+                                _ => true,
+                            };
+                            if !connected {
+                                break;
+                            }
+                        }
+                        command = commands.recv() => {
+                            let Some(command) = command else {
+                                info!("[{}] Subscription channel closed. Stopping dynamic listener.", listener);
+                                FEED_HEALTH.remove(&listener);
+                                return;
+                            };
+                            command.apply(&mut asset_ids);
+                            let mut sent = true;
+                            for message in command.messages() {
+                                if write.send(Message::Text(message)).await.is_err() {
+                                    sent = false;
+                                    break;
+                                }
+                            }
+                            if !sent {
+                                error!("[{}] Failed to send {:?}.", listener, command);
+                                break;
+                            }
+                            info!("[{}] {:?} ({} assets now).", listener, command, asset_ids.len());
+                        }
+                        _ = ping_interval.tick() => {
+                            if write.send(Message::Text(r#"{"type":"ping"}"#.to_string())).await.is_err() {
+                                error!("[{}] Failed to send app-level ping.", listener); break;
+                            }
+                        }
+                    }
+                }
+                health.on_disconnected();
+                // Updates sent while we reconnect are lost; the books resync from the
+                // snapshots sent on resubscription.
+                let _ = event_tx.send(SocketEvent::MarketDisconnected {
+                    asset_ids: asset_ids.iter().cloned().collect(),
+                });
+            }
+            Err(e) => error!("[{}] Connection failed: {}", listener, e),
+        }
+        warn!("[{}] Listener DOWN. Reconnecting in 5s...", listener);
+        time::sleep(Duration::from_secs(5)).await;
+    }
 }
 
 pub async fn polymarket_user_listener(event_tx: Arc<CountingSender>) {
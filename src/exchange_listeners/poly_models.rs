@@ -156,6 +156,9 @@ pub struct PolymarketMessageWrapperOld {
     pub bids: Vec<OrderbookEntry>,
     #[serde(default)]
     pub asks: Vec<OrderbookEntry>,
+    /// Tokens of the market, on `market_resolved`.
+    #[serde(default)]
+    pub assets_ids: Vec<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -199,9 +202,11 @@ pub struct PriceChange {
     pub size: String,
     #[serde(rename = "si")]
     pub side: String,
-    // pub hash: String,
-    // pub best_bid: String,
-    // pub best_ask: String
+    /// Top of book after the change, when the feed reports it (legacy channel only).
+    #[serde(default)]
+    pub best_bid: Option<String>,
+    #[serde(default)]
+    pub best_ask: Option<String>,
 }
 #[derive(Deserialize, Debug, Clone)]
 pub struct TickSizeChangePayload {
@@ -281,6 +286,7 @@ impl OrderEventType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Option<Self> {
         match value {
             "PLACEMENT" => Some(OrderEventType::PLACEMENT),
@@ -317,6 +323,7 @@ impl OrderSide {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Option<Self> {
         match value {
             "BUY" => Some(OrderSide::Buy),
@@ -389,6 +396,8 @@ pub enum OrderState {
 pub struct OpenOrder {
     id: Option<String>,
     asset: String,
+    /// Strategy that placed the order; `None` for orders of the operator.
+    strategy: Option<&'static str>,
     state: OrderState,
     price: u32,
     size: u32,
@@ -400,6 +409,7 @@ impl OpenOrder {
         let mut order = Self {
             id: None,
             asset,
+            strategy: None,
             state: OrderState::Unconfirmed,
             price,
             size,
@@ -417,6 +427,10 @@ impl OpenOrder {
         &self.asset
     }
 
+    pub fn strategy(&self) -> Option<&'static str> {
+        self.strategy
+    }
+
     pub fn state(&self) -> OrderState {
         self.state
     }
@@ -442,6 +456,10 @@ impl OpenOrder {
         };
     }
 
+    pub fn set_strategy(&mut self, strategy: Option<&'static str>) {
+        self.strategy = strategy;
+    }
+
     pub fn set_state(&mut self, state: OrderState) {
         self.state = state;
     }
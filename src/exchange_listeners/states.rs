@@ -1,21 +1,29 @@
-use dashmap::DashMap;
+use chrono::{DateTime, Utc};
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
 };
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     exchange_listeners::{
-        crypto_models::CryptoPrice,
+        autodiscover_markets::MarketConfig,
+        crypto_models::{CryptoPrice, FairValue, Rates},
         orderbooks::{
             poly_orderbook::{OrderBook, OrderBookSnapshot},
             CryptoOrderbook, OrderbookDepth,
         },
+        paper_exchange::PaperLedger,
         poly_client::PolyClient,
         poly_models::{AssetOrders, OpenOrder, Position, RateLimit},
-        Exchange, Instrument,
+        Crypto, Exchange, Instrument,
     },
     marketmaking::poly_market_struct::Market,
+    strategies::pricing::binary_option::BinaryQuote,
 };
 
 // --- Shared State Structure (Unchanged) ---
@@ -25,16 +33,314 @@ pub struct PolyMarketState {
     pub prev_orderbooks: Arc<DashMap<String, OrderBookSnapshot>>,
     pub positions: Arc<DashMap<String, Arc<RwLock<Position>>>>,
     pub open_orders: Arc<DashMap<String, AssetOrders>>,
-    pub markets: Arc<HashMap<String, Arc<Market>>>,
+    pub markets: Arc<DashMap<String, Arc<Market>>>,
     pub rate_limit: Arc<RwLock<RateLimit>>,
+    /// Live hourly up-or-down market per underlying, replaced on every rollover.
+    pub up_down_markets: Arc<DashMap<Crypto, MarketConfig>>,
+    /// Books that may have missed updates; no orders are placed on them until a fresh snapshot.
+    pub stale_books: Arc<DashMap<String, StaleBook>>,
+    /// Where books that missed updates are sent to be resynced. Unset in backtests.
+    pub book_resync_tx: Option<UnboundedSender<String>>,
+    /// Event processor lanes whose events wait longer than the lag threshold, with the
+    /// lag that tripped it; no orders are placed while any lane lags.
+    pub lagging_lanes: Arc<DashMap<String, Duration>>,
+    /// Set once shutdown starts; no orders are placed afterwards.
+    pub shutting_down: Arc<AtomicBool>,
+    /// Strategies that may not place orders until resumed. They still get every event
+    /// and timer, so they resume with current state.
+    pub paused_strategies: Arc<DashSet<&'static str>>,
+    /// Set by the operator; strategies may not place orders for the rest of the session.
+    pub kill_switch: Arc<AtomicBool>,
+    /// Orders are filled against our books instead of sent (paper trading and backtests).
+    pub paper_trading: bool,
+    /// Fills of paper orders; empty unless `paper_trading`.
+    pub paper_ledger: Arc<PaperLedger>,
+    /// Time of the latest replayed event, in epoch milliseconds. Set in backtests only;
+    /// elsewhere `now` is the wall clock.
+    pub replay_clock: Option<Arc<AtomicI64>>,
+}
+
+/// Why an orderbook stopped being trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleReason {
+    /// Our top of book disagrees with the best prices reported alongside a price change.
+    TopOfBookMismatch,
+    /// The connection carrying the book dropped; updates may have been missed.
+    Disconnected,
+    /// The processor lane of the book was full and dropped one of its updates.
+    Overflow,
 }
 
-/// The main application state, holding final, converted USDT prices.
+#[derive(Debug, Clone)]
+pub struct StaleBook {
+    pub reason: StaleReason,
+    pub since: Instant,
+}
+
+impl PolyMarketState {
+    /// Makes the tick size and minimum size of `market`'s tokens known to order placement
+    /// and book handling. Without metadata the tokens are left as they are.
+    pub fn register_market(&self, market: &MarketConfig) {
+        for (token_id, metadata) in market.token_metadata() {
+            self.markets.insert(token_id, metadata);
+        }
+    }
+
+    /// Current time: that of the replayed events in backtests, the wall clock otherwise.
+    pub fn now(&self) -> DateTime<Utc> {
+        match &self.replay_clock {
+            Some(clock) => {
+                DateTime::from_timestamp_millis(clock.load(Ordering::Relaxed)).unwrap_or_default()
+            }
+            None => Utc::now(),
+        }
+    }
+
+    /// Moves the replay clock forward to `timestamp_ms`. Events of different lanes are
+    /// handled out of order, so the clock never goes back. No-op outside backtests.
+    pub fn advance_replay_clock(&self, timestamp_ms: i64) {
+        if let Some(clock) = &self.replay_clock {
+            clock.fetch_max(timestamp_ms, Ordering::Relaxed);
+        }
+    }
+
+    /// Marks `asset_id` stale. Returns `true` if it was fresh before.
+    pub fn mark_book_stale(&self, asset_id: &str, reason: StaleReason) -> bool {
+        match self.stale_books.entry(asset_id.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(StaleBook {
+                    reason,
+                    since: Instant::now(),
+                });
+                true
+            }
+        }
+    }
+
+    /// Marks `asset_id` stale and requests a fresh snapshot if it was fresh before.
+    pub fn resync_book(&self, asset_id: &str, reason: StaleReason) -> bool {
+        if !self.mark_book_stale(asset_id, reason) {
+            return false;
+        }
+        if let Some(resync_tx) = &self.book_resync_tx {
+            let _ = resync_tx.send(asset_id.to_string());
+        }
+        true
+    }
+
+    /// Clears the stale flag after a fresh snapshot. Returns the previous state, if any.
+    pub fn mark_book_fresh(&self, asset_id: &str) -> Option<StaleBook> {
+        self.stale_books.remove(asset_id).map(|(_, stale)| stale)
+    }
+
+    pub fn is_book_stale(&self, asset_id: &str) -> bool {
+        self.stale_books.contains_key(asset_id)
+    }
+
+    /// Marks processor lane `lane` as lagging. Returns `true` if it was caught up before.
+    pub fn mark_lane_lagging(&self, lane: &str, lag: Duration) -> bool {
+        match self.lagging_lanes.entry(lane.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(lag);
+                true
+            }
+        }
+    }
+
+    /// Returns `true` if `lane` was lagging.
+    pub fn mark_lane_caught_up(&self, lane: &str) -> bool {
+        self.lagging_lanes.remove(lane).is_some()
+    }
+
+    pub fn is_processing_lagging(&self) -> bool {
+        !self.lagging_lanes.is_empty()
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if `strategy` was running before.
+    pub fn pause_strategy(&self, strategy: &'static str) -> bool {
+        self.paused_strategies.insert(strategy)
+    }
+
+    /// Returns `true` if `strategy` was paused before.
+    pub fn resume_strategy(&self, strategy: &str) -> bool {
+        self.paused_strategies.remove(strategy).is_some()
+    }
+
+    pub fn is_strategy_paused(&self, strategy: &str) -> bool {
+        self.paused_strategies.contains(strategy)
+    }
+
+    /// Returns `true` if the kill switch was not tripped before.
+    pub fn trip_kill_switch(&self) -> bool {
+        !self.kill_switch.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_kill_switch_tripped(&self) -> bool {
+        self.kill_switch.load(Ordering::SeqCst)
+    }
+
+    /// Orders in `open_orders`, live or not yet confirmed.
+    pub fn open_order_count(&self) -> usize {
+        self.open_orders
+            .iter()
+            .map(|orders| orders.bids.len() + orders.asks.len())
+            .sum()
+    }
+}
+
+pub type CryptoOrderbookMap = DashMap<(Exchange, Instrument, OrderbookDepth), CryptoOrderbook>;
+pub type CryptoPriceMap = DashMap<(Exchange, Instrument, OrderbookDepth), CryptoPrice>;
+
+/// The main application state, holding venue prices per underlying and the
+/// stablecoin rates used to express them in USD.
 #[derive(Debug, Clone, Default)]
 pub struct AppState {
-    pub btc_orderbooks: Arc<DashMap<(Exchange, Instrument, OrderbookDepth), CryptoOrderbook>>,
-    // pub eth_orderbooks: Arc<DashMap<(Exchange, Instrument, OrderbookDepth), CryptoOrderbook>>,
-    // pub xrp_orderbooks: Arc<DashMap<(Exchange, Instrument, OrderbookDepth), CryptoOrderbook>>,
-    // pub sol_orderbooks: Arc<DashMap<(Exchange, Instrument, OrderbookDepth), CryptoOrderbook>>,
-    pub btc_prices: Arc<DashMap<(Exchange, Instrument, OrderbookDepth), CryptoPrice>>,
+    pub orderbooks: Arc<DashMap<Crypto, Arc<CryptoOrderbookMap>>>,
+    pub prices: Arc<DashMap<Crypto, Arc<CryptoPriceMap>>>,
+    pub fair_values: Arc<DashMap<Crypto, FairValue>>,
+    pub rates: Arc<Rates>,
+    /// Model fair values of binary crypto markets, keyed by outcome token id.
+    pub binary_quotes: Arc<DashMap<String, BinaryQuote>>,
+}
+
+impl AppState {
+    /// Orderbooks of `crypto`, created on first use.
+    pub fn orderbooks_for(&self, crypto: Crypto) -> Arc<CryptoOrderbookMap> {
+        Arc::clone(self.orderbooks.entry(crypto).or_default().value())
+    }
+
+    /// Prices of `crypto`, created on first use.
+    pub fn prices_for(&self, crypto: Crypto) -> Arc<CryptoPriceMap> {
+        Arc::clone(self.prices.entry(crypto).or_default().value())
+    }
+
+    /// USD per unit of the quote currency of `exchange`'s `instrument` market for `crypto`.
+    pub fn usd_rate(
+        &self,
+        exchange: Exchange,
+        instrument: Instrument,
+        crypto: Crypto,
+    ) -> Option<f64> {
+        self.rates.usd_per(exchange.quote(instrument, crypto))
+    }
+
+    /// Re-applies the current rates to every stored price, e.g. after a rate update.
+    pub fn refresh_usd_rates(&self) {
+        for prices in self.prices.iter() {
+            let crypto = *prices.key();
+            for mut price in prices.value().iter_mut() {
+                let (exchange, instrument, _) = *price.key();
+                price.usd_rate = self.usd_rate(exchange, instrument, crypto);
+            }
+        }
+    }
+
+    /// Latest composite fair value of `crypto`, if enough venues are live.
+    pub fn fair_value(&self, crypto: Crypto) -> Option<FairValue> {
+        self.fair_values.get(&crypto).map(|fv| *fv)
+    }
+
+    /// Latest model quote of an outcome token, from that token's side.
+    pub fn binary_quote(&self, token_id: &str) -> Option<BinaryQuote> {
+        self.binary_quotes.get(token_id).map(|quote| *quote)
+    }
+
+    /// Underlyings with an orderbook map, i.e. every `crypto` passed to `orderbooks_for`
+    /// so far. A listed map may still be empty.
+    pub fn tracked_cryptos(&self) -> Vec<Crypto> {
+        let mut cryptos: Vec<Crypto> = self.orderbooks.iter().map(|e| *e.key()).collect();
+        cryptos.sort();
+        cryptos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::crypto_models::RateKind;
+
+    const BINANCE_SPOT: (Exchange, Instrument, OrderbookDepth) =
+        (Exchange::Binance, Instrument::Spot, OrderbookDepth::L1);
+
+    #[test]
+    fn crypto_maps_are_kept_per_underlying() {
+        let app_state = AppState::default();
+        assert!(app_state.tracked_cryptos().is_empty());
+
+        app_state
+            .orderbooks_for(Crypto::ETH)
+            .insert(BINANCE_SPOT, CryptoOrderbook::default());
+        let btc = app_state.orderbooks_for(Crypto::BTC);
+        assert!(Arc::ptr_eq(&btc, &app_state.orderbooks_for(Crypto::BTC)));
+        assert!(btc.is_empty());
+        assert_eq!(app_state.orderbooks_for(Crypto::ETH).len(), 1);
+        assert_eq!(app_state.tracked_cryptos(), vec![Crypto::BTC, Crypto::ETH]);
+
+        app_state.prices_for(Crypto::SOL);
+        assert!(app_state.prices_for(Crypto::BTC).is_empty());
+        assert_eq!(app_state.tracked_cryptos(), vec![Crypto::BTC, Crypto::ETH]);
+    }
+
+    #[test]
+    fn rate_updates_and_clears_reach_stored_prices() {
+        let app_state = AppState::default();
+        let deribit_perp = (Exchange::Deribit, Instrument::Perpetual, OrderbookDepth::L1);
+        let prices = app_state.prices_for(Crypto::BTC);
+        for key in [BINANCE_SPOT, deribit_perp] {
+            let mut price = CryptoPrice::new();
+            price.set(100_000.0, 100_000.0, None);
+            prices.insert(key, price);
+        }
+
+        app_state.refresh_usd_rates();
+        assert_eq!(prices.get(&BINANCE_SPOT).unwrap().usd_price(), None);
+        assert_eq!(prices.get(&deribit_perp).unwrap().usd_rate, Some(1.0));
+
+        app_state.rates.set(RateKind::UsdUsdtCoinbase, 0.999);
+        app_state.refresh_usd_rates();
+        assert_eq!(
+            prices.get(&BINANCE_SPOT).unwrap().usd_price(),
+            Some(99_900.0)
+        );
+
+        // Without the Coinbase rate USDT goes through Binance USDC.
+        app_state.rates.set(RateKind::UsdcUsdtBinance, 1.25);
+        app_state.rates.clear(RateKind::UsdUsdtCoinbase);
+        app_state.refresh_usd_rates();
+        assert_eq!(prices.get(&BINANCE_SPOT).unwrap().usd_rate, Some(0.8));
+
+        app_state.rates.clear(RateKind::UsdcUsdtBinance);
+        app_state.refresh_usd_rates();
+        assert_eq!(prices.get(&BINANCE_SPOT).unwrap().usd_rate, None);
+        assert_eq!(prices.get(&deribit_perp).unwrap().usd_rate, Some(1.0));
+    }
+
+    #[test]
+    fn concurrent_stale_marks_report_one_transition() {
+        let poly_state = Arc::new(PolyMarketState::default());
+        let fresh_before: usize = (0..8)
+            .map(|_| {
+                let poly_state = Arc::clone(&poly_state);
+                std::thread::spawn(move || {
+                    poly_state.mark_book_stale("1", StaleReason::Disconnected) as usize
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+        assert_eq!(fresh_before, 1);
+        assert!(poly_state.mark_book_fresh("1").is_some());
+        assert!(poly_state.mark_book_stale("1", StaleReason::TopOfBookMismatch));
+    }
 }
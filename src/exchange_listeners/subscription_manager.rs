@@ -0,0 +1,305 @@
+use log::{error, info};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::event_processor::{CountingSender, SocketEvent};
+use super::poly_listeners::{
+    polymarket_dynamic_market_listener, SubscriptionCommand, MAX_ASSETS_PER_SUB,
+};
+
+/// What the manager has to do to the connection pool after a command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolAction {
+    /// Spawn a new connection with this id.
+    Open(usize),
+    Send(usize, SubscriptionCommand),
+    /// Drop an empty connection.
+    Close(usize),
+}
+
+/// Assignment of asset IDs to market connections of at most `capacity` assets each.
+#[derive(Debug)]
+pub struct SubscriptionPool {
+    capacity: usize,
+    next_id: usize,
+    connections: HashMap<usize, BTreeSet<String>>,
+    assignments: HashMap<String, usize>,
+}
+
+impl SubscriptionPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            next_id: 0,
+            connections: HashMap::new(),
+            assignments: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.assignments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn contains(&self, asset_id: &str) -> bool {
+        self.assignments.contains_key(asset_id)
+    }
+
+    /// Applies `command` and returns the connection changes it requires.
+    pub fn apply(&mut self, command: SubscriptionCommand) -> Vec<PoolAction> {
+        let mut actions = Vec::new();
+        let mut moves: HashMap<usize, Vec<String>> = HashMap::new();
+        match command {
+            SubscriptionCommand::Subscribe(ids) => {
+                for id in ids {
+                    if self.assignments.contains_key(&id) {
+                        continue;
+                    }
+                    let connection = self.connection_with_room(&mut actions);
+                    self.assign(id.clone(), connection);
+                    moves.entry(connection).or_default().push(id);
+                }
+                push_sends(&mut actions, moves, SubscriptionCommand::Subscribe);
+            }
+            SubscriptionCommand::Unsubscribe(ids) => {
+                for id in ids {
+                    let Some(connection) = self.assignments.remove(&id) else {
+                        continue;
+                    };
+                    if let Some(assets) = self.connections.get_mut(&connection) {
+                        assets.remove(&id);
+                    }
+                    moves.entry(connection).or_default().push(id);
+                }
+                push_sends(&mut actions, moves, SubscriptionCommand::Unsubscribe);
+                self.rebalance(&mut actions);
+            }
+            SubscriptionCommand::Resubscribe(ids) => {
+                for id in ids {
+                    if let Some(connection) = self.assignments.get(&id) {
+                        moves.entry(*connection).or_default().push(id);
+                    }
+                }
+                push_sends(&mut actions, moves, SubscriptionCommand::Resubscribe);
+            }
+        }
+        actions
+    }
+
+    /// Fullest connection that still has room, opening a new one if all are full.
+    /// Filling the fullest first keeps the pool small.
+    fn connection_with_room(&mut self, actions: &mut Vec<PoolAction>) -> usize {
+        let fullest = self
+            .connections
+            .iter()
+            .filter(|(_, assets)| assets.len() < self.capacity)
+            .max_by_key(|(id, assets)| (assets.len(), std::cmp::Reverse(**id)))
+            .map(|(id, _)| *id);
+        if let Some(id) = fullest {
+            return id;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.connections.insert(id, BTreeSet::new());
+        actions.push(PoolAction::Open(id));
+        id
+    }
+
+    fn assign(&mut self, asset_id: String, connection: usize) {
+        if let Some(assets) = self.connections.get_mut(&connection) {
+            assets.insert(asset_id.clone());
+        }
+        self.assignments.insert(asset_id, connection);
+    }
+
+    /// Drains the emptiest connections into the others while the pool holds more
+    /// connections than its assets need, closing the drained ones.
+    fn rebalance(&mut self, actions: &mut Vec<PoolAction>) {
+        loop {
+            let needed = self.assignments.len().div_ceil(self.capacity);
+            if self.connections.len() <= needed {
+                return;
+            }
+            let Some(emptiest) = self
+                .connections
+                .iter()
+                .min_by_key(|(id, assets)| (assets.len(), std::cmp::Reverse(**id)))
+                .map(|(id, _)| *id)
+            else {
+                return;
+            };
+            let drained = self.connections.remove(&emptiest).unwrap_or_default();
+            let mut moves: HashMap<usize, Vec<String>> = HashMap::new();
+            for id in drained.iter() {
+                let target = self.connection_with_room(actions);
+                self.assign(id.clone(), target);
+                moves.entry(target).or_default().push(id.clone());
+            }
+            // Subscribe on the target first so the moved books never go silent.
+            push_sends(actions, moves, SubscriptionCommand::Subscribe);
+            if !drained.is_empty() {
+                actions.push(PoolAction::Send(
+                    emptiest,
+                    SubscriptionCommand::Unsubscribe(drained.into_iter().collect()),
+                ));
+            }
+            actions.push(PoolAction::Close(emptiest));
+        }
+    }
+}
+
+fn push_sends(
+    actions: &mut Vec<PoolAction>,
+    moves: HashMap<usize, Vec<String>>,
+    command: fn(Vec<String>) -> SubscriptionCommand,
+) {
+    let mut moves: Vec<_> = moves.into_iter().collect();
+    moves.sort_by_key(|(connection, _)| *connection);
+    actions.extend(
+        moves
+            .into_iter()
+            .map(|(connection, ids)| PoolAction::Send(connection, command(ids))),
+    );
+}
+
+/// `MarketAdded` for the assets `command` subscribes that the pool does not hold yet.
+/// Resubscribed assets are live already.
+fn market_added(pool: &SubscriptionPool, command: &SubscriptionCommand) -> Option<SocketEvent> {
+    let SubscriptionCommand::Subscribe(ids) = command else {
+        return None;
+    };
+    let asset_ids: Vec<String> = ids
+        .iter()
+        .filter(|id| !pool.contains(id))
+        .cloned()
+        .collect();
+    (!asset_ids.is_empty()).then_some(SocketEvent::MarketAdded { asset_ids })
+}
+
+/// Owns the pool of legacy market connections. Asset IDs are added and removed through
+/// `commands`; every connection resubscribes its own set after a reconnect. Strategies
+/// are told which assets were actually added. Removals are not reported: closures come
+/// from rollover and resolution.
+pub async fn run_subscription_manager(
+    mut commands: UnboundedReceiver<SubscriptionCommand>,
+    event_tx: Arc<CountingSender>,
+) {
+    let mut pool = SubscriptionPool::new(MAX_ASSETS_PER_SUB);
+    let mut senders: HashMap<usize, UnboundedSender<SubscriptionCommand>> = HashMap::new();
+
+    while let Some(command) = commands.recv().await {
+        let added = market_added(&pool, &command);
+        for action in pool.apply(command) {
+            match action {
+                PoolAction::Open(id) => {
+                    let (tx, rx) = mpsc::unbounded_channel();
+                    tokio::spawn(polymarket_dynamic_market_listener(
+                        id,
+                        Vec::new(),
+                        rx,
+                        Arc::clone(&event_tx),
+                    ));
+                    senders.insert(id, tx);
+                }
+                PoolAction::Send(id, command) => {
+                    let sent = senders.get(&id).map(|tx| tx.send(command).is_ok());
+                    if sent != Some(true) {
+                        error!("[Subscriptions] Connection {} is gone.", id);
+                    }
+                }
+                PoolAction::Close(id) => {
+                    senders.remove(&id);
+                }
+            }
+        }
+        if let Some(event) = added {
+            if event_tx.send(event).is_err() {
+                error!("[Subscriptions] Event processor is gone.");
+            }
+        }
+        info!(
+            "[Subscriptions] {} assets on {} connections.",
+            pool.len(),
+            pool.connections()
+        );
+    }
+    info!("[Subscriptions] Command channel closed. Stopping subscription manager.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn fills_connections_and_drains_after_unsubscribe() {
+        let mut pool = SubscriptionPool::new(3);
+        let actions = pool.apply(SubscriptionCommand::Subscribe(ids(0..5)));
+        assert_eq!(
+            actions,
+            vec![
+                PoolAction::Open(0),
+                PoolAction::Open(1),
+                PoolAction::Send(0, SubscriptionCommand::Subscribe(ids(0..3))),
+                PoolAction::Send(1, SubscriptionCommand::Subscribe(ids(3..5))),
+            ]
+        );
+        // Duplicates are ignored.
+        assert!(pool
+            .apply(SubscriptionCommand::Subscribe(ids(0..1)))
+            .is_empty());
+
+        // Four assets need two connections, so nothing moves.
+        pool.apply(SubscriptionCommand::Unsubscribe(ids(0..1)));
+        assert_eq!(pool.connections(), 2);
+
+        // Three fit in one: the emptier connection 0 is drained into connection 1 and closed.
+        let actions = pool.apply(SubscriptionCommand::Unsubscribe(ids(1..2)));
+        assert_eq!(
+            actions,
+            vec![
+                PoolAction::Send(0, SubscriptionCommand::Unsubscribe(ids(1..2))),
+                PoolAction::Send(1, SubscriptionCommand::Subscribe(ids(2..3))),
+                PoolAction::Send(0, SubscriptionCommand::Unsubscribe(ids(2..3))),
+                PoolAction::Close(0),
+            ]
+        );
+        assert_eq!((pool.len(), pool.connections()), (3, 1));
+    }
+
+    #[test]
+    fn only_new_subscriptions_are_reported() {
+        let mut pool = SubscriptionPool::new(3);
+        let subscribe = SubscriptionCommand::Subscribe(ids(0..2));
+        assert!(matches!(
+            market_added(&pool, &subscribe),
+            Some(SocketEvent::MarketAdded { asset_ids }) if asset_ids == ids(0..2)
+        ));
+        pool.apply(subscribe);
+        assert!(market_added(&pool, &SubscriptionCommand::Subscribe(ids(1..2))).is_none());
+        assert!(market_added(&pool, &SubscriptionCommand::Unsubscribe(ids(1..2))).is_none());
+
+        // A resync resubscribes a live asset on its own connection: nothing is added.
+        let resubscribe = SubscriptionCommand::Resubscribe(ids(1..3));
+        assert!(market_added(&pool, &resubscribe).is_none());
+        assert_eq!(
+            pool.apply(resubscribe),
+            vec![PoolAction::Send(
+                0,
+                SubscriptionCommand::Resubscribe(ids(1..2))
+            )]
+        );
+        assert_eq!((pool.len(), pool.connections()), (2, 1));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod backtest;
+pub mod cli;
+pub mod clob_client;
+pub mod config;
+pub mod control;
+pub mod credentials;
+pub mod exchange_listeners;
+pub mod marketmaking;
+pub mod metrics;
+pub mod poly_orderbooks;
+pub mod shutdown;
+pub mod strategies;
@@ -1,82 +1,63 @@
 //main.rs
-pub mod clob_client;
-pub mod config;
-pub mod credentials;
-pub mod marketmaking;
-pub mod poly_orderbooks;
-pub mod strategies;
-
-use itertools::Itertools;
-use log::{info, warn};
-use strategies::{Strategy, UpdateOrderStrategy, UpdateOrderbookStrategy, UpdatePositionStrategy};
-
-use clob_client::{
-    client::ClobClient,
-    prebuilt_order::{build_prebuilt_order, PrebuiltOrder},
+
+use polymarket::{
+    backtest, cli, clob_client, control, exchange_listeners, marketmaking, metrics, shutdown,
+    strategies,
 };
 
-use ethers::abi::Hash;
+use log::info;
+use strategies::registry::{self, LaunchConfig, StrategyDeps, StrategySelection};
 
 use std::{
-    alloc::System,
-    cmp::{min, Ordering},
-    collections::{HashMap, HashSet, VecDeque},
-    ops::Deref,
     process,
-    sync::{atomic, Arc, RwLock},
-    thread,
-    time::{Instant, SystemTime},
+    sync::{atomic, Arc},
 };
 
-use tokio::time::{sleep, Duration};
+use tokio::time::Duration;
 
-use clob_client::constants::{FRAC_CENTS, FULL_CENTS};
+use clob_client::constants::POLYGON;
+use clob_client::signer::PolySigner;
 
-use dashmap::DashMap;
-use marketmaking::{
-    marketmakingclient::CLIENT,
-    poly_get_markets::fetch_neg_risk_markets,
-    poly_market_struct::{
-        build_asset_id_to_event_map, build_asset_id_to_market_map, Event, EventJson, Market,
-    },
-};
+use marketmaking::{marketmakingclient, poly_get_markets::fetch_neg_risk_markets};
 
-mod exchange_listeners;
 use exchange_listeners::{event_processor, AppState, PolyMarketState};
-use tokio::runtime;
-
-use crate::{
-    credentials::ADDRESS_STR, exchange_listeners::{autodiscover_markets::autodiscover_market_config, poly_models::{Position, get_positions}}, marketmaking::poly_market_struct::events_json_to_events_with_market_map, strategies::{
-        app_state_updates::update_crypto_orderbooks::UpdateCryptoOrderbookStrategy,
-        custom::{koen::koen_strategy::KoenStrategy, tob::tob_strategy::TobStrategy},
-        logging::{
-            bbo_logging::BBOLoggingStrategy, crypto_logging::CryptoLoggingStrategy,
-            main_logging::MainLoggingStrategy, order_logging::OrderLoggingStrategy,
-            position_logging::PositionLoggingStrategy, trade_logging::TradeLoggingStrategy,
-        },
-    }
+
+use polymarket::{
+    cli::{Cli, Mode, USAGE},
+    config::{
+        CONTROL_ADDR, EVENT_SHARDS, METRICS_ADDR, SHUTDOWN_TIMEOUT_MS, TRACKED_CRYPTOS,
+        VARIANCE_PROFILE_PATH, VARIANCE_PROFILE_STATE_PATH,
+    },
+    control::{server::CONTROL_TOKEN_ENV, ControlPlane},
+    credentials::ADDRESS_STR,
+    exchange_listeners::{
+        book_resync, feed_health, market_discovery,
+        market_rollover::{self, RolloverSchedule},
+        poly_listeners::SubscriptionCommand,
+        poly_models::get_positions,
+        subscription_manager, Crypto,
+    },
+    marketmaking::poly_market_struct::events_json_to_events_with_market_map,
+    strategies::pricing::variance_profile::VarianceProfile,
 };
 
-fn log_initial_positions(positions: &Arc<DashMap<String, Arc<RwLock<Position>>>>) {
-    if positions.is_empty() {
-        info!("No initial positions found for the configured account.");
+fn main() {
+    let cli = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{:#}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if cli.help {
+        println!("{}", USAGE);
         return;
     }
-
-    info!("Loaded {} initial positions:", positions.len());
-    for entry in positions.iter() {
-        let asset_id = entry.key();
-        match entry.value().read() {
-            Ok(position) => {
-                let display_size = position.size as f64 / 1000.0;
-                info!("    {} => {:.3}", asset_id, display_size);
-            }
-            Err(_) => warn!("    {} => <failed to read position lock>", asset_id),
-        }
+    if cli.list_strategies {
+        println!("{}", registry::describe());
+        return;
     }
-}
 
-fn main() {
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .worker_threads(4)
         .max_blocking_threads(8)
@@ -86,88 +67,140 @@ fn main() {
 
     //runtime.block_on(debug_main());
     // runtime.block_on(strategies::pricing::pricing_main());
-    runtime.block_on(debug_main());
+    runtime.block_on(debug_main(cli));
 }
 
-async fn debug_main() {
+/// Strategies chosen by `--config` and `--strategies`, or the default set.
+fn strategy_selection(cli: &Cli) -> Vec<StrategySelection> {
+    let launch = match &cli.config {
+        Some(path) => LaunchConfig::load(path).expect("Failed to load launch config"),
+        None => LaunchConfig::default(),
+    };
+    launch.select(cli.strategies.as_deref())
+}
+
+async fn debug_main(cli: Cli) {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let selection = strategy_selection(&cli);
+    if cli.mode == Mode::Backtest {
+        return backtest_main(cli, selection).await;
+    }
+    // Paper trading never signs; live trading fails here rather than on its first order.
+    let order_client = if cli.mode == Mode::Run {
+        let client = PolySigner::from_env(POLYGON).and_then(marketmakingclient::init_client);
+        match client {
+            Ok(client) => Some(client),
+            Err(e) => {
+                eprintln!("Failed to initialise order signer: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
     info!("Fetching neg risk markets");
     let events = fetch_neg_risk_markets().await.unwrap();
-    let (events, market_map) = events_json_to_events_with_market_map(events);
+    let (_, market_map) = events_json_to_events_with_market_map(events);
 
     let app_state = Arc::new(AppState::default()); // Financial instruments
     let market_map = Arc::new(market_map); // put into Arc for sharing
     let market_asset_ids: Vec<String> = market_map.keys().cloned().collect();
-    let market_asset_ids = Arc::new(market_asset_ids);
     let positions = Arc::new(get_positions(ADDRESS_STR).await);
-    log_initial_positions(&positions.clone());
+    let (book_resync_tx, book_resync_rx) = tokio::sync::mpsc::unbounded_channel();
     let polymarket_state = Arc::new(PolyMarketState {
-        markets: Arc::clone(&market_map),
+        markets: Arc::new(
+            market_map
+                .iter()
+                .map(|(asset_id, market)| (asset_id.clone(), Arc::clone(market)))
+                .collect(),
+        ),
         positions,
+        book_resync_tx: Some(book_resync_tx.clone()),
+        paper_trading: cli.mode == Mode::Paper,
         ..Default::default()
     }); // Orderbooks
+    if let Some(client) = &order_client {
+        client.warm_order_templates(
+            market_map
+                .iter()
+                .map(|(asset_id, market)| (asset_id.as_str(), market.negRisk.unwrap_or(false))),
+        );
+        info!(
+            "Prepared order templates for {} assets",
+            client.order_templates.len()
+        );
+    }
+    let variance_profile =
+        VarianceProfile::load_or_seed(VARIANCE_PROFILE_STATE_PATH, VARIANCE_PROFILE_PATH)
+            .expect("Failed to load variance profile")
+            .into_shared();
+
     info!("Starting strategies");
-    let strategies: Vec<Arc<dyn Strategy>> = vec![
-        Arc::new(UpdateOrderbookStrategy::new()),
-        Arc::new(UpdateOrderStrategy::new()),
-        Arc::new(UpdatePositionStrategy::new()),
-        Arc::new(KoenStrategy::new()),
-        // Arc::new(OrderLoggingStrategy::new()),
-        Arc::new(PositionLoggingStrategy::new()),
-        // Arc::new(UpdateCryptoOrderbookStrategy::new()),
-        Arc::new(BBOLoggingStrategy::new()),
-        Arc::new(TradeLoggingStrategy::new()),
-        // Arc::new(MainLoggingStrategy::new()),
-        // Arc::new(CryptoLoggingStrategy),
-    ];
+    let strategies = registry::build_strategies(
+        &selection,
+        &StrategyDeps {
+            book_resync_tx,
+            variance_profile,
+            variance_profile_path: Some(VARIANCE_PROFILE_STATE_PATH.into()),
+        },
+    )
+    .expect("Invalid strategy selection");
+    let running = strategies.clone();
+    // Params edited in the launch config are applied without a restart.
+    if let Some(path) = cli.config.clone() {
+        tokio::spawn(registry::watch_launch_config(
+            path,
+            strategies.clone(),
+            selection,
+        ));
+    }
     let counting_sender = event_processor::spawn_event_processor(
         Arc::clone(&app_state),
         Arc::clone(&polymarket_state),
         strategies,
+        EVENT_SHARDS,
     );
 
-    let market_config = autodiscover_market_config("bitcoin", "btc")
-        .await
-        .unwrap()
-        .unwrap();
-
     log::info!("--- Exchange Listener Thread has been started ---");
 
-    let market_counting_sender = counting_sender.clone();
-    const MARKET_LISTENER_BATCH_SIZE: usize = 500;
-    let total_asset_ids = market_asset_ids.len();
-
-    if total_asset_ids == 0 {
-        tokio::spawn(async move {
-            let asset_refs: Vec<&str> = Vec::new();
-            exchange_listeners::poly_listeners::polymarket_market_listener_legacy(
-                &asset_refs,
-                market_counting_sender,
-            )
-            .await;
-        });
-    } else {
-        for batch_start in (0..total_asset_ids).step_by(MARKET_LISTENER_BATCH_SIZE) {
-            let batch_end = (batch_start + MARKET_LISTENER_BATCH_SIZE).min(total_asset_ids);
-            let market_asset_ids = Arc::clone(&market_asset_ids);
-            let market_counting_sender = counting_sender.clone();
-
-            tokio::spawn(async move {
-                let asset_refs: Vec<&str> = market_asset_ids[batch_start..batch_end]
-                    .iter()
-                    .map(String::as_str)
-                    .collect();
-                exchange_listeners::poly_listeners::polymarket_market_listener_legacy(
-                    &asset_refs,
-                    market_counting_sender,
-                )
-                .await;
-            });
-        }
-    }
+    // Market websocket pool: asset IDs are added and removed on the fly and spread across
+    // connections of at most MAX_ASSETS_PER_SUB assets.
+    let (market_sub_tx, market_sub_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(subscription_manager::run_subscription_manager(
+        market_sub_rx,
+        counting_sender.clone(),
+    ));
+    let _ = market_sub_tx.send(SubscriptionCommand::Subscribe(market_asset_ids));
+    // Books that fall out of sync are replaced by REST snapshots.
+    tokio::spawn(book_resync::run_book_resync(
+        book_resync_rx,
+        market_sub_tx.clone(),
+        counting_sender.clone(),
+    ));
 
-    let _exchange_listener_handles =
-        exchange_listeners::spawn_exchange_price_listeners(counting_sender.clone());
+    // Daily up-or-down, above-strike ladders and price ranges.
+    tokio::spawn(market_discovery::run_crypto_market_discovery(
+        TRACKED_CRYPTOS,
+        Arc::clone(&polymarket_state),
+        market_sub_tx.clone(),
+    ));
+    // Hourly up-or-down market, rolled over to the next market every hour.
+    tokio::spawn(market_rollover::run_hourly_rollover(
+        RolloverSchedule::new(Crypto::BTC, "bitcoin", "btc"),
+        Arc::clone(&polymarket_state),
+        market_sub_tx,
+        counting_sender.clone(),
+    ));
+
+    let _exchange_listener_handles = exchange_listeners::spawn_exchange_price_listeners(
+        counting_sender.clone(),
+        TRACKED_CRYPTOS,
+    );
+
+    tokio::spawn(feed_health::log_feed_health(Duration::from_secs(60)));
+    tokio::spawn(strategies::profiler::log_strategy_profile(
+        Duration::from_secs(60),
+    ));
 
     let user_counting_sender = counting_sender.clone();
     tokio::spawn(async move {
@@ -175,15 +208,77 @@ async fn debug_main() {
             .await;
     });
 
-    loop {
-        sleep(Duration::from_millis(1)).await;
-        let pending = counting_sender.pending();
-        if pending > 0 {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis();
-            println!("Pending events: {} - {}", counting_sender.pending(), now);
+    tokio::spawn(metrics::server::serve_metrics(
+        METRICS_ADDR,
+        counting_sender.clone(),
+        Arc::clone(&polymarket_state),
+    ));
+    match std::env::var(CONTROL_TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => {
+            tokio::spawn(control::server::serve_control(
+                CONTROL_ADDR,
+                token,
+                Arc::new(ControlPlane::new(Arc::clone(&polymarket_state), running)),
+            ));
         }
+        _ => log::warn!(
+            "[Control] {} is not set; the control API is disabled",
+            CONTROL_TOKEN_ENV
+        ),
+    }
+
+    let signal = shutdown::wait_for_signal().await;
+    info!("{} received, shutting down", signal);
+    shutdown::shutdown(
+        &counting_sender,
+        polymarket_state,
+        Duration::from_millis(SHUTDOWN_TIMEOUT_MS),
+    )
+    .await;
+}
+
+/// Runs the selected strategies over the books recorded under `--data`, without
+/// connecting anywhere; orders are filled against the replayed books, on their clock.
+async fn backtest_main(cli: Cli, mut selection: Vec<StrategySelection>) {
+    // Recording the replayed books would append them to the recording itself.
+    selection.retain(|strategy| strategy.name != "BBOLoggingStrategy");
+    let recording = backtest::Recording::load(&cli.data).expect("Failed to load recorded books");
+    let polymarket_state = Arc::new(PolyMarketState {
+        markets: Arc::new(recording.markets().into_iter().collect()),
+        paper_trading: true,
+        replay_clock: Some(Arc::new(atomic::AtomicI64::new(0))),
+        ..Default::default()
+    });
+    let variance_profile = VarianceProfile::load(VARIANCE_PROFILE_PATH)
+        .expect("Failed to load variance profile")
+        .into_shared();
+    // Stale books are not resynced: there is no exchange to ask.
+    let (book_resync_tx, _book_resync_rx) = tokio::sync::mpsc::unbounded_channel();
+    let strategies = registry::build_strategies(
+        &selection,
+        &StrategyDeps {
+            book_resync_tx,
+            variance_profile,
+            // Replayed prices must not end up in the live profile.
+            variance_profile_path: None,
+        },
+    )
+    .expect("Invalid strategy selection");
+    let counting_sender = event_processor::spawn_event_processor(
+        Arc::new(AppState::default()),
+        Arc::clone(&polymarket_state),
+        strategies,
+        EVENT_SHARDS,
+    );
+
+    match backtest::replay_recorded_books(recording, &counting_sender).await {
+        Ok(sent) => info!("[Backtest] Replayed {} events", sent),
+        Err(e) => log::error!("[Backtest] {:#}", e),
     }
+    shutdown::shutdown(
+        &counting_sender,
+        polymarket_state,
+        Duration::from_millis(SHUTDOWN_TIMEOUT_MS),
+    )
+    .await;
 }
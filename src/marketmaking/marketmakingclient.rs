@@ -1,9 +1,8 @@
-use crate::clob_client::{client::ClobClient, clob_types::ApiCreds};
-use crate::credentials::{
-    ADDRESS, POLY_API_KEY, POLY_API_PASSPHRASE, POLY_API_SECRET, PRIVATE_KEY,
-};
+use crate::clob_client::{client::ClobClient, clob_types::ApiCreds, signer::PolySigner};
+use crate::credentials::{ADDRESS, POLY_API_KEY, POLY_API_PASSPHRASE, POLY_API_SECRET};
 use lazy_static::lazy_static;
-use std::sync::Arc;
+use std::error::Error;
+use std::sync::{Arc, OnceLock};
 
 lazy_static! {
     pub static ref CREDENTIALS: ApiCreds = ApiCreds {
@@ -11,10 +10,28 @@ lazy_static! {
         api_secret: POLY_API_SECRET.to_string(),
         api_pass: POLY_API_PASSPHRASE.to_string(),
     };
-    pub static ref CLIENT: Arc<ClobClient> = Arc::new(ClobClient::new(
-        PRIVATE_KEY,
+}
+
+static CLIENT: OnceLock<Arc<ClobClient>> = OnceLock::new();
+
+/// Builds the order client around `signer`. Called once from `main` before any order is sent.
+pub fn init_client(signer: PolySigner) -> Result<Arc<ClobClient>, Box<dyn Error + Send + Sync>> {
+    let client = Arc::new(ClobClient::with_signer(
+        signer,
         CREDENTIALS.clone(),
         Some(2),
-        Some(*ADDRESS)
+        Some(*ADDRESS),
     ));
+    CLIENT
+        .set(Arc::clone(&client))
+        .map_err(|_| "order client is already initialised")?;
+    Ok(client)
+}
+
+/// The order client set up by `init_client`, or an error when no signer was configured.
+pub fn client() -> Result<Arc<ClobClient>, Box<dyn Error + Send + Sync>> {
+    CLIENT
+        .get()
+        .cloned()
+        .ok_or_else(|| "order client is not initialised".into())
 }
@@ -0,0 +1,521 @@
+//! Process metrics, rendered in the Prometheus text exposition format.
+pub mod server;
+
+use atomic_float::AtomicF64;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use std::fmt::Write;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::exchange_listeners::event_processor::CountingSender;
+use crate::exchange_listeners::feed_health::{FeedStatus, FEED_HEALTH};
+use crate::exchange_listeners::poly_models::OrderSide;
+use crate::exchange_listeners::states::PolyMarketState;
+
+/// Buckets for in-process handler latencies, 10µs to 1s.
+const HANDLER_BUCKETS: &[f64] = &[
+    0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0,
+];
+/// Buckets for REST round trips, 10ms to 5s.
+const REST_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::default();
+}
+
+/// Monotonic counter. Float-valued, as Prometheus counters are.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicF64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1.0);
+    }
+
+    pub fn inc_by(&self, value: f64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicF64);
+
+impl Gauge {
+    pub fn set(&self, value: f64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Histogram over fixed upper bounds; the last bucket is `+Inf`.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    buckets: Box<[Counter]>,
+    sum: Counter,
+    count: Counter,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| Counter::default()).collect(),
+            sum: Counter::default(),
+            count: Counter::default(),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket].inc();
+        self.sum.inc_by(value);
+        self.count.inc();
+    }
+
+    pub fn observe_duration(&self, elapsed: Duration) {
+        self.observe(elapsed.as_secs_f64());
+    }
+}
+
+/// A metric type that can write its samples for one label set.
+pub trait Metric: Send + Sync {
+    const KIND: &'static str;
+
+    fn write_samples(&self, out: &mut String, name: &str, labels: &[(&str, &str)]);
+}
+
+impl Metric for Counter {
+    const KIND: &'static str = "counter";
+
+    fn write_samples(&self, out: &mut String, name: &str, labels: &[(&str, &str)]) {
+        write_sample(out, name, labels, self.get());
+    }
+}
+
+impl Metric for Gauge {
+    const KIND: &'static str = "gauge";
+
+    fn write_samples(&self, out: &mut String, name: &str, labels: &[(&str, &str)]) {
+        write_sample(out, name, labels, self.get());
+    }
+}
+
+impl Metric for Histogram {
+    const KIND: &'static str = "histogram";
+
+    fn write_samples(&self, out: &mut String, name: &str, labels: &[(&str, &str)]) {
+        let bucket_name = format!("{}_bucket", name);
+        let mut cumulative = 0.0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.get();
+            let le = self
+                .bounds
+                .get(i)
+                .map_or("+Inf".to_string(), |bound| bound.to_string());
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            write_sample(out, &bucket_name, &bucket_labels, cumulative);
+        }
+        write_sample(out, &format!("{}_sum", name), labels, self.sum.get());
+        write_sample(out, &format!("{}_count", name), labels, self.count.get());
+    }
+}
+
+/// One metric name with a series per combination of label values.
+pub struct Family<M> {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    series: DashMap<Vec<String>, Arc<M>>,
+    init: fn() -> M,
+}
+
+impl<M: Metric> Family<M> {
+    pub fn new(
+        name: &'static str,
+        help: &'static str,
+        label_names: &'static [&'static str],
+        init: fn() -> M,
+    ) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            series: DashMap::new(),
+            init,
+        }
+    }
+
+    /// Series for `values`, given in the order of the family's label names. Hot paths
+    /// should keep the returned `Arc` rather than look it up per event.
+    pub fn with(&self, values: &[&str]) -> Arc<M> {
+        debug_assert_eq!(values.len(), self.label_names.len());
+        let key: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        if let Some(series) = self.series.get(&key) {
+            return Arc::clone(series.value());
+        }
+        Arc::clone(
+            self.series
+                .entry(key)
+                .or_insert_with(|| Arc::new((self.init)()))
+                .value(),
+        )
+    }
+
+    fn write(&self, out: &mut String) {
+        write_header(out, self.name, self.help, M::KIND);
+        let mut series: Vec<(Vec<String>, Arc<M>)> = self
+            .series
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+            .collect();
+        series.sort_by(|a, b| a.0.cmp(&b.0));
+        for (values, metric) in series {
+            let labels: Vec<(&str, &str)> = self
+                .label_names
+                .iter()
+                .copied()
+                .zip(values.iter().map(String::as_str))
+                .collect();
+            metric.write_samples(out, self.name, &labels);
+        }
+    }
+}
+
+impl Family<Counter> {
+    /// Sum over every series.
+    pub fn total(&self) -> f64 {
+        self.series.iter().map(|series| series.value().get()).sum()
+    }
+}
+
+/// Cash flow and net shares traded per asset since start, for marking PnL to the book.
+#[derive(Debug, Default)]
+pub struct TradedPnl {
+    traded: DashMap<String, (f64, f64)>,
+}
+
+impl TradedPnl {
+    pub fn record_fill(&self, asset_id: &str, side: OrderSide, price: f64, size: f64) {
+        let mut entry = self.traded.entry(asset_id.to_string()).or_default();
+        let (cash, shares) = entry.value_mut();
+        match side {
+            OrderSide::Buy => {
+                *cash -= price * size;
+                *shares += size;
+            }
+            OrderSide::Sell => {
+                *cash += price * size;
+                *shares -= size;
+            }
+        }
+    }
+
+    /// PnL of `asset_id` with the remaining shares marked at `mark`.
+    pub fn pnl(&self, asset_id: &str, mark: f64) -> f64 {
+        self.traded
+            .get(asset_id)
+            .map_or(0.0, |entry| entry.0 + entry.1 * mark)
+    }
+
+    pub fn assets(&self) -> Vec<String> {
+        let mut assets: Vec<String> = self.traded.iter().map(|e| e.key().clone()).collect();
+        assets.sort();
+        assets
+    }
+}
+
+pub struct Metrics {
+    pub event_queue_depth: Family<Gauge>,
+    pub event_lag_seconds: Family<Gauge>,
+    pub events_coalesced_total: Family<Counter>,
+    pub events_dropped_total: Family<Counter>,
+    pub strategy_handler_seconds: Family<Histogram>,
+    pub strategy_over_budget_total: Family<Counter>,
+    pub orders_total: Family<Counter>,
+    pub fills_total: Family<Counter>,
+    pub filled_shares_total: Family<Counter>,
+    pub rest_request_seconds: Family<Histogram>,
+    pub traded_pnl: TradedPnl,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            event_queue_depth: Family::new(
+                "polymarket_event_queue_depth",
+                "Events sent to the event processor and not yet handled, per lane.",
+                &["lane"],
+                Gauge::default,
+            ),
+            event_lag_seconds: Family::new(
+                "polymarket_event_lag_seconds",
+                "Time the last handled event waited in its lane.",
+                &["lane"],
+                Gauge::default,
+            ),
+            events_coalesced_total: Family::new(
+                "polymarket_events_coalesced_total",
+                "Events merged into the previous event of the same instrument.",
+                &["lane"],
+                Counter::default,
+            ),
+            events_dropped_total: Family::new(
+                "polymarket_events_dropped_total",
+                "Top-of-book prices dropped because their lane was full.",
+                &["lane"],
+                Counter::default,
+            ),
+            strategy_handler_seconds: Family::new(
+                "polymarket_strategy_handler_seconds",
+                "Time spent in a strategy callback.",
+                &["strategy", "hook"],
+                || Histogram::new(HANDLER_BUCKETS),
+            ),
+            strategy_over_budget_total: Family::new(
+                "polymarket_strategy_over_budget_total",
+                "Strategy callbacks that took longer than the handler budget.",
+                &["strategy", "hook"],
+                Counter::default,
+            ),
+            orders_total: Family::new(
+                "polymarket_orders_total",
+                "Order requests by result: placed, rejected, cancelled or cancel_failed.",
+                &["result"],
+                Counter::default,
+            ),
+            fills_total: Family::new(
+                "polymarket_fills_total",
+                "Own fills by side and trade role.",
+                &["side", "role"],
+                Counter::default,
+            ),
+            filled_shares_total: Family::new(
+                "polymarket_filled_shares_total",
+                "Shares filled by side and trade role.",
+                &["side", "role"],
+                Counter::default,
+            ),
+            rest_request_seconds: Family::new(
+                "polymarket_rest_request_seconds",
+                "CLOB REST round trip time by method, path and outcome.",
+                &["method", "path", "status"],
+                || Histogram::new(REST_BUCKETS),
+            ),
+            traded_pnl: TradedPnl::default(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn order(&self, result: &str) {
+        self.orders_total.with(&[result]).inc();
+    }
+
+    /// Renders every metric, sampling queue depth, feeds, positions and PnL now.
+    pub fn render(&self, event_tx: &CountingSender, poly_state: &PolyMarketState) -> String {
+        for (lane, pending) in event_tx.lane_pending() {
+            self.event_queue_depth.with(&[&lane]).set(pending as f64);
+        }
+
+        let mut out = String::new();
+        self.event_queue_depth.write(&mut out);
+        self.event_lag_seconds.write(&mut out);
+        self.events_coalesced_total.write(&mut out);
+        self.events_dropped_total.write(&mut out);
+        self.strategy_handler_seconds.write(&mut out);
+        self.strategy_over_budget_total.write(&mut out);
+        self.orders_total.write(&mut out);
+        self.fills_total.write(&mut out);
+        self.filled_shares_total.write(&mut out);
+        self.rest_request_seconds.write(&mut out);
+        write_feeds(&mut out);
+        write_positions(&mut out, poly_state);
+        self.write_pnl(&mut out, poly_state);
+        out
+    }
+
+    fn write_pnl(&self, out: &mut String, poly_state: &PolyMarketState) {
+        write_header(
+            out,
+            "polymarket_pnl_usd",
+            "PnL of fills since start, open shares marked at the book mid.",
+            "gauge",
+        );
+        let mut total = 0.0;
+        for asset_id in self.traded_pnl.assets() {
+            let Some(mark) = book_mid(poly_state, &asset_id) else {
+                continue;
+            };
+            let pnl = self.traded_pnl.pnl(&asset_id, mark);
+            total += pnl;
+            write_sample(out, "polymarket_pnl_usd", &[("asset_id", &asset_id)], pnl);
+        }
+        write_header(
+            out,
+            "polymarket_pnl_total_usd",
+            "Sum of polymarket_pnl_usd over all assets with a book.",
+            "gauge",
+        );
+        write_sample(out, "polymarket_pnl_total_usd", &[], total);
+    }
+}
+
+/// Mid of the book of `asset_id` in USD, or the only side quoted.
+fn book_mid(poly_state: &PolyMarketState, asset_id: &str) -> Option<f64> {
+    let book = poly_state.orderbooks.get(asset_id)?;
+    let book = book.read().ok()?;
+    let mid = match (book.best_bid(), book.best_ask()) {
+        (Some((bid, _)), Some((ask, _))) => (bid + ask) as f64 / 2.0,
+        (Some((price, _)), None) | (None, Some((price, _))) => price as f64,
+        (None, None) => return None,
+    };
+    Some(mid / 1000.0)
+}
+
+fn write_feeds(out: &mut String) {
+    type Sample = fn(&FeedStatus) -> Option<f64>;
+    let metrics: [(&str, &str, &str, Sample); 5] = [
+        (
+            "polymarket_feed_connected",
+            "Whether the websocket feed is connected.",
+            "gauge",
+            |s| Some(if s.connected { 1.0 } else { 0.0 }),
+        ),
+        (
+            "polymarket_feed_reconnects_total",
+            "Reconnects of the websocket feed since start.",
+            "counter",
+            |s| Some(s.reconnects as f64),
+        ),
+        (
+            "polymarket_feed_messages_total",
+            "Messages received on the websocket feed since start.",
+            "counter",
+            |s| Some(s.messages as f64),
+        ),
+        (
+            "polymarket_feed_last_message_age_seconds",
+            "Time since the last message on the websocket feed.",
+            "gauge",
+            |s| s.last_message_age.map(|age| age.as_secs_f64()),
+        ),
+        (
+            "polymarket_feed_latency_seconds",
+            "Smoothed exchange-to-local latency of the websocket feed.",
+            "gauge",
+            |s| s.latency_ms.map(|ms| ms / 1000.0),
+        ),
+    ];
+    let statuses = FEED_HEALTH.statuses();
+    for (name, help, kind, sample) in metrics {
+        write_header(out, name, help, kind);
+        for status in &statuses {
+            if let Some(value) = sample(status) {
+                write_sample(out, name, &[("feed", &status.name)], value);
+            }
+        }
+    }
+}
+
+fn write_positions(out: &mut String, poly_state: &PolyMarketState) {
+    write_header(
+        out,
+        "polymarket_position_shares",
+        "Shares held per asset.",
+        "gauge",
+    );
+    let mut positions: Vec<(String, u32)> = poly_state
+        .positions
+        .iter()
+        .filter_map(|entry| {
+            let size = entry.value().read().ok()?.size;
+            Some((entry.key().clone(), size))
+        })
+        .collect();
+    positions.sort();
+    for (asset_id, size) in positions {
+        write_sample(
+            out,
+            "polymarket_position_shares",
+            &[("asset_id", &asset_id)],
+            size as f64 / 1000.0,
+        );
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        out.push('{');
+        for (i, (label, value)) in labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            let _ = write!(out, "{}=\"{}\"", label, escaped);
+        }
+        out.push('}');
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_histograms_cumulatively() {
+        let family = Family::new("handler_seconds", "Handler time.", &["strategy"], || {
+            Histogram::new(&[0.1, 1.0])
+        });
+        let histogram = family.with(&["Koen \"v2\""]);
+        histogram.observe(0.0625);
+        histogram.observe(0.5);
+        histogram.observe(3.0);
+
+        let mut out = String::new();
+        family.write(&mut out);
+        assert_eq!(
+            out,
+            "# HELP handler_seconds Handler time.\n\
+             # TYPE handler_seconds histogram\n\
+             handler_seconds_bucket{strategy=\"Koen \\\"v2\\\"\",le=\"0.1\"} 1\n\
+             handler_seconds_bucket{strategy=\"Koen \\\"v2\\\"\",le=\"1\"} 2\n\
+             handler_seconds_bucket{strategy=\"Koen \\\"v2\\\"\",le=\"+Inf\"} 3\n\
+             handler_seconds_sum{strategy=\"Koen \\\"v2\\\"\"} 3.5625\n\
+             handler_seconds_count{strategy=\"Koen \\\"v2\\\"\"} 3\n"
+        );
+    }
+
+    #[test]
+    fn marks_traded_pnl() {
+        let pnl = TradedPnl::default();
+        pnl.record_fill("a", OrderSide::Buy, 0.40, 10.0);
+        pnl.record_fill("a", OrderSide::Sell, 0.55, 4.0);
+        assert!((pnl.pnl("a", 0.50) - (-4.0 + 2.2 + 3.0)).abs() < 1e-9);
+        assert_eq!(pnl.pnl("b", 0.50), 0.0);
+    }
+}
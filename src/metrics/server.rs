@@ -0,0 +1,80 @@
+use log::{error, info, warn};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::METRICS;
+use crate::exchange_listeners::event_processor::CountingSender;
+use crate::exchange_listeners::states::PolyMarketState;
+
+/// Largest request head accepted; scrapes are a single short GET.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Serves `GET /metrics` on `addr` until the process exits.
+pub async fn serve_metrics(
+    addr: &'static str,
+    event_tx: Arc<CountingSender>,
+    poly_state: Arc<PolyMarketState>,
+) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("[Metrics] Failed to bind {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("[Metrics] Serving http://{}/metrics", addr);
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("[Metrics] Accept failed: {}", e);
+                continue;
+            }
+        };
+        let event_tx = Arc::clone(&event_tx);
+        let poly_state = Arc::clone(&poly_state);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &event_tx, &poly_state).await {
+                warn!("[Metrics] Request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    event_tx: &CountingSender,
+    poly_state: &PolyMarketState,
+) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&request);
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let (status, content_type, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            METRICS.render(event_tx, poly_state),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -7,9 +7,12 @@ use std::{
 };
 
 use ethers::abi::Hash;
-use serde_json::Value;
+use rust_decimal::Decimal;
 
-use crate::{clob_client::clob_types::OrderArgs, marketmaking::marketmakingclient::CLIENT};
+use crate::{
+    clob_client::clob_types::{CancelOrdersResponse, OrderArgs, PostOrderResponse},
+    marketmaking::marketmakingclient::client,
+};
 use std::collections::HashSet;
 use tokio::sync::RwLock;
 
@@ -54,13 +57,13 @@ impl OrderManager {
         price: u32,
         size: u32,
         tick_size: &str,
-    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    ) -> Result<PostOrderResponse, Box<dyn Error + Send + Sync>> {
         let task_start = Instant::now();
-        let client = Arc::clone(&CLIENT);
+        let client = client()?;
 
-        // convert price/size into f64
-        let f_price = price as f64 / 1000.0;
-        let f_size = size as f64 / 1000.0;
+        // convert milli-unit price/size into exact decimals
+        let f_price = Decimal::new(price as i64, 3);
+        let f_size = Decimal::new(size as i64, 3);
 
         // compute the “expire” timestamp
         let now = SystemTime::now()
@@ -86,10 +89,10 @@ impl OrderManager {
                 Some(epoch_secs),
                 None,
             );
-            client_clone.create_order(&order_args, &tick_size_owned, true)
+            client_clone.create_order(&order_args, &tick_size_owned, true, None)
         })
         .await
-        .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?; // propagate JoinError
+        .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)??; // propagate JoinError / amount error
 
         let duration = task_start.elapsed();
         println!("Time elapsed to sign order is {:?}", duration);
@@ -107,11 +110,11 @@ impl OrderManager {
         price: u32,
         size: u32,
         tick_size: &str,
-    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
-        let client = Arc::clone(&CLIENT);
+    ) -> Result<PostOrderResponse, Box<dyn Error + Send + Sync>> {
+        let client = client()?;
 
-        let f_price = price as f64 / 1000.0;
-        let f_size = size as f64 / 1000.0;
+        let f_price = Decimal::new(price as i64, 3);
+        let f_size = Decimal::new(size as i64, 3);
 
         let order_args = OrderArgs::new(asset_id, f_price, f_size, "BUY", None, None, None, None);
 
@@ -126,15 +129,15 @@ impl OrderManager {
         }
 
         // Create and post the order
-        let order = client.create_order(&order_args, &tick_size, true);
+        let order = client.create_order(&order_args, &tick_size, true, None)?;
         let posted_order = client.post_order(&order, "GTC").await;
 
         match posted_order {
             Ok(ref value) => {
                 // Extract the order ID as a String
-                let order_id = value["orderID"]
-                    .as_str()
-                    .ok_or("orderID is not a string")?
+                let order_id = value
+                    .accepted_order_id()
+                    .ok_or("orderID missing from response")?
                     .to_string();
 
                 // Add the order ID to the global HashSet
@@ -172,8 +175,10 @@ impl OrderManager {
         }
     }
 
-    pub async fn cancel_all_bids(&mut self) -> Result<Value, Box<dyn Error + Send + Sync>> {
-        let client = Arc::clone(&CLIENT);
+    pub async fn cancel_all_bids(
+        &mut self,
+    ) -> Result<CancelOrdersResponse, Box<dyn Error + Send + Sync>> {
+        let client = client()?;
         let vec_of_bids: Vec<&str> = self
             .open_bids
             .iter()
@@ -0,0 +1,184 @@
+use log::{error, info, warn};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::sleep;
+
+use crate::exchange_listeners::event_processor::CountingSender;
+use crate::exchange_listeners::paper_exchange;
+use crate::exchange_listeners::poly_client::PolyClient;
+use crate::exchange_listeners::PolyMarketState;
+use crate::metrics::METRICS;
+
+const POLL_EVERY: Duration = Duration::from_millis(100);
+/// Between cancel requests for orders that are still live.
+const CANCEL_RETRY_EVERY: Duration = Duration::from_secs(1);
+
+/// Waits for SIGINT or SIGTERM and returns its name.
+pub async fn wait_for_signal() -> &'static str {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            warn!("[Shutdown] Cannot listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return "SIGINT";
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    }
+}
+
+/// What was left behind when the process stopped.
+#[derive(Debug, Clone)]
+pub struct ShutdownSummary {
+    pub elapsed: Duration,
+    /// Orders in `open_orders` when shutdown started.
+    pub orders_open: usize,
+    /// Orders still in `open_orders` when the timeout hit.
+    pub orders_left: usize,
+    /// Events queued but not handled when the timeout hit.
+    pub events_left: usize,
+    pub orders_placed: f64,
+    pub orders_cancelled: f64,
+    pub filled_shares: f64,
+}
+
+impl fmt::Display for ShutdownSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stopped in {:?}: {} orders open, {} left open, {} events unhandled | session: {} orders placed, {} cancelled, {:.3} shares filled",
+            self.elapsed,
+            self.orders_open,
+            self.orders_left,
+            self.events_left,
+            self.orders_placed,
+            self.orders_cancelled,
+            self.filled_shares
+        )
+    }
+}
+
+/// Stops trading within `timeout`: blocks new orders, runs the strategies' `on_stop`,
+/// cancels every tracked open order and waits for the user feed to confirm the
+/// cancellations, retrying the orders the exchange did not cancel, then stops the event
+/// processor once its queued events, and so every log line they write, are handled.
+pub async fn shutdown(
+    events: &CountingSender,
+    poly_state: Arc<PolyMarketState>,
+    timeout: Duration,
+) -> ShutdownSummary {
+    let started = Instant::now();
+    let deadline = started + timeout;
+    poly_state.begin_shutdown();
+    events.stop();
+
+    // Orders still being placed only get an id later, and cancels the exchange refused
+    // leave orders live, so live orders are cancelled again until none are left.
+    let orders_open = poly_state.open_order_count();
+    let mut next_cancel = Instant::now();
+    while poly_state.open_order_count() > 0 && Instant::now() < deadline {
+        if Instant::now() >= next_cancel {
+            if let Err(e) = PolyClient::cancel_open_orders(Arc::clone(&poly_state)).await {
+                error!("[Shutdown] Failed to cancel open orders: {}", e);
+            }
+            next_cancel = Instant::now() + CANCEL_RETRY_EVERY;
+        }
+        sleep(POLL_EVERY).await;
+    }
+    let orders_left = poly_state.open_order_count();
+    if orders_left > 0 {
+        warn!(
+            "[Shutdown] {} orders still open after {:?}",
+            orders_left, timeout
+        );
+    }
+
+    events.close();
+    while events.pending() > 0 && Instant::now() < deadline {
+        sleep(POLL_EVERY).await;
+    }
+    let summary = ShutdownSummary {
+        elapsed: started.elapsed(),
+        orders_open,
+        orders_left,
+        events_left: events.pending(),
+        orders_placed: METRICS.orders_total.with(&["placed"]).get(),
+        orders_cancelled: METRICS.orders_total.with(&["cancelled"]).get(),
+        filled_shares: METRICS.filled_shares_total.total(),
+    };
+    info!("[Shutdown] {}", summary);
+    if poly_state.paper_trading {
+        info!("[Shutdown] {}", paper_exchange::summary(&poly_state));
+    }
+    log::logger().flush();
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::event_processor::spawn_event_processor;
+    use crate::exchange_listeners::poly_models::{AssetOrders, OpenOrder, OrderState};
+    use crate::exchange_listeners::AppState;
+    use dashmap::DashMap;
+    use std::sync::Mutex;
+
+    /// State with one order whose cancel was accepted but not yet confirmed by the feed.
+    fn state_with_pending_cancel() -> Arc<PolyMarketState> {
+        let poly_state = Arc::new(PolyMarketState::default());
+        let mut order = OpenOrder::new("1".to_string(), 500, 10_000, 0, Some("a".to_string()));
+        order.set_state(OrderState::ToBeCanceled);
+        let bids = DashMap::new();
+        bids.insert((500, 10_000), Arc::new(Mutex::new(order)));
+        poly_state
+            .open_orders
+            .insert("1".to_string(), AssetOrders::new(bids, DashMap::new()));
+        poly_state
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_feed_to_confirm_cancels() {
+        let poly_state = state_with_pending_cancel();
+        let events = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            Vec::new(),
+            1,
+        );
+        let feed_state = Arc::clone(&poly_state);
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(300)).await;
+            feed_state
+                .open_orders
+                .get("1")
+                .unwrap()
+                .bids
+                .remove(&(500, 10_000));
+        });
+
+        let summary = shutdown(&events, poly_state, Duration::from_secs(5)).await;
+
+        assert_eq!((summary.orders_open, summary.orders_left), (1, 0));
+        assert!(summary.elapsed >= Duration::from_millis(300));
+        assert!(summary.elapsed < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn reports_orders_left_unconfirmed_at_the_timeout() {
+        let poly_state = state_with_pending_cancel();
+        let events = spawn_event_processor(
+            Arc::new(AppState::default()),
+            Arc::clone(&poly_state),
+            Vec::new(),
+            1,
+        );
+
+        let summary = shutdown(&events, poly_state, Duration::from_millis(300)).await;
+
+        assert_eq!((summary.orders_open, summary.orders_left), (1, 1));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod update_crypto_orderbooks;
 pub mod update_crypto_prices;
+pub mod update_fair_value;
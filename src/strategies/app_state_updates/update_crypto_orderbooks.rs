@@ -1,8 +1,6 @@
 use crate::{
     exchange_listeners::{
-        crypto_models::{
-            get_crypto_orderbook_map, get_crypto_prices_map, CryptoPrice, CryptoPriceUpdate,
-        },
+        crypto_models::{get_crypto_orderbook_map, get_crypto_prices_map, CryptoPriceUpdate},
         orderbooks::{
             crypto_orderbook::{CryptoOrderbook, VwapState},
             OrderbookDepth, OrderbookLevel,
@@ -124,9 +122,10 @@ impl Strategy for UpdateCryptoOrderbookStrategy {
             orderbook.update_l1(Some(bid_level), Some(ask_level));
 
             let price_key = (_exchange, _instrument, _depth);
-            let mut price = prices_map.entry(price_key).or_insert_with(CryptoPrice::new);
-            price.midpoint = orderbook.get_midpoint();
-            price.price = price.midpoint;
+            let mut price = prices_map.entry(price_key).or_default();
+            let midpoint = orderbook.get_midpoint();
+            let usd_rate = ctx.app_state.usd_rate(_exchange, _instrument, _crypto);
+            price.set(midpoint, midpoint, usd_rate);
         }
     }
 
@@ -172,10 +171,10 @@ impl Strategy for UpdateCryptoOrderbookStrategy {
         if let Some((midpoint, final_price)) = price_data {
             let prices_map = get_crypto_prices_map(ctx.app_state.clone(), crypto);
             let price_key = (exchange, instrument, OrderbookDepth::L2);
-            let mut price = prices_map.entry(price_key).or_insert_with(CryptoPrice::new);
+            let mut price = prices_map.entry(price_key).or_default();
+            let usd_rate = ctx.app_state.usd_rate(exchange, instrument, crypto);
 
-            price.midpoint = midpoint;
-            price.price = final_price;
+            price.set(midpoint, final_price, usd_rate);
         }
     }
 
@@ -257,10 +256,10 @@ impl Strategy for UpdateCryptoOrderbookStrategy {
         if let Some((midpoint, final_price)) = price_data {
             let prices_map = get_crypto_prices_map(ctx.app_state.clone(), crypto);
             let price_key = (exchange, instrument, OrderbookDepth::L2);
-            let mut price = prices_map.entry(price_key).or_insert_with(CryptoPrice::new);
+            let mut price = prices_map.entry(price_key).or_default();
+            let usd_rate = ctx.app_state.usd_rate(exchange, instrument, crypto);
 
-            price.midpoint = midpoint;
-            price.price = final_price;
+            price.set(midpoint, final_price, usd_rate);
         }
     }
 }
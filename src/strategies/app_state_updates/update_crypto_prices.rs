@@ -46,8 +46,8 @@ impl Strategy for UpdateCryptoPriceStrategy {
 
             let midpoint = orderbook.get_midpoint();
             let mut crypto_price = prices_map.entry(key).or_insert_with(|| CryptoPrice::new());
-            crypto_price.price = midpoint;
-            crypto_price.midpoint = midpoint;
+            let usd_rate = ctx.app_state.usd_rate(_exchange, _instrument, _crypto);
+            crypto_price.set(midpoint, midpoint, usd_rate);
         }
     }
 }
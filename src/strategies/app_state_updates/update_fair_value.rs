@@ -0,0 +1,545 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exchange_listeners::{
+        crypto_models::{get_crypto_prices_map, CryptoPriceUpdate, FairValue, RateKind},
+        orderbooks::{OrderbookDepth, OrderbookLevel},
+        states::CryptoPriceMap,
+        Crypto, Exchange, Instrument,
+    },
+    strategies::{
+        params::{LiveParams, StrategyParams},
+        Strategy, StrategyContext,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FairValueConfig {
+    /// Weight per venue; venues not listed use `default_weight` (0 excludes them).
+    pub weights: Vec<VenueWeight>,
+    pub default_weight: f64,
+    /// Prices older than this are ignored.
+    pub max_staleness_ms: u64,
+    /// Sources further than this from the weighted median are rejected.
+    pub outlier_bps: f64,
+    /// Time constant of the perp-vs-spot basis EWMA: an observation held for this long
+    /// carries 1 - 1/e of the weight, however often the price is recomputed.
+    pub basis_tau_ms: u64,
+    /// Half-width of the band in weighted standard deviations.
+    pub band_sigmas: f64,
+    /// Lower bound on the band half-width.
+    pub min_band_bps: f64,
+    pub min_sources: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueWeight {
+    pub exchange: Exchange,
+    pub instrument: Instrument,
+    pub weight: f64,
+}
+
+impl Default for FairValueConfig {
+    fn default() -> Self {
+        let venue = |exchange, instrument, weight| VenueWeight {
+            exchange,
+            instrument,
+            weight,
+        };
+        Self {
+            weights: vec![
+                venue(Exchange::Binance, Instrument::Spot, 1.0),
+                venue(Exchange::Binance, Instrument::Perpetual, 1.0),
+                venue(Exchange::Bybit, Instrument::Perpetual, 0.75),
+                venue(Exchange::Okx, Instrument::Perpetual, 0.75),
+                venue(Exchange::Deribit, Instrument::Perpetual, 0.5),
+            ],
+            default_weight: 0.25,
+            max_staleness_ms: 2000,
+            outlier_bps: 25.0,
+            basis_tau_ms: 30_000,
+            band_sigmas: 2.0,
+            min_band_bps: 0.5,
+            min_sources: 1,
+        }
+    }
+}
+
+impl FairValueConfig {
+    fn weight(&self, exchange: Exchange, instrument: Instrument) -> f64 {
+        self.weights
+            .iter()
+            .find(|venue| venue.exchange == exchange && venue.instrument == instrument)
+            .map_or(self.default_weight, |venue| venue.weight)
+    }
+}
+
+impl StrategyParams for FairValueConfig {
+    fn validate(&self) -> Result<()> {
+        let weight_ok = |weight: f64| weight.is_finite() && weight >= 0.0;
+        ensure!(
+            weight_ok(self.default_weight) && self.weights.iter().all(|w| weight_ok(w.weight)),
+            "weights must be finite and not negative"
+        );
+        ensure!(
+            self.max_staleness_ms > 0 && self.basis_tau_ms > 0,
+            "max_staleness_ms and basis_tau_ms must be positive"
+        );
+        ensure!(self.outlier_bps > 0.0, "outlier_bps must be positive");
+        ensure!(
+            self.band_sigmas >= 0.0 && self.min_band_bps >= 0.0,
+            "band_sigmas and min_band_bps must not be negative"
+        );
+        ensure!(self.min_sources > 0, "min_sources must be positive");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Source {
+    exchange: Exchange,
+    instrument: Instrument,
+    price: f64,
+    weight: f64,
+}
+
+/// Publishes a composite USD fair price per crypto into `AppState::fair_values`.
+/// Must run after the strategy that maintains the per-venue prices.
+pub struct UpdateFairValueStrategy {
+    config: LiveParams<FairValueConfig>,
+    basis: DashMap<(Crypto, Exchange), Basis>,
+}
+
+/// Smoothed perp-minus-spot price of one venue and when it was last observed.
+#[derive(Debug, Clone, Copy)]
+struct Basis {
+    value: f64,
+    observed_at: Instant,
+}
+
+impl Default for UpdateFairValueStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpdateFairValueStrategy {
+    pub fn new() -> Self {
+        Self::with_config(FairValueConfig::default())
+    }
+
+    pub fn with_config(config: FairValueConfig) -> Self {
+        Self {
+            config: LiveParams::new(config),
+            basis: DashMap::new(),
+        }
+    }
+
+    /// One fresh USD price per venue, preferring the L2 VWAP over the L1 midpoint.
+    fn live_sources(
+        config: &FairValueConfig,
+        prices: &CryptoPriceMap,
+        now: Instant,
+    ) -> Vec<Source> {
+        let mut by_venue: HashMap<(Exchange, Instrument), (OrderbookDepth, f64)> = HashMap::new();
+        for entry in prices.iter() {
+            let (exchange, instrument, depth) = *entry.key();
+            let crypto_price = entry.value();
+            let max_staleness = Duration::from_millis(config.max_staleness_ms);
+            if now.saturating_duration_since(crypto_price.updated_at) > max_staleness {
+                continue;
+            }
+            // Venues whose quote currency has no USD rate yet are left out.
+            let price = match crypto_price.usd_price() {
+                Some(price) if price > 0.0 && price.is_finite() => price,
+                _ => continue,
+            };
+            let slot = by_venue
+                .entry((exchange, instrument))
+                .or_insert((depth, price));
+            if depth == OrderbookDepth::L2 {
+                *slot = (depth, price);
+            }
+        }
+
+        by_venue
+            .into_iter()
+            .filter_map(|((exchange, instrument), (_, price))| {
+                let weight = config.weight(exchange, instrument);
+                (weight > 0.0).then_some(Source {
+                    exchange,
+                    instrument,
+                    price,
+                    weight,
+                })
+            })
+            .collect()
+    }
+
+    /// Moves perp prices onto the spot level using an EWMA of each venue's basis.
+    fn adjust_for_basis(
+        &self,
+        config: &FairValueConfig,
+        crypto: Crypto,
+        sources: &mut [Source],
+        now: Instant,
+    ) {
+        let (spot_value, spot_weight) = sources
+            .iter()
+            .filter(|s| s.instrument == Instrument::Spot)
+            .fold((0.0, 0.0), |(v, w), s| {
+                (v + s.price * s.weight, w + s.weight)
+            });
+        let spot = (spot_weight > 0.0).then(|| spot_value / spot_weight);
+
+        for source in sources
+            .iter_mut()
+            .filter(|s| s.instrument == Instrument::Perpetual)
+        {
+            let key = (crypto, source.exchange);
+            if let Some(spot) = spot {
+                let observed = source.price - spot;
+                let mut basis = self.basis.entry(key).or_insert(Basis {
+                    value: observed,
+                    observed_at: now,
+                });
+                let dt = now.saturating_duration_since(basis.observed_at);
+                let tau = Duration::from_millis(config.basis_tau_ms);
+                let alpha = 1.0 - (-dt.as_secs_f64() / tau.as_secs_f64()).exp();
+                basis.value += alpha * (observed - basis.value);
+                basis.observed_at = basis.observed_at.max(now);
+            }
+            if let Some(basis) = self.basis.get(&key) {
+                source.price -= basis.value;
+            }
+        }
+    }
+
+    pub fn compute(
+        &self,
+        crypto: Crypto,
+        prices: &CryptoPriceMap,
+        now: Instant,
+    ) -> Option<FairValue> {
+        let config = self.config.get();
+        let mut sources = Self::live_sources(&config, prices, now);
+        if sources.is_empty() {
+            return None;
+        }
+        self.adjust_for_basis(&config, crypto, &mut sources, now);
+
+        let median = weighted_median(&mut sources);
+        let max_deviation = median * config.outlier_bps / 10_000.0;
+        sources.retain(|s| (s.price - median).abs() <= max_deviation);
+        if sources.len() < config.min_sources.max(1) {
+            return None;
+        }
+
+        let total_weight: f64 = sources.iter().map(|s| s.weight).sum();
+        let price = sources.iter().map(|s| s.price * s.weight).sum::<f64>() / total_weight;
+        let variance = sources
+            .iter()
+            .map(|s| s.weight * (s.price - price).powi(2))
+            .sum::<f64>()
+            / total_weight;
+        let half_width =
+            (config.band_sigmas * variance.sqrt()).max(price * config.min_band_bps / 10_000.0);
+
+        Some(FairValue {
+            price,
+            lower: price - half_width,
+            upper: price + half_width,
+            sources: sources.len(),
+            updated_at: now,
+        })
+    }
+
+    fn refresh(&self, ctx: &StrategyContext, crypto: Crypto) {
+        let prices = get_crypto_prices_map(Arc::clone(&ctx.app_state), crypto);
+        match self.compute(crypto, &prices, Instant::now()) {
+            Some(fair_value) => {
+                ctx.app_state.fair_values.insert(crypto, fair_value);
+            }
+            None => {
+                ctx.app_state.fair_values.remove(&crypto);
+            }
+        }
+    }
+
+    fn refresh_all(&self, ctx: &StrategyContext) {
+        let cryptos: Vec<Crypto> = ctx.app_state.prices.iter().map(|e| *e.key()).collect();
+        for crypto in cryptos {
+            self.refresh(ctx, crypto);
+        }
+    }
+}
+
+fn weighted_median(sources: &mut [Source]) -> f64 {
+    sources.sort_by(|a, b| a.price.total_cmp(&b.price));
+    let half = sources.iter().map(|s| s.weight).sum::<f64>() / 2.0;
+    let mut cumulative = 0.0;
+    for source in sources.iter() {
+        cumulative += source.weight;
+        if cumulative >= half {
+            return source.price;
+        }
+    }
+    sources[sources.len() - 1].price
+}
+
+impl Strategy for UpdateFairValueStrategy {
+    fn name(&self) -> &'static str {
+        "UpdateFairValue"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        self.config.to_value()
+    }
+
+    fn reload_params(&self, params: serde_json::Value) -> anyhow::Result<()> {
+        self.config.reload(params)
+    }
+
+    fn crypto_handle_price_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _exchange: Exchange,
+        _instrument: Instrument,
+        crypto: Crypto,
+        _depth: OrderbookDepth,
+        _price_update: &CryptoPriceUpdate,
+    ) {
+        self.refresh(&ctx, crypto);
+    }
+
+    fn crypto_handle_l2_snapshot(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _exchange: Exchange,
+        _instrument: Instrument,
+        crypto: Crypto,
+        _bids: &[OrderbookLevel],
+        _asks: &[OrderbookLevel],
+    ) {
+        self.refresh(&ctx, crypto);
+    }
+
+    fn crypto_handle_l2_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _exchange: Exchange,
+        _instrument: Instrument,
+        crypto: Crypto,
+        _bids: &[OrderbookLevel],
+        _asks: &[OrderbookLevel],
+    ) {
+        self.refresh(&ctx, crypto);
+    }
+
+    fn crypto_handle_price_clear(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _exchange: Exchange,
+        _instrument: Instrument,
+        crypto: Crypto,
+        _depth: OrderbookDepth,
+    ) {
+        self.refresh(&ctx, crypto);
+    }
+
+    fn conversion_handle_rate_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _kind: RateKind,
+        _value: f64,
+    ) {
+        self.refresh_all(&ctx);
+    }
+
+    fn conversion_handle_rate_clear(&self, ctx: Arc<StrategyContext>, _kind: RateKind) {
+        self.refresh_all(&ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::crypto_models::CryptoPrice;
+    use crate::strategies::params::parse_params;
+
+    fn price_map(
+        entries: &[(Exchange, Instrument, OrderbookDepth, f64, Duration)],
+    ) -> CryptoPriceMap {
+        let now = Instant::now();
+        let map = CryptoPriceMap::new();
+        for &(exchange, instrument, depth, price, age) in entries {
+            let mut crypto_price = CryptoPrice::new();
+            crypto_price.set(price, price, Some(1.0));
+            crypto_price.updated_at = now - age;
+            map.insert((exchange, instrument, depth), crypto_price);
+        }
+        map
+    }
+
+    #[test]
+    fn rejects_stale_and_outlier_venues() {
+        let fresh = Duration::ZERO;
+        let prices = price_map(&[
+            (
+                Exchange::Binance,
+                Instrument::Spot,
+                OrderbookDepth::L1,
+                100_000.0,
+                fresh,
+            ),
+            (
+                Exchange::Bybit,
+                Instrument::Spot,
+                OrderbookDepth::L1,
+                100_010.0,
+                fresh,
+            ),
+            (
+                Exchange::Okx,
+                Instrument::Spot,
+                OrderbookDepth::L1,
+                101_000.0,
+                fresh,
+            ),
+            (
+                Exchange::Kraken,
+                Instrument::Spot,
+                OrderbookDepth::L1,
+                90_000.0,
+                Duration::from_secs(10),
+            ),
+        ]);
+        let config = FairValueConfig {
+            default_weight: 1.0,
+            ..FairValueConfig::default()
+        };
+        let strategy = UpdateFairValueStrategy::with_config(config);
+
+        let fair = strategy
+            .compute(Crypto::BTC, &prices, Instant::now())
+            .unwrap();
+        assert_eq!(fair.sources, 2);
+        assert!((fair.price - 100_005.0).abs() < 1e-6);
+        assert!(fair.lower < fair.price && fair.price < fair.upper);
+    }
+
+    #[test]
+    fn removes_perp_basis() {
+        let fresh = Duration::ZERO;
+        let strategy = UpdateFairValueStrategy::new();
+        let with_spot = price_map(&[
+            (
+                Exchange::Binance,
+                Instrument::Spot,
+                OrderbookDepth::L1,
+                100_000.0,
+                fresh,
+            ),
+            (
+                Exchange::Deribit,
+                Instrument::Perpetual,
+                OrderbookDepth::L2,
+                100_050.0,
+                fresh,
+            ),
+        ]);
+        let fair = strategy
+            .compute(Crypto::BTC, &with_spot, Instant::now())
+            .unwrap();
+        assert!((fair.price - 100_000.0).abs() < 1e-6);
+
+        // Spot drops out: the perp is still shifted by the learned basis.
+        let perp_only = price_map(&[(
+            Exchange::Deribit,
+            Instrument::Perpetual,
+            OrderbookDepth::L2,
+            100_150.0,
+            fresh,
+        )]);
+        let fair = strategy
+            .compute(Crypto::BTC, &perp_only, Instant::now())
+            .unwrap();
+        assert!((fair.price - 100_100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn basis_ewma_is_weighted_by_elapsed_time() {
+        let fresh = Duration::ZERO;
+        let strategy = UpdateFairValueStrategy::new();
+        let tau = Duration::from_millis(strategy.config.get().basis_tau_ms);
+        let prices = |perp: f64, at: Instant| {
+            let map = price_map(&[
+                (
+                    Exchange::Binance,
+                    Instrument::Spot,
+                    OrderbookDepth::L1,
+                    100_000.0,
+                    fresh,
+                ),
+                (
+                    Exchange::Deribit,
+                    Instrument::Perpetual,
+                    OrderbookDepth::L2,
+                    perp,
+                    fresh,
+                ),
+            ]);
+            for mut price in map.iter_mut() {
+                price.updated_at = at;
+            }
+            map
+        };
+        let basis = || {
+            strategy
+                .basis
+                .get(&(Crypto::BTC, Exchange::Deribit))
+                .unwrap()
+                .value
+        };
+
+        let start = Instant::now();
+        strategy.compute(Crypto::BTC, &prices(100_050.0, start), start);
+        assert!((basis() - 50.0).abs() < 1e-9);
+
+        // A burst of updates at the same instant does not move the basis.
+        for _ in 0..100 {
+            strategy.compute(Crypto::BTC, &prices(100_150.0, start), start);
+        }
+        assert!((basis() - 50.0).abs() < 1e-9);
+
+        // One time constant later the basis has covered 1 - 1/e of the gap.
+        strategy.compute(Crypto::BTC, &prices(100_150.0, start + tau), start + tau);
+        let expected = 50.0 + 100.0 * (1.0 - (-1.0f64).exp());
+        assert!((basis() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weights_are_set_from_params() {
+        let config: FairValueConfig = parse_params(serde_json::json!({
+            "weights": [{"exchange": "Kraken", "instrument": "Spot", "weight": 2.0}],
+            "default_weight": 0.0
+        }))
+        .unwrap();
+        assert_eq!(config.weight(Exchange::Kraken, Instrument::Spot), 2.0);
+        assert_eq!(config.weight(Exchange::Binance, Instrument::Spot), 0.0);
+        assert!(parse_params::<FairValueConfig>(serde_json::json!({
+            "weights": [{"exchange": "Kraken", "instrument": "Spot", "weight": -1.0}]
+        }))
+        .is_err());
+    }
+}
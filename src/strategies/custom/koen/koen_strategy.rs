@@ -1,44 +1,41 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use log::{error, info};
+use serde_json::Value;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::time::sleep;
+use std::time::Duration;
 
 use crate::{
     exchange_listeners::poly_models::{Listener, OrderSide, PriceChange},
     strategies::{
+        custom::koen::models::KoenParams,
+        params::LiveParams,
         strategy_utils::{parse_millis, StrategyAsset, StrategyClient, StrategyPosition},
+        timers::{AsyncStrategy, TimerEvent, TimerSpec},
         Strategy, StrategyContext,
     },
 };
 
+/// Between sweeps of expired cooldowns. Cooldowns are checked against the current
+/// `trade_cooldown_secs` on every event; sweeping only forgets the expired ones, so a
+/// reload of the params does not need a new timer.
+const COOLDOWN_SWEEP_EVERY: Duration = Duration::from_secs(10);
+
+/// Time from `then` to `now`, zero if `then` is later.
+fn since(now: DateTime<Utc>, then: DateTime<Utc>) -> Duration {
+    (now - then).to_std().unwrap_or(Duration::ZERO)
+}
+
 pub struct KoenStrategy {
-    max_spread: f64,
-    price_lower_bound: f64,
-    price_upper_bound: f64,
-    predicted_move: f64,
-    predicted_move_hedge: f64,
-    max_order_size: f64,
-    max_counterparty_size: f64,
-    min_same_side_liquidity: f64,
-    trade_cooldown: Duration,
-    cancel_after: Duration,
-    last_trade: DashMap<String, Instant>,
+    params: LiveParams<KoenParams>,
+    last_trade: DashMap<String, DateTime<Utc>>,
 }
 
 impl KoenStrategy {
-    pub fn new() -> Self {
+    pub fn new(params: KoenParams) -> Self {
         Self {
-            max_spread: 0.011,
-            price_lower_bound: 0.025,
-            price_upper_bound: 0.975,
-            predicted_move: 0.06,
-            predicted_move_hedge: 0.2,
-            max_order_size: 50.0,
-            max_counterparty_size: 100.0,
-            min_same_side_liquidity: 150.0,
-            trade_cooldown: Duration::from_secs(1 * 60),
-            cancel_after: Duration::from_secs(1),
+            params: LiveParams::new(params),
             last_trade: DashMap::new(),
         }
     }
@@ -56,6 +53,48 @@ impl KoenStrategy {
     fn size_to_int(size: f64) -> u32 {
         (size * 1000.0).round() as u32
     }
+
+    /// Cancels the BUY order of a `asset:price:size` cancel key if it is still open.
+    fn cancel_if_open(&self, ctx: Arc<StrategyContext>, key: &str) {
+        let mut parts = key.rsplitn(3, ':');
+        let (Some(size_int), Some(price_int), Some(asset_id)) = (
+            parts.next().and_then(|size| size.parse::<u32>().ok()),
+            parts.next().and_then(|price| price.parse::<u32>().ok()),
+            parts.next(),
+        ) else {
+            error!("[{}] Malformed cancel key '{}'", self.name(), key);
+            return;
+        };
+        let still_open = ctx
+            .poly_state
+            .open_orders
+            .get(asset_id)
+            .map(|orders| orders.order_exists(OrderSide::Buy, price_int, size_int))
+            .unwrap_or(false);
+        if !still_open {
+            return;
+        }
+        if let Err(err) = StrategyClient::cancel_orders(
+            Arc::clone(&ctx),
+            asset_id,
+            vec![(OrderSide::Buy, price_int, size_int)],
+        ) {
+            error!(
+                "[{}] Failed to cancel stale BUY order for asset {}: {}",
+                self.name(),
+                asset_id,
+                err
+            );
+        } else {
+            info!(
+                "[{}] Canceled unfilled BUY order asset={} price={:.3} size={:.3}",
+                self.name(),
+                asset_id,
+                price_int as f64 / 1000.0,
+                size_int as f64 / 1000.0
+            );
+        }
+    }
 }
 
 impl Strategy for KoenStrategy {
@@ -63,6 +102,18 @@ impl Strategy for KoenStrategy {
         "KoenStrategy"
     }
 
+    fn as_async(self: Arc<Self>) -> Option<Arc<dyn AsyncStrategy>> {
+        Some(self)
+    }
+
+    fn params(&self) -> Value {
+        self.params.to_value()
+    }
+
+    fn reload_params(&self, params: Value) -> anyhow::Result<()> {
+        self.params.reload(params)
+    }
+
     fn poly_handle_market_price_change(
         &self,
         ctx: Arc<StrategyContext>,
@@ -70,13 +121,14 @@ impl Strategy for KoenStrategy {
         _payload: &PriceChange,
     ) {
         let asset_id = &_payload.asset_id;
+        let params = self.params.get();
 
         let market = StrategyAsset::get_market(&ctx.clone(), asset_id);
 
         if let Some(orderbook_entry) = ctx.poly_state.orderbooks.get(asset_id) {
             if let Ok(orderbook) = orderbook_entry.read() {
                 if let Some(entry) = self.last_trade.get(asset_id) {
-                    if entry.elapsed() < self.trade_cooldown {
+                    if since(ctx.poly_state.now(), *entry) < params.trade_cooldown() {
                         return;
                     }
                 }
@@ -108,7 +160,7 @@ impl Strategy for KoenStrategy {
                 let ask_price_f = best_ask_price as f64 / 1000.0;
                 let mid_price = (bid_price_f + ask_price_f) / 2.0;
 
-                if mid_price < self.price_lower_bound || mid_price > self.price_upper_bound {
+                if mid_price < params.price_lower_bound || mid_price > params.price_upper_bound {
                     return;
                 }
 
@@ -148,7 +200,7 @@ impl Strategy for KoenStrategy {
                     let a1_size_f = a1_size as f64 / 1000.0;
                     let a2_size_f = a2_size as f64 / 1000.0;
 
-                    if a1_size_f >= self.max_counterparty_size {
+                    if a1_size_f >= params.max_counterparty_size {
                         return;
                     }
 
@@ -156,12 +208,12 @@ impl Strategy for KoenStrategy {
                         return;
                     }
 
-                    if b1_size_f < self.min_same_side_liquidity {
+                    if b1_size_f < params.min_same_side_liquidity {
                         return;
                     }
 
                     let spread = a1_price_f - b1_price_f;
-                    if spread > self.max_spread {
+                    if spread > params.max_spread {
                         return;
                     }
 
@@ -202,9 +254,9 @@ impl Strategy for KoenStrategy {
                     };
 
                     let predicted_move_coef = if use_hedge {
-                        self.predicted_move_hedge
+                        params.predicted_move_hedge
                     } else {
-                        self.predicted_move
+                        params.predicted_move
                     };
 
                     let predicted_delta = gap * predicted_move_coef;
@@ -216,7 +268,7 @@ impl Strategy for KoenStrategy {
                     let min_edge = 0.002;
                     if predicted_delta > 0.0 && predicted_price >= a1_price_f && (predicted_price - a1_price_f) >= min_edge {
                         let price_int = Self::price_to_int(a1_price_f);
-                        let trade_size = self.max_order_size;
+                        let trade_size = params.max_order_size;
                         if trade_size <= 0.0 {
                             return;
                         }
@@ -232,6 +284,7 @@ impl Strategy for KoenStrategy {
 
                         if let Err(err) = StrategyClient::place_limit_order(
                             Arc::clone(&ctx),
+                            self.name(),
                             asset_id,
                             OrderSide::Buy,
                             price_int,
@@ -241,9 +294,6 @@ impl Strategy for KoenStrategy {
                         ) {
                             error!("[{}] Failed to place BUY order: {}", self.name(), err);
                         } else {
-                            let ctx_for_cancel = Arc::clone(&ctx);
-                            let asset_for_cancel = asset_id.to_string();
-                            let cancel_delay = self.cancel_after;
                             let hedge_flag = if use_hedge { " [HEDGE]" } else { "" };
                             info!(
                                 "[{}] BUY executed{} asset={} gap={:.4} mid={:.3} predicted={:.3} size={:.3} asset_net_pos={:.3} asset_net_pos_units={} price={:.3} | bids: {:.3}@{:.3}, {:.3}@{:.3} | asks: {:.3}@{:.3}, {:.3}@{:.3}",
@@ -266,37 +316,13 @@ impl Strategy for KoenStrategy {
                                 a2_price_f,
                                 a2_size_f
                             );
-                            self.last_trade.insert(asset_id.to_string(), Instant::now());
-                            tokio::spawn(async move {
-                                sleep(cancel_delay).await;
-                                let still_open = ctx_for_cancel
-                                    .poly_state
-                                    .open_orders
-                                    .get(&asset_for_cancel)
-                                    .map(|orders| {
-                                        orders.order_exists(OrderSide::Buy, price_int, size_int)
-                                    })
-                                    .unwrap_or(false);
-                                if still_open {
-                                    if let Err(err) = StrategyClient::cancel_orders(
-                                        Arc::clone(&ctx_for_cancel),
-                                        &asset_for_cancel,
-                                        vec![(OrderSide::Buy, price_int, size_int)],
-                                    ) {
-                                        error!(
-                                            "[{}] Failed to cancel stale BUY order for asset {}: {}",
-                                            "KoenStrategy", asset_for_cancel, err
-                                        );
-                                    } else {
-                                        info!(
-                                            "[KoenStrategy] Canceled unfilled BUY order asset={} price={:.3} size={:.3}",
-                                            asset_for_cancel,
-                                            price_int as f64 / 1000.0,
-                                            size_int as f64 / 1000.0
-                                        );
-                                    }
-                                }
-                            });
+                            self.last_trade
+                                .insert(asset_id.to_string(), ctx.poly_state.now());
+                            ctx.timers.schedule(
+                                self.name(),
+                                format!("{}:{}:{}", asset_id, price_int, size_int),
+                                params.cancel_after(),
+                            );
                         }
                     }
                 }
@@ -304,3 +330,25 @@ impl Strategy for KoenStrategy {
         }
     }
 }
+
+#[async_trait]
+impl AsyncStrategy for KoenStrategy {
+    fn timers(&self) -> Vec<TimerSpec> {
+        vec![TimerSpec {
+            name: "expire_cooldowns",
+            every: COOLDOWN_SWEEP_EVERY,
+        }]
+    }
+
+    async fn on_timer(&self, ctx: Arc<StrategyContext>, timer: TimerEvent) {
+        match timer {
+            TimerEvent::Periodic(_) => {
+                let cooldown = self.params.get().trade_cooldown();
+                let now = ctx.poly_state.now();
+                self.last_trade
+                    .retain(|_, traded_at| since(now, *traded_at) < cooldown);
+            }
+            TimerEvent::Scheduled(key) => self.cancel_if_open(ctx, &key),
+        }
+    }
+}
@@ -1 +1,79 @@
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
+use crate::strategies::params::StrategyParams;
+
+/// Prices are in dollars and sizes in shares.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KoenParams {
+    /// Widest spread that is still traded.
+    pub max_spread: f64,
+    /// Mid prices outside these bounds are not traded.
+    pub price_lower_bound: f64,
+    pub price_upper_bound: f64,
+    /// Expected move of the mid per unit of book gap.
+    pub predicted_move: f64,
+    /// Same, when buying NO against a long YES position.
+    pub predicted_move_hedge: f64,
+    pub max_order_size: f64,
+    /// Largest best ask that is still lifted.
+    pub max_counterparty_size: f64,
+    /// Smallest best bid that backs a buy.
+    pub min_same_side_liquidity: f64,
+    /// Between two trades on one asset.
+    pub trade_cooldown_secs: u64,
+    /// Buys still open after this are cancelled.
+    pub cancel_after_ms: u64,
+}
+
+impl Default for KoenParams {
+    fn default() -> Self {
+        Self {
+            max_spread: 0.011,
+            price_lower_bound: 0.025,
+            price_upper_bound: 0.975,
+            predicted_move: 0.06,
+            predicted_move_hedge: 0.2,
+            max_order_size: 50.0,
+            max_counterparty_size: 100.0,
+            min_same_side_liquidity: 150.0,
+            trade_cooldown_secs: 60,
+            cancel_after_ms: 1_000,
+        }
+    }
+}
+
+impl KoenParams {
+    pub fn trade_cooldown(&self) -> Duration {
+        Duration::from_secs(self.trade_cooldown_secs)
+    }
+
+    pub fn cancel_after(&self) -> Duration {
+        Duration::from_millis(self.cancel_after_ms)
+    }
+}
+
+impl StrategyParams for KoenParams {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            0.0 <= self.price_lower_bound
+                && self.price_lower_bound < self.price_upper_bound
+                && self.price_upper_bound <= 1.0,
+            "price bounds must satisfy 0 <= price_lower_bound < price_upper_bound <= 1"
+        );
+        ensure!(self.max_spread > 0.0, "max_spread must be positive");
+        ensure!(
+            self.predicted_move.is_finite() && self.predicted_move_hedge.is_finite(),
+            "predicted moves must be finite"
+        );
+        ensure!(self.max_order_size > 0.0, "max_order_size must be positive");
+        ensure!(
+            self.trade_cooldown_secs > 0,
+            "trade_cooldown_secs must be positive"
+        );
+        ensure!(self.cancel_after_ms > 0, "cancel_after_ms must be positive");
+        Ok(())
+    }
+}
@@ -1,22 +1,63 @@
-// Orderbook configuration mirrors the legacy market-making bot defaults.
-pub const IGNORING_VOLUME: u32 = 2000 * 1000;
-pub const SEARCH_DEPTH: u32 = 30;
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
 
-// Main configuration
-pub const MARKET_LIMIT: u32 = 30 * 1000;
+use crate::strategies::params::StrategyParams;
 
-pub const ALPHA: u32 = 2;
-pub const SECONDARY_ALPHA: u32 = 2;
+/// Prices are in tenths of a cent and sizes in thousandths of a share, like book levels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NegRiskParams {
+    /// Events whose outcomes add up to more than this are skipped.
+    pub market_limit: u32,
+    /// Margin below the price at which the bid completes the event.
+    pub alpha: u32,
+    /// Same, when the thinnest other outcome has 3 shares or less.
+    pub secondary_alpha: u32,
+    /// Events up to this sum count as small.
+    pub small_market_amount: i32,
+    /// Volume across the event, split over its outcomes, for small events and the rest.
+    pub max_small_market_volume: i32,
+    pub max_volume: i32,
+    /// Smaller bids are not placed.
+    pub min_auto_buy_volume: i32,
+    /// Between two orders.
+    pub rate_limit_ms: u64,
+    /// Resting bids older than this are canceled and placed again at the current price.
+    pub refresh_time_s: u64,
+}
 
-pub const SMALL_MARKET_AMOUNT: i32 = 4 * 1000;
-pub const MAX_SMALL_MARKET_VOLUME: i32 = 20000 * 1000;
-pub const MAX_VOLUME: i32 = 20000 * 1000;
+impl Default for NegRiskParams {
+    fn default() -> Self {
+        Self {
+            market_limit: 30 * 1000,
+            alpha: 2,
+            secondary_alpha: 2,
+            small_market_amount: 4 * 1000,
+            max_small_market_volume: 20000 * 1000,
+            max_volume: 20000 * 1000,
+            min_auto_buy_volume: 20 * 1000,
+            rate_limit_ms: 200,
+            refresh_time_s: 3 * 60 * 60,
+        }
+    }
+}
 
-pub const MIN_TAKER_VOLUME: u32 = 300 * 1000;
-
-// Maker leg quotes
-pub const REFRESH_TIME: u64 = 3 * 60 * 60;
-pub const RATE_LIMIT: u128 = 200; // milliseconds between orders
-
-pub const MIN_AUTO_BUY_VOLUME: i32 = 20 * 1000;
-pub const MIDPOINT_DISTANCE: i32 = 40;
+impl StrategyParams for NegRiskParams {
+    fn validate(&self) -> Result<()> {
+        ensure!(self.market_limit > 0, "market_limit must be positive");
+        ensure!(
+            self.alpha < 1000 && self.secondary_alpha < 1000,
+            "alpha and secondary_alpha must be below 1000"
+        );
+        ensure!(
+            self.max_volume > 0 && self.max_small_market_volume > 0,
+            "max volumes must be positive"
+        );
+        ensure!(
+            self.small_market_amount >= 0 && self.min_auto_buy_volume >= 0,
+            "small_market_amount and min_auto_buy_volume must not be negative"
+        );
+        ensure!(self.refresh_time_s > 0, "refresh_time_s must be positive");
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use dashmap::DashMap;
 use log::error;
+use serde_json::Value;
 use std::{
     cmp::min,
     collections::{HashMap, HashSet},
+    error::Error,
     sync::{Arc, Mutex},
     time::{Duration, SystemTime},
 };
@@ -13,31 +15,49 @@ use crate::{
         orderbooks::poly_orderbook::OrderBook,
         poly_models::{Listener, OrderSide, PriceChange},
     },
-    
-    
-    
+    strategies::custom::negrisk::maker_taker_config::NegRiskParams,
+    strategies::custom::negrisk::utils::MarketMakingCalculated,
+    strategies::params::LiveParams,
     strategies::{
         strategy_utils::{StrategyAsset, StrategyClient},
         Strategy, StrategyContext,
     },
-    
-    strategies::custom::negrisk::maker_taker_config::{
-        ALPHA, MARKET_LIMIT, MAX_SMALL_MARKET_VOLUME, MAX_VOLUME, MIN_AUTO_BUY_VOLUME,
-        RATE_LIMIT, SECONDARY_ALPHA, SMALL_MARKET_AMOUNT,
-    },
-    strategies::custom::negrisk::utils::MarketMakingCalculated,
 };
 
+/// How long an order counts as in flight if it never shows up among the open orders.
+const ORDER_ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct NegRiskNoMakerStrategy {
+    params: LiveParams<NegRiskParams>,
     event_asset_cache: DashMap<String, Arc<Vec<String>>>,
     last_order_time: Mutex<SystemTime>,
+    /// Per neg-risk event, the asset and time of an order not yet seen among the open
+    /// orders. Outcomes of one event can be handled on different processor lanes.
+    in_flight: DashMap<String, (String, SystemTime)>,
+    /// Per asset, when its resting bid was placed.
+    placed_at: DashMap<String, SystemTime>,
+}
+
+/// Book state and params `determine_signal` decides on.
+#[derive(Clone, Copy)]
+struct SignalInput<'a> {
+    params: &'a NegRiskParams,
+    slug: &'a str,
+    asset_id: &'a str,
+    orderbooks: &'a [OrderBookStats],
+    k1_total: i32,
+    bot_best_bid: i32,
+    has_open_bids: bool,
 }
 
 impl NegRiskNoMakerStrategy {
-    pub fn new() -> Self {
+    pub fn new(params: NegRiskParams) -> Self {
         Self {
+            params: LiveParams::new(params),
             event_asset_cache: DashMap::new(),
             last_order_time: Mutex::new(SystemTime::UNIX_EPOCH),
+            in_flight: DashMap::new(),
+            placed_at: DashMap::new(),
         }
     }
 
@@ -48,7 +68,7 @@ impl NegRiskNoMakerStrategy {
         }
 
         let market = match ctx.poly_state.markets.get(asset_id) {
-            Some(market) => Arc::clone(market),
+            Some(market) => Arc::clone(market.value()),
             None => return,
         };
 
@@ -56,6 +76,7 @@ impl NegRiskNoMakerStrategy {
             Some(assets) => assets,
             None => return,
         };
+        let event_id = market.negRiskMarketID.clone().unwrap_or_default();
 
         let orderbooks = self.collect_orderbook_stats(ctx.as_ref(), &neg_risk_assets);
         if orderbooks.is_empty() {
@@ -69,23 +90,37 @@ impl NegRiskNoMakerStrategy {
             .max()
             .unwrap_or(0);
         let has_open_bids = !open_bids.is_empty();
+        if has_open_bids {
+            self.in_flight
+                .remove_if(&event_id, |_, (in_flight_asset, _)| {
+                    in_flight_asset == asset_id
+                });
+        }
         let k1_total = Self::compute_k1_total(&orderbooks);
 
+        let params = self.params.get();
         let slug = market.slug.clone().unwrap_or_default();
-        let signal = self.determine_signal(
-            &slug,
+        let signal = self.determine_signal(SignalInput {
+            params: &params,
+            slug: &slug,
             asset_id,
-            &orderbooks,
+            orderbooks: &orderbooks,
             k1_total,
             bot_best_bid,
             has_open_bids,
-        );
+        });
 
         let mut open_bids_option = Some(open_bids);
         match signal {
             MarketSignal::Place(calc) => {
                 if let Some(open_bids_snapshot) = open_bids_option.take() {
-                    self.process_place_signal(Arc::clone(&ctx), calc, open_bids_snapshot);
+                    self.process_place_signal(
+                        Arc::clone(&ctx),
+                        &params,
+                        &event_id,
+                        calc,
+                        open_bids_snapshot,
+                    );
                 }
             }
             MarketSignal::CancelBids => {
@@ -113,7 +148,8 @@ impl NegRiskNoMakerStrategy {
         }
 
         let mut asset_ids = HashSet::new();
-        for (other_asset, other_market) in ctx.poly_state.markets.iter() {
+        for entry in ctx.poly_state.markets.iter() {
+            let (other_asset, other_market) = entry.pair();
             if other_market
                 .negRiskMarketID
                 .as_ref()
@@ -175,15 +211,16 @@ impl NegRiskNoMakerStrategy {
         k1
     }
 
-    fn determine_signal(
-        &self,
-        slug: &str,
-        asset_id: &str,
-        orderbooks: &[OrderBookStats],
-        k1_total: i32,
-        bot_best_bid: i32,
-        has_open_bids: bool,
-    ) -> MarketSignal {
+    fn determine_signal(&self, input: SignalInput) -> MarketSignal {
+        let SignalInput {
+            params,
+            slug,
+            asset_id,
+            orderbooks,
+            k1_total,
+            bot_best_bid,
+            has_open_bids,
+        } = input;
         if k1_total <= 0 {
             return Self::cancel_signal(has_open_bids);
         }
@@ -210,7 +247,7 @@ impl NegRiskNoMakerStrategy {
             return Self::cancel_signal(has_open_bids);
         }
 
-        if k1_no_empty > MARKET_LIMIT as i32 {
+        if k1_no_empty > params.market_limit as i32 {
             return MarketSignal::NoAction;
         }
 
@@ -249,13 +286,13 @@ impl NegRiskNoMakerStrategy {
             return Self::cancel_signal(has_open_bids);
         }
 
-        let auto_buy_volume = if k1_no_empty <= SMALL_MARKET_AMOUNT {
+        let auto_buy_volume = if k1_no_empty <= params.small_market_amount {
             min(
                 bottleneck as i32,
-                (MAX_SMALL_MARKET_VOLUME / k1_total) * 1000,
+                (params.max_small_market_volume / k1_total) * 1000,
             )
         } else {
-            min(bottleneck as i32, (MAX_VOLUME / k1_total) * 1000)
+            min(bottleneck as i32, (params.max_volume / k1_total) * 1000)
         };
 
         if auto_buy_volume <= 0 {
@@ -263,9 +300,9 @@ impl NegRiskNoMakerStrategy {
         }
 
         let alpha = if bottleneck > 3000 {
-            ALPHA as i32
+            params.alpha as i32
         } else {
-            SECONDARY_ALPHA as i32
+            params.secondary_alpha as i32
         };
 
         if auto_buy_at_ask_threshold - alpha < 1 || auto_buy_at_ask_threshold > 999 {
@@ -423,32 +460,32 @@ impl NegRiskNoMakerStrategy {
     fn process_place_signal(
         &self,
         ctx: Arc<StrategyContext>,
+        params: &NegRiskParams,
+        event_id: &str,
         calc: MarketMakingCalculated,
         open_bids: Vec<(u32, u32)>,
     ) {
-        if calc.size_to_buy < MIN_AUTO_BUY_VOLUME {
+        if calc.size_to_buy < params.min_auto_buy_volume {
             self.cancel_bid_orders_with_snapshot(ctx, &calc.asset_id, open_bids);
             return;
         }
 
-        let elapsed = {
-            let guard = self.last_order_time.lock().unwrap();
-            SystemTime::now()
-                .duration_since(*guard)
-                .unwrap_or(Duration::ZERO)
-                .as_millis()
-        };
-
-        if open_bids.is_empty() && elapsed > RATE_LIMIT {
-            if let Err(err) = StrategyClient::place_limit_order(
-                Arc::clone(&ctx),
-                &calc.asset_id,
-                OrderSide::Buy,
-                calc.price_to_buy as u32,
-                calc.size_to_buy as u32,
-                &calc.tick_size,
-                true,
-            ) {
+        let now = ctx.poly_state.now().into();
+        if open_bids.is_empty() {
+            let placed =
+                self.place_guarded(now, params.rate_limit_ms, event_id, &calc.asset_id, || {
+                    StrategyClient::place_limit_order(
+                        Arc::clone(&ctx),
+                        self.name(),
+                        &calc.asset_id,
+                        OrderSide::Buy,
+                        calc.price_to_buy as u32,
+                        calc.size_to_buy as u32,
+                        &calc.tick_size,
+                        true,
+                    )
+                });
+            if let Err(err) = placed {
                 error!(
                     "[{}] Failed to initiate neg-risk order for {} at {}x{}: {}",
                     self.name(),
@@ -457,11 +494,6 @@ impl NegRiskNoMakerStrategy {
                     calc.size_to_buy,
                     err
                 );
-                return;
-            }
-
-            if let Ok(mut guard) = self.last_order_time.lock() {
-                *guard = SystemTime::now();
             }
             return;
         }
@@ -471,12 +503,51 @@ impl NegRiskNoMakerStrategy {
             .any(|(price, _)| (*price as i32) > calc.price_to_buy)
             || open_bids
                 .iter()
-                .any(|(_, size)| (*size as i32) > calc.size_to_buy + 200_000);
+                .any(|(_, size)| (*size as i32) > calc.size_to_buy + 200_000)
+            || self.is_due_for_refresh(&calc.asset_id, now, params.refresh_time_s);
 
         if cancel_conditions {
             self.cancel_bid_orders_with_snapshot(ctx, &calc.asset_id, open_bids);
         }
     }
+
+    /// Places an order on `asset_id` through `place` unless, at `now`, one went out less
+    /// than `rate_limit_ms` ago or an order on `event_id` is still in flight. The order
+    /// time lock is held until `place` returns, so two lanes cannot both pass the checks.
+    /// Returns whether the order was placed.
+    fn place_guarded(
+        &self,
+        now: SystemTime,
+        rate_limit_ms: u64,
+        event_id: &str,
+        asset_id: &str,
+        place: impl FnOnce() -> Result<(), Box<dyn Error + Send + Sync>>,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let mut last_order_time = self.last_order_time.lock().unwrap();
+        let since = |time: SystemTime| now.duration_since(time).unwrap_or(Duration::ZERO);
+        if since(*last_order_time) <= Duration::from_millis(rate_limit_ms) {
+            return Ok(false);
+        }
+        if let Some(in_flight) = self.in_flight.get(event_id) {
+            if since(in_flight.1) < ORDER_ACK_TIMEOUT {
+                return Ok(false);
+            }
+        }
+        place()?;
+        *last_order_time = now;
+        self.in_flight
+            .insert(event_id.to_string(), (asset_id.to_string(), now));
+        self.placed_at.insert(asset_id.to_string(), now);
+        Ok(true)
+    }
+
+    /// Whether the bid resting on `asset_id` was placed more than `refresh_time_s` before `now`.
+    fn is_due_for_refresh(&self, asset_id: &str, now: SystemTime, refresh_time_s: u64) -> bool {
+        self.placed_at.get(asset_id).is_some_and(|placed| {
+            now.duration_since(*placed).unwrap_or(Duration::ZERO)
+                > Duration::from_secs(refresh_time_s)
+        })
+    }
 }
 
 impl Strategy for NegRiskNoMakerStrategy {
@@ -484,6 +555,14 @@ impl Strategy for NegRiskNoMakerStrategy {
         "NegRiskNoMakerStrategy"
     }
 
+    fn params(&self) -> Value {
+        self.params.to_value()
+    }
+
+    fn reload_params(&self, params: Value) -> anyhow::Result<()> {
+        self.params.reload(params)
+    }
+
     fn poly_handle_market_price_change(
         &self,
         ctx: Arc<StrategyContext>,
@@ -542,3 +621,70 @@ enum MarketSignal {
     CancelBids,
     NoAction,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn outcomes_of_one_event_on_different_lanes_place_one_order() {
+        let strategy = Arc::new(NegRiskNoMakerStrategy::new(NegRiskParams::default()));
+        let placed = Arc::new(AtomicUsize::new(0));
+        let lanes: Vec<_> = (0..8)
+            .map(|lane| {
+                let (strategy, placed) = (Arc::clone(&strategy), Arc::clone(&placed));
+                std::thread::spawn(move || {
+                    strategy
+                        .place_guarded(
+                            SystemTime::now(),
+                            0,
+                            "event",
+                            &format!("outcome{}", lane),
+                            || {
+                                std::thread::sleep(Duration::from_millis(5));
+                                placed.fetch_add(1, Ordering::SeqCst);
+                                Ok(())
+                            },
+                        )
+                        .unwrap()
+                })
+            })
+            .collect();
+        let passed = lanes
+            .into_iter()
+            .map(|lane| lane.join().unwrap())
+            .filter(|placed| *placed)
+            .count();
+        assert_eq!((passed, placed.load(Ordering::SeqCst)), (1, 1));
+
+        // Other events are only held back by the rate limit.
+        assert!(strategy
+            .place_guarded(SystemTime::now(), 0, "other", "outcome0", || Ok(()))
+            .unwrap());
+        // A failed placement leaves nothing in flight.
+        assert!(strategy
+            .place_guarded(SystemTime::now(), 0, "third", "outcome0", || Err(
+                "rejected".into()
+            ))
+            .is_err());
+        assert!(strategy
+            .place_guarded(SystemTime::now(), 0, "third", "outcome0", || Ok(()))
+            .unwrap());
+    }
+
+    #[test]
+    fn bids_are_refreshed_after_refresh_time() {
+        let strategy = NegRiskNoMakerStrategy::new(NegRiskParams::default());
+        let placed = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let after = |secs| placed + Duration::from_secs(secs);
+        assert!(!strategy.is_due_for_refresh("outcome0", after(60), 30));
+
+        strategy
+            .place_guarded(placed, 0, "event", "outcome0", || Ok(()))
+            .unwrap();
+        assert!(!strategy.is_due_for_refresh("outcome0", after(30), 30));
+        assert!(strategy.is_due_for_refresh("outcome0", after(31), 30));
+        assert!(!strategy.is_due_for_refresh("outcome1", after(31), 30));
+    }
+}
@@ -1,5 +1,47 @@
-pub const MAX_VOLUME: u32 = 2000_000;
-pub const TARGET_ORDER_SIZE: u32 = 200_000;
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::strategies::params::StrategyParams;
+
+/// Sizes are in thousandths of a share, like book sizes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TobParams {
+    /// Only bids above this size are joined.
+    pub max_volume: u32,
+    /// Size of each order, and the net position at which buying stops.
+    pub target_order_size: u32,
+    /// Markets quoted, by slug.
+    pub slugs: Vec<String>,
+}
+
+impl Default for TobParams {
+    fn default() -> Self {
+        Self {
+            max_volume: 2_000_000,
+            target_order_size: 200_000,
+            slugs: [
+                "will-the-government-shutdown-end-november-13-182",
+                "will-the-government-shutdown-end-november-14-412",
+                "will-the-government-shutdown-end-november-15-216",
+                "will-the-government-shutdown-end-november-16-928",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl StrategyParams for TobParams {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.target_order_size > 0,
+            "target_order_size must be positive"
+        );
+        Ok(())
+    }
+}
+
 pub struct OrderBookContext {
     pub midpoint: u32,
     pub spread: u32,
@@ -1,4 +1,5 @@
 use log::{error, info};
+use serde_json::Value;
 use std::{
     collections::VecDeque,
     sync::{Arc, Mutex},
@@ -13,7 +14,8 @@ use crate::{
         poly_models::{LegacyPriceChange, Listener, OrderSide, PriceChange},
     },
     strategies::{
-        custom::tob::models::{OrderBookContext, MAX_VOLUME, TARGET_ORDER_SIZE},
+        custom::tob::models::{OrderBookContext, TobParams},
+        params::LiveParams,
         strategy_utils::{
             parse_millis, StrategyAsset, StrategyClient, StrategyOpenOrder, StrategyOrderBook,
             StrategyPosition,
@@ -23,6 +25,7 @@ use crate::{
 };
 
 pub struct TobStrategy {
+    params: LiveParams<TobParams>,
     orderbook_context_queue: Mutex<std::collections::VecDeque<OrderBookContext>>,
 }
 
@@ -33,8 +36,9 @@ struct PlannedOrder {
 }
 
 impl TobStrategy {
-    pub fn new() -> Self {
+    pub fn new(params: TobParams) -> Self {
         Self {
+            params: LiveParams::new(params),
             orderbook_context_queue: Mutex::new(VecDeque::with_capacity(1000)), // 1000 is the max size of the queue
         }
     }
@@ -56,12 +60,13 @@ impl TobStrategy {
     fn plan_order(
         &self,
         ctx: &StrategyContext,
+        params: &TobParams,
         asset_id: &str,
         orderbook: &OrderBook,
     ) -> Option<PlannedOrder> {
         let (bid_price, bid_size) = orderbook.best_bid()?;
 
-        if bid_size <= MAX_VOLUME {
+        if bid_size <= params.max_volume {
             return None;
         }
 
@@ -76,7 +81,7 @@ impl TobStrategy {
             .copied()
             .unwrap_or(0);
 
-        if current_position.saturating_sub(other_position) >= TARGET_ORDER_SIZE {
+        if current_position.saturating_sub(other_position) >= params.target_order_size {
             return None;
         }
 
@@ -91,7 +96,7 @@ impl TobStrategy {
             asset_id,
             OrderSide::Buy,
             bid_price,
-            TARGET_ORDER_SIZE,
+            params.target_order_size,
         );
 
         if exists {
@@ -102,7 +107,7 @@ impl TobStrategy {
 
         Some(PlannedOrder {
             price: bid_price,
-            size: TARGET_ORDER_SIZE,
+            size: params.target_order_size,
             tick_size: orderbook.get_tick_size().to_string(),
         })
     }
@@ -138,6 +143,7 @@ impl TobStrategy {
 
         if let Err(err) = StrategyClient::place_limit_order(
             ctx,
+            self.name(),
             &asset_id_owned,
             OrderSide::Buy,
             plan.price,
@@ -162,6 +168,14 @@ impl Strategy for TobStrategy {
         "TobStrategy"
     }
 
+    fn params(&self) -> Value {
+        self.params.to_value()
+    }
+
+    fn reload_params(&self, params: Value) -> anyhow::Result<()> {
+        self.params.reload(params)
+    }
+
     fn poly_handle_market_price_change(
         &self,
         ctx: Arc<StrategyContext>,
@@ -170,14 +184,16 @@ impl Strategy for TobStrategy {
     ) {
         let asset_id = &_payload.asset_id;
 
+        let params = self.params.get();
+
         let market = StrategyAsset::get_market(&ctx, asset_id);
         let slug = market.slug.clone().unwrap();
         // let volume_f64 = market.volume24hr.clone().unwrap();
 
-        if !(slug.eq_ignore_ascii_case("will-the-government-shutdown-end-november-13-182")
-            | slug.eq_ignore_ascii_case("will-the-government-shutdown-end-november-14-412")
-            | slug.eq_ignore_ascii_case("will-the-government-shutdown-end-november-15-216")
-            | slug.eq_ignore_ascii_case("will-the-government-shutdown-end-november-16-928"))
+        if !params
+            .slugs
+            .iter()
+            .any(|quoted| slug.eq_ignore_ascii_case(quoted))
         {
             return;
         }
@@ -227,7 +243,7 @@ impl Strategy for TobStrategy {
                     self.record_orderbook_context(&orderbook);
                 }
 
-                if let Some(plan) = self.plan_order(ctx.as_ref(), asset_id, &orderbook) {
+                if let Some(plan) = self.plan_order(ctx.as_ref(), &params, asset_id, &orderbook) {
                     drop(orderbook);
                     self.execute_order_plan(Arc::clone(&ctx), asset_id, plan);
                 }
@@ -7,8 +7,8 @@ use std::sync::Arc;
 
 use crate::exchange_listeners::poly_models::{AggOrderbook, Listener, PriceChange};
 use crate::strategies::strategy_utils::StrategyAsset;
+use crate::strategies::Strategy;
 use crate::strategies::StrategyContext;
-use crate::Strategy;
 
 #[derive(Default)]
 pub struct BBOLoggingStrategy;
@@ -35,15 +35,16 @@ impl BBOLoggingStrategy {
             "timestamp": timestamp,
         });
 
-        let line = serde_json::to_string(&line_value)
+        let mut line = serde_json::to_string(&line_value)
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        // One write per line, so an interrupted process never leaves half a line.
+        line.push('\n');
 
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&file_path)?;
         file.write_all(line.as_bytes())?;
-        file.write_all(b"\n")?;
         Ok(())
     }
 
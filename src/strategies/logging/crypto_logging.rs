@@ -9,8 +9,8 @@ use crate::exchange_listeners::crypto_models::{get_crypto_prices_map, CryptoPric
 use crate::exchange_listeners::orderbooks::{OrderbookDepth, OrderbookLevel};
 use crate::exchange_listeners::poly_models::{AggOrderbook, Listener, PriceChange};
 use crate::exchange_listeners::{Crypto, Exchange, Instrument};
+use crate::strategies::Strategy;
 use crate::strategies::StrategyContext;
-use crate::Strategy;
 
 #[derive(Default)]
 pub struct CryptoLoggingStrategy;
@@ -0,0 +1,86 @@
+use log::warn;
+use std::sync::Arc;
+
+use crate::{
+    credentials::ADDRESS_STR,
+    exchange_listeners::poly_models::{Listener, OrderSide, TradePayload, TradeRole, TradeStatus},
+    metrics::METRICS,
+    strategies::{Strategy, StrategyContext},
+};
+
+/// Counts own fills and feeds them to the PnL metrics.
+#[derive(Default)]
+pub struct FillMetricsStrategy;
+
+impl FillMetricsStrategy {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn record_fill(&self, asset_id: &str, side: &str, price: &str, size: &str, role: &str) {
+        let (Some(side), Ok(price), Ok(size)) = (
+            OrderSide::from_str(side),
+            price.parse::<f64>(),
+            size.parse::<f64>(),
+        ) else {
+            warn!(
+                "[{}] Unparseable {} fill for asset {}: side={} price={} size={}",
+                self.name(),
+                role,
+                asset_id,
+                side,
+                price,
+                size
+            );
+            return;
+        };
+        METRICS.fills_total.with(&[side.as_str(), role]).inc();
+        METRICS
+            .filled_shares_total
+            .with(&[side.as_str(), role])
+            .inc_by(size);
+        METRICS.traded_pnl.record_fill(asset_id, side, price, size);
+    }
+}
+
+impl Strategy for FillMetricsStrategy {
+    fn name(&self) -> &'static str {
+        "FillMetrics"
+    }
+
+    fn poly_handle_user_trade(
+        &self,
+        _ctx: Arc<StrategyContext>,
+        _listener: Listener,
+        trade: &TradePayload,
+    ) {
+        if trade.status != TradeStatus::Matched {
+            return;
+        }
+        match trade.trade_role {
+            TradeRole::Taker => self.record_fill(
+                &trade.asset_id,
+                &trade.side,
+                &trade.price,
+                &trade.size,
+                "taker",
+            ),
+            TradeRole::Maker => {
+                for order in trade
+                    .maker_orders
+                    .iter()
+                    .filter(|order| order.maker_address.eq_ignore_ascii_case(ADDRESS_STR))
+                {
+                    self.record_fill(
+                        &order.asset_id,
+                        &order.side,
+                        &order.price,
+                        &order.matched_amount,
+                        "maker",
+                    );
+                }
+            }
+            TradeRole::Unknown => {}
+        }
+    }
+}
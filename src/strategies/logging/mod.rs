@@ -1,5 +1,6 @@
 pub mod bbo_logging;
 pub mod crypto_logging;
+pub mod fill_metrics;
 pub mod main_logging;
 pub mod order_logging;
 pub mod position_logging;
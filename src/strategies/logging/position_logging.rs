@@ -56,6 +56,33 @@ impl Strategy for PositionLoggingStrategy {
         "PositionLogger"
     }
 
+    fn on_start(&self, ctx: Arc<StrategyContext>) {
+        let positions = &ctx.poly_state.positions;
+        if positions.is_empty() {
+            info!(
+                "[{}] No initial positions found for the configured account.",
+                self.name()
+            );
+            return;
+        }
+
+        info!(
+            "[{}] Loaded {} initial positions:",
+            self.name(),
+            positions.len()
+        );
+        for entry in positions.iter() {
+            let asset_id = entry.key();
+            match entry.value().read() {
+                Ok(position) => {
+                    let display_size = position.size as f64 / 1000.0;
+                    info!("    {} => {:.3}", asset_id, display_size);
+                }
+                Err(_) => warn!("    {} => <failed to read position lock>", asset_id),
+            }
+        }
+    }
+
     fn poly_handle_user_trade(
         &self,
         ctx: Arc<StrategyContext>,
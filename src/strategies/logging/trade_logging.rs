@@ -10,7 +10,7 @@ use crate::credentials::ADDRESS_STR;
 use crate::exchange_listeners::poly_models::{AggOrderbook, Listener, OrderSide, PriceChange, TradeRole, TradeStatus};
 use crate::strategies::strategy_utils::{StrategyAsset, parse_millis};
 use crate::strategies::StrategyContext;
-use crate::Strategy;
+use crate::strategies::Strategy;
 
 #[derive(Default)]
 pub struct TradeLoggingStrategy;
@@ -48,15 +48,16 @@ impl TradeLoggingStrategy {
             "timestamp": timestamp,
         });
 
-        let line = serde_json::to_string(&line_value)
+        let mut line = serde_json::to_string(&line_value)
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        // One write per line, so an interrupted process never leaves half a line.
+        line.push('\n');
 
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&file_path)?;
         file.write_all(line.as_bytes())?;
-        file.write_all(b"\n")?;
         Ok(())
     }
 
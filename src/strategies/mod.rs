@@ -3,11 +3,16 @@ pub mod logging;
 pub mod poly_state_updates;
 
 pub mod custom;
+pub mod params;
+pub mod pricing;
+pub mod profiler;
+pub mod registry;
 pub mod strategy;
 pub mod strategy_utils;
+pub mod timers;
 
 pub use poly_state_updates::{
-    update_orderbooks::UpdateOrderbookStrategy, update_orders::UpdateOrderStrategy,
-    update_positions::UpdatePositionStrategy,
+    paper_fills::PaperFillStrategy, update_orderbooks::UpdateOrderbookStrategy,
+    update_orders::UpdateOrderStrategy, update_positions::UpdatePositionStrategy,
 };
 pub use strategy::{Strategy, StrategyContext};
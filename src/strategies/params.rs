@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+/// Parameters a strategy reads from the launch config.
+pub trait StrategyParams: Serialize + DeserializeOwned + Default + Send + Sync {
+    /// Rejects values the strategy cannot run with.
+    fn validate(&self) -> Result<()>;
+}
+
+/// `params` over the defaults of `P`, validated; `null` keeps every default.
+pub fn parse_params<P: StrategyParams>(params: Value) -> Result<P> {
+    let parsed = if params.is_null() {
+        P::default()
+    } else {
+        serde_json::from_value(params).context("invalid parameters")?
+    };
+    parsed.validate().context("invalid parameters")?;
+    Ok(parsed)
+}
+
+/// Parameters that can be replaced while the strategy runs. Readers take a snapshot,
+/// so one event is handled with one set of parameters throughout.
+#[derive(Debug)]
+pub struct LiveParams<P>(RwLock<Arc<P>>);
+
+impl<P: StrategyParams> LiveParams<P> {
+    pub fn new(params: P) -> Self {
+        Self(RwLock::new(Arc::new(params)))
+    }
+
+    pub fn get(&self) -> Arc<P> {
+        Arc::clone(&self.0.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self.get().as_ref()).unwrap_or(Value::Null)
+    }
+
+    /// Replaces the parameters with `params` if they parse and validate, keeping the
+    /// current ones otherwise.
+    pub fn reload(&self, params: Value) -> Result<()> {
+        let parsed = parse_params(params)?;
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(parsed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::ensure;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct Sizes {
+        max: u32,
+        target: u32,
+    }
+
+    impl Default for Sizes {
+        fn default() -> Self {
+            Self { max: 10, target: 5 }
+        }
+    }
+
+    impl StrategyParams for Sizes {
+        fn validate(&self) -> Result<()> {
+            ensure!(self.target <= self.max, "target above max");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reload_keeps_current_params_when_invalid() {
+        let live = LiveParams::new(parse_params::<Sizes>(Value::Null).unwrap());
+        live.reload(json!({ "target": 8 })).unwrap();
+        assert_eq!((live.get().max, live.get().target), (10, 8));
+
+        assert!(live.reload(json!({ "target": 20 })).is_err());
+        assert!(live.reload(json!({ "size": 1 })).is_err());
+        assert_eq!((live.get().max, live.get().target), (10, 8));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod paper_fills;
 pub mod update_orderbooks;
 pub mod update_orders;
 pub mod update_positions;
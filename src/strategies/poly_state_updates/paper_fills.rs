@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use crate::{
+    exchange_listeners::{
+        paper_exchange,
+        poly_models::{AggOrderbook, Listener, PriceChange},
+    },
+    strategies::{Strategy, StrategyContext},
+};
+
+/// Fills paper orders against `poly_state.orderbooks` when paper trading. It runs ahead
+/// of the selected strategies, so an order fills at the earliest on the next update of
+/// its book, as if it took that long to reach the exchange.
+#[derive(Default)]
+pub struct PaperFillStrategy;
+
+impl PaperFillStrategy {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Strategy for PaperFillStrategy {
+    fn name(&self) -> &'static str {
+        "PaperFills"
+    }
+
+    fn poly_handle_market_agg_orderbook(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _listener: Listener,
+        snapshot: &AggOrderbook,
+    ) {
+        if ctx.poly_state.paper_trading {
+            paper_exchange::fill_crossed_orders(&ctx.poly_state, &snapshot.asset_id);
+        }
+    }
+
+    fn poly_handle_market_price_change(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _listener: Listener,
+        payload: &PriceChange,
+    ) {
+        if ctx.poly_state.paper_trading {
+            paper_exchange::fill_crossed_orders(&ctx.poly_state, &payload.asset_id);
+        }
+    }
+}
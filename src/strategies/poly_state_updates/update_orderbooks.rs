@@ -1,18 +1,26 @@
+use log::warn;
 use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     exchange_listeners::{
         orderbooks::poly_orderbook::OrderBook,
         poly_models::{LegacyPriceChange, Listener, PriceChange},
+        states::StaleReason,
     },
     strategies::Strategy,
 };
 
-pub struct UpdateOrderbookStrategy;
+/// Maintains `poly_state.orderbooks`, verifying every price change against the reported
+/// top of book. Books that disagree are marked stale and their asset ID is sent to the
+/// resync task; the next snapshot makes them fresh again.
+pub struct UpdateOrderbookStrategy {
+    resync_tx: UnboundedSender<String>,
+}
 
 impl UpdateOrderbookStrategy {
-    pub fn new() -> Self {
-        Self
+    pub fn new(resync_tx: UnboundedSender<String>) -> Self {
+        Self { resync_tx }
     }
 }
 
@@ -50,7 +58,8 @@ impl Strategy for UpdateOrderbookStrategy {
 
         ctx.poly_state
             .orderbooks
-            .insert(asset_id, Arc::new(RwLock::new(orderbook)));
+            .insert(asset_id.clone(), Arc::new(RwLock::new(orderbook)));
+        ctx.poly_state.mark_book_fresh(&asset_id);
     }
 
     fn poly_handle_market_price_change(
@@ -65,10 +74,26 @@ impl Strategy for UpdateOrderbookStrategy {
                     .prev_orderbooks
                     .insert(_payload.asset_id.clone(), book.snapshot());
 
-                // Pass an epoch timestamp string as the second argument.
-                // For now, use chrono to get the current epoch as a string.
-                let now_epoch = chrono::Utc::now().timestamp().to_string();
+                // Pass an epoch timestamp string as the second argument; in backtests
+                // it is the time of the replayed event.
+                let now_epoch = ctx.poly_state.now().timestamp().to_string();
                 book.apply_price_change(_payload, &now_epoch);
+
+                let in_sync = book.matches_top_of_book(
+                    _payload.best_bid.as_deref(),
+                    _payload.best_ask.as_deref(),
+                );
+                if !in_sync
+                    && ctx
+                        .poly_state
+                        .mark_book_stale(&_payload.asset_id, StaleReason::TopOfBookMismatch)
+                {
+                    warn!(
+                        "[UpdateOrderbooks] Book {} out of sync (feed bid {:?} ask {:?}). Resyncing.",
+                        _payload.asset_id, _payload.best_bid, _payload.best_ask
+                    );
+                    let _ = self.resync_tx.send(_payload.asset_id.clone());
+                }
             }
         }
     }
@@ -5,6 +5,7 @@ use crate::{
     exchange_listeners::poly_models::{
         Listener, OrderEventType, OrderSide, TradeRole, TradeStatus,
     },
+    metrics::METRICS,
     strategies::Strategy,
 };
 use log::{info, warn};
@@ -258,6 +259,8 @@ impl Strategy for UpdateOrderStrategy {
                                     price,
                                     original_size
                                 );
+                            } else {
+                                METRICS.order("cancelled");
                             }
                         }
                         Some(OrderSide::Sell) => {
@@ -269,6 +272,8 @@ impl Strategy for UpdateOrderStrategy {
                                     price,
                                     original_size
                                 );
+                            } else {
+                                METRICS.order("cancelled");
                             }
                         }
                         None => {
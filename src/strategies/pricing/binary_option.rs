@@ -0,0 +1,114 @@
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+
+/// Fair value of a cash-or-nothing binary paying 1 if the underlying closes at or above
+/// the strike, with greeks of the YES side. NO greeks are the negation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinaryQuote {
+    pub yes: f64,
+    pub no: f64,
+    /// dYES/dSpot.
+    pub delta: f64,
+    /// d²YES/dSpot².
+    pub gamma: f64,
+    /// dYES per unit of remaining log-return standard deviation.
+    pub vega: f64,
+    /// Change in YES over the next minute from variance decay alone.
+    pub theta: f64,
+    pub spot: f64,
+    pub strike: f64,
+    /// Remaining log-return variance until expiry.
+    pub variance: f64,
+}
+
+impl BinaryQuote {
+    /// The same quote seen from the NO token.
+    pub fn flipped(&self) -> Self {
+        Self {
+            yes: self.no,
+            no: self.yes,
+            delta: -self.delta,
+            gamma: -self.gamma,
+            vega: -self.vega,
+            theta: -self.theta,
+            ..*self
+        }
+    }
+}
+
+/// Prices P(S_T >= K) assuming a driftless lognormal underlying.
+///
+/// `variance` is the total log-return variance left until expiry and `minute_variance`
+/// the variance expected over the coming minute, used for theta.
+pub fn price_binary(spot: f64, strike: f64, variance: f64, minute_variance: f64) -> BinaryQuote {
+    let settled = |yes: f64| BinaryQuote {
+        yes,
+        no: 1.0 - yes,
+        delta: 0.0,
+        gamma: 0.0,
+        vega: 0.0,
+        theta: 0.0,
+        spot,
+        strike,
+        variance: variance.max(0.0),
+    };
+
+    if variance <= 0.0 || spot <= 0.0 || strike <= 0.0 {
+        return settled(if spot >= strike { 1.0 } else { 0.0 });
+    }
+
+    let normal = Normal::new(0.0, 1.0).expect("standard normal");
+    let std_dev = variance.sqrt();
+    let d2 = ((spot / strike).ln() - variance / 2.0) / std_dev;
+    let d1 = d2 + std_dev;
+    let density = normal.pdf(d2);
+    let yes = normal.cdf(d2);
+
+    BinaryQuote {
+        yes,
+        no: 1.0 - yes,
+        delta: density / (spot * std_dev),
+        gamma: -density * d1 / (spot * spot * variance),
+        vega: -density * d1 / std_dev,
+        theta: density * d1 / (2.0 * variance) * minute_variance.min(variance),
+        spot,
+        strike,
+        variance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_the_money_is_near_half() {
+        let quote = price_binary(100_000.0, 100_000.0, 0.005_f64.powi(2), 1e-7);
+        assert!((quote.yes - 0.5).abs() < 0.01);
+        assert!((quote.yes + quote.no - 1.0).abs() < 1e-12);
+        assert!(quote.delta > 0.0);
+    }
+
+    #[test]
+    fn greeks_match_finite_differences() {
+        let (spot, strike, variance) = (100_200.0, 100_000.0, 0.004_f64.powi(2));
+        let quote = price_binary(spot, strike, variance, 0.0);
+        let h = 1.0;
+        let up = price_binary(spot + h, strike, variance, 0.0);
+        let down = price_binary(spot - h, strike, variance, 0.0);
+        assert!((quote.delta - (up.yes - down.yes) / (2.0 * h)).abs() < 1e-8);
+        assert!((quote.gamma - (up.delta - down.delta) / (2.0 * h)).abs() < 1e-9);
+
+        let s = variance.sqrt();
+        let ds = 1e-6;
+        let wide = price_binary(spot, strike, (s + ds).powi(2), 0.0);
+        let narrow = price_binary(spot, strike, (s - ds).powi(2), 0.0);
+        assert!((quote.vega - (wide.yes - narrow.yes) / (2.0 * ds)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn expired_market_settles() {
+        assert_eq!(price_binary(101.0, 100.0, 0.0, 0.0).yes, 1.0);
+        assert_eq!(price_binary(100.0, 100.0, 0.0, 0.0).yes, 1.0);
+        assert_eq!(price_binary(99.0, 100.0, 0.0, 0.0).no, 1.0);
+    }
+}
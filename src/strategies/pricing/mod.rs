@@ -0,0 +1,7 @@
+pub mod binary_option;
+pub mod up_down_pricing;
+pub mod update_variance_profile;
+pub mod variance_profile;
+
+pub use up_down_pricing::UpDownPricingStrategy;
+pub use update_variance_profile::UpdateVarianceProfileStrategy;
@@ -0,0 +1,305 @@
+use anyhow::{ensure, Result};
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{
+    exchange_listeners::{
+        autodiscover_markets::MarketConfig,
+        crypto_models::{CryptoPriceUpdate, Quote, RateKind},
+        orderbooks::{OrderbookDepth, OrderbookLevel},
+        Crypto, Exchange, Instrument,
+    },
+    strategies::{
+        params::{LiveParams, StrategyParams},
+        pricing::{
+            binary_option::price_binary,
+            variance_profile::{SharedVarianceProfile, VarianceProfile},
+        },
+        Strategy, StrategyContext,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpDownPricingConfig {
+    /// Share of the variance level taken from realized returns (0 = profile only).
+    pub realized_weight: f64,
+    /// EWMA factor applied to each deseasonalised squared 1m return.
+    pub realized_alpha: f64,
+    /// Minutes observed before realized variance is blended in.
+    pub min_realized_samples: u32,
+    /// Bounds on the realized/profile variance ratio.
+    pub min_realized_ratio: f64,
+    pub max_realized_ratio: f64,
+}
+
+impl Default for UpDownPricingConfig {
+    fn default() -> Self {
+        Self {
+            realized_weight: 0.5,
+            realized_alpha: 0.05,
+            min_realized_samples: 15,
+            min_realized_ratio: 0.25,
+            max_realized_ratio: 4.0,
+        }
+    }
+}
+
+impl StrategyParams for UpDownPricingConfig {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            (0.0..=1.0).contains(&self.realized_weight),
+            "realized_weight must be within [0, 1]"
+        );
+        ensure!(
+            self.realized_alpha > 0.0 && self.realized_alpha <= 1.0,
+            "realized_alpha must be within (0, 1]"
+        );
+        ensure!(
+            0.0 < self.min_realized_ratio && self.min_realized_ratio <= self.max_realized_ratio,
+            "realized ratio bounds must satisfy 0 < min_realized_ratio <= max_realized_ratio"
+        );
+        Ok(())
+    }
+}
+
+/// A priced hourly up-or-down market.
+#[derive(Debug, Clone)]
+pub struct UpDownMarket {
+    pub crypto: Crypto,
+    pub config: MarketConfig,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl UpDownMarket {
+    pub fn new(crypto: Crypto, config: MarketConfig) -> Option<Self> {
+        let end = config.end_time_utc()?;
+        Some(Self {
+            crypto,
+            start: end - Duration::hours(1),
+            end,
+            config,
+        })
+    }
+}
+
+/// EWMA of squared 1m log-returns divided by the profile's expectation for that minute.
+#[derive(Debug, Clone, Copy)]
+struct RealizedVariance {
+    minute: i64,
+    price: f64,
+    ratio: f64,
+    samples: u32,
+}
+
+impl RealizedVariance {
+    fn observe(&mut self, now: DateTime<Utc>, price: f64, profile: &VarianceProfile, alpha: f64) {
+        let minute = now.timestamp().div_euclid(60);
+        if minute <= self.minute {
+            return;
+        }
+        if self.price > 0.0 {
+            let elapsed = (minute - self.minute) as f64;
+            let expected = profile.minute_variance(now) * elapsed;
+            if expected > 0.0 {
+                let ret = (price / self.price).ln();
+                self.ratio += alpha * (ret * ret / expected - self.ratio);
+                self.samples += 1;
+            }
+        }
+        self.minute = minute;
+        self.price = price;
+    }
+}
+
+impl Default for RealizedVariance {
+    fn default() -> Self {
+        Self {
+            minute: i64::MIN,
+            price: 0.0,
+            ratio: 1.0,
+            samples: 0,
+        }
+    }
+}
+
+/// Prices hourly up-or-down markets from the composite index and publishes YES/NO quotes
+/// into `AppState::binary_quotes`. Must run after `UpdateFairValueStrategy`.
+pub struct UpDownPricingStrategy {
+    profile: SharedVarianceProfile,
+    config: LiveParams<UpDownPricingConfig>,
+    markets: RwLock<Vec<UpDownMarket>>,
+    realized: Mutex<HashMap<Crypto, RealizedVariance>>,
+}
+
+impl UpDownPricingStrategy {
+    pub fn new(profile: SharedVarianceProfile) -> Self {
+        Self::with_config(profile, UpDownPricingConfig::default())
+    }
+
+    pub fn with_config(profile: SharedVarianceProfile, config: UpDownPricingConfig) -> Self {
+        Self {
+            profile,
+            config: LiveParams::new(config),
+            markets: RwLock::new(Vec::new()),
+            realized: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn add_market(&self, crypto: Crypto, config: MarketConfig) {
+        match UpDownMarket::new(crypto, config) {
+            Some(market) => {
+                info!(
+                    "[UpDownPricing] Pricing {} (strike {}, ends {})",
+                    market.config.name, market.config.strike_price, market.end
+                );
+                if let Ok(mut markets) = self.markets.write() {
+                    markets.push(market);
+                }
+            }
+            None => warn!("[UpDownPricing] Could not parse the end time of a market"),
+        }
+    }
+
+    pub fn markets(&self) -> Vec<UpDownMarket> {
+        self.markets.read().map(|m| m.clone()).unwrap_or_default()
+    }
+
+    /// Multiplier applied to the profile variance from recent realized returns.
+    fn variance_scale(config: &UpDownPricingConfig, realized: &RealizedVariance) -> f64 {
+        if realized.samples < config.min_realized_samples {
+            return 1.0;
+        }
+        let ratio = realized
+            .ratio
+            .clamp(config.min_realized_ratio, config.max_realized_ratio);
+        1.0 - config.realized_weight + config.realized_weight * ratio
+    }
+
+    fn reprice(&self, ctx: &StrategyContext, crypto: Crypto) {
+        let Some(fair_value) = ctx.app_state.fair_value(crypto) else {
+            return;
+        };
+        // Strikes are Binance USDT candle opens, so price the index in USDT.
+        let usd_per_usdt = ctx.app_state.rates.usd_per(Quote::Usdt).unwrap_or(1.0);
+        let spot = fair_value.price / usd_per_usdt;
+        let now = ctx.poly_state.now();
+        let Ok(profile) = self.profile.read() else {
+            return;
+        };
+
+        let config = self.config.get();
+        let scale = {
+            let Ok(mut realized) = self.realized.lock() else {
+                return;
+            };
+            let realized = realized.entry(crypto).or_default();
+            realized.observe(now, spot, &profile, config.realized_alpha);
+            Self::variance_scale(&config, realized)
+        };
+
+        let Ok(markets) = self.markets.read() else {
+            return;
+        };
+        for market in markets.iter().filter(|m| m.crypto == crypto) {
+            let variance = profile.expected_variance(now.max(market.start), market.end) * scale;
+            let minute_variance = profile.minute_variance(now) * scale;
+            let quote = price_binary(spot, market.config.strike_price, variance, minute_variance);
+
+            ctx.app_state
+                .binary_quotes
+                .insert(market.config.no_token_id.clone(), quote.flipped());
+            ctx.app_state
+                .binary_quotes
+                .insert(market.config.yes_token_id.clone(), quote);
+        }
+    }
+
+    fn reprice_all(&self, ctx: &StrategyContext) {
+        let mut cryptos: Vec<Crypto> = self.markets().iter().map(|m| m.crypto).collect();
+        cryptos.sort();
+        cryptos.dedup();
+        for crypto in cryptos {
+            self.reprice(ctx, crypto);
+        }
+    }
+}
+
+impl Strategy for UpDownPricingStrategy {
+    fn name(&self) -> &'static str {
+        "UpDownPricing"
+    }
+
+    fn params(&self) -> serde_json::Value {
+        self.config.to_value()
+    }
+
+    fn reload_params(&self, params: serde_json::Value) -> anyhow::Result<()> {
+        self.config.reload(params)
+    }
+
+    fn crypto_handle_price_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _exchange: Exchange,
+        _instrument: Instrument,
+        crypto: Crypto,
+        _depth: OrderbookDepth,
+        _price_update: &CryptoPriceUpdate,
+    ) {
+        self.reprice(&ctx, crypto);
+    }
+
+    fn crypto_handle_l2_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _exchange: Exchange,
+        _instrument: Instrument,
+        crypto: Crypto,
+        _bids: &[OrderbookLevel],
+        _asks: &[OrderbookLevel],
+    ) {
+        self.reprice(&ctx, crypto);
+    }
+
+    fn market_handle_rollover(
+        &self,
+        ctx: Arc<StrategyContext>,
+        crypto: Crypto,
+        previous: Option<&MarketConfig>,
+        next: &MarketConfig,
+    ) {
+        if let Some(previous) = previous {
+            ctx.app_state.binary_quotes.remove(&previous.yes_token_id);
+            ctx.app_state.binary_quotes.remove(&previous.no_token_id);
+        }
+        // One hourly series per underlying: the new market replaces the old one.
+        if let Ok(mut markets) = self.markets.write() {
+            markets.retain(|m| m.crypto != crypto);
+        }
+        self.add_market(crypto, next.clone());
+        self.reprice(&ctx, crypto);
+    }
+
+    fn on_market_closed(&self, ctx: Arc<StrategyContext>, asset_ids: &[String]) {
+        for asset_id in asset_ids {
+            ctx.app_state.binary_quotes.remove(asset_id);
+        }
+        if let Ok(mut markets) = self.markets.write() {
+            markets.retain(|m| !asset_ids.contains(&m.config.yes_token_id));
+        }
+    }
+
+    fn conversion_handle_rate_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        _kind: RateKind,
+        _value: f64,
+    ) {
+        self.reprice_all(&ctx);
+    }
+}
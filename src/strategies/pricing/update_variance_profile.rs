@@ -0,0 +1,242 @@
+use chrono::{DateTime, TimeZone, Utc};
+use log::{error, info};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+
+use crate::{
+    exchange_listeners::{
+        crypto_models::CryptoPriceUpdate, orderbooks::OrderbookDepth, Crypto, Exchange, Instrument,
+    },
+    strategies::{pricing::variance_profile::SharedVarianceProfile, Strategy, StrategyContext},
+};
+
+#[derive(Debug, Clone)]
+pub struct VarianceUpdaterConfig {
+    /// Feed whose 1m returns are sampled; should match the profile's symbol.
+    pub crypto: Crypto,
+    pub exchange: Exchange,
+    pub instrument: Instrument,
+    /// EWMA weight of one observed minute in its bucket.
+    pub alpha: f64,
+    /// Squared returns are capped at this multiple of the bucket before updating.
+    pub max_ratio: f64,
+    /// Where refreshed profiles are written; `None` keeps updates in memory only.
+    pub path: Option<PathBuf>,
+    /// Minutes between saves.
+    pub save_every_minutes: u32,
+}
+
+impl Default for VarianceUpdaterConfig {
+    fn default() -> Self {
+        Self {
+            crypto: Crypto::BTC,
+            exchange: Exchange::Binance,
+            instrument: Instrument::Spot,
+            // Roughly a 12-week half-life at 30 samples per bucket and week.
+            alpha: 0.002,
+            max_ratio: 25.0,
+            path: None,
+            save_every_minutes: 60,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MinuteBar {
+    /// Minute since epoch of the bar being built.
+    minute: i64,
+    close: f64,
+    previous_close: Option<f64>,
+    minutes_since_save: u32,
+    updates: u64,
+}
+
+/// Keeps the intraday variance profile current from live 1m returns and
+/// periodically writes it back in the original JSON schema.
+pub struct UpdateVarianceProfileStrategy {
+    profile: SharedVarianceProfile,
+    config: VarianceUpdaterConfig,
+    bar: Mutex<MinuteBar>,
+}
+
+impl UpdateVarianceProfileStrategy {
+    pub fn new(profile: SharedVarianceProfile, config: VarianceUpdaterConfig) -> Self {
+        Self {
+            profile,
+            config,
+            bar: Mutex::new(MinuteBar::default()),
+        }
+    }
+
+    /// Feeds one price observation; closes the previous bar when the minute rolls over.
+    pub fn observe(&self, now: DateTime<Utc>, price: f64) {
+        if price <= 0.0 || !price.is_finite() {
+            return;
+        }
+        let Ok(mut bar) = self.bar.lock() else {
+            return;
+        };
+        let minute = now.timestamp().div_euclid(60);
+        if minute == bar.minute {
+            bar.close = price;
+            return;
+        }
+
+        let contiguous = minute == bar.minute + 1;
+        let closed = (bar.close > 0.0).then_some(bar.close);
+        match (contiguous, bar.previous_close, closed) {
+            (true, Some(previous), Some(close)) => {
+                let bar_start = Utc.timestamp_opt(bar.minute * 60, 0).single();
+                if let Some(bar_start) = bar_start {
+                    self.record(bar_start, (close / previous).ln());
+                    bar.updates += 1;
+                }
+                bar.previous_close = Some(close);
+            }
+            // A gap in the feed: the next return would span several minutes.
+            _ => bar.previous_close = if contiguous { closed } else { None },
+        }
+        bar.minute = minute;
+        bar.close = price;
+
+        bar.minutes_since_save += 1;
+        if bar.minutes_since_save >= self.config.save_every_minutes {
+            bar.minutes_since_save = 0;
+            let updates = bar.updates;
+            drop(bar);
+            self.save(now, updates);
+        }
+    }
+
+    fn record(&self, bar_start: DateTime<Utc>, log_return: f64) {
+        let Ok(mut profile) = self.profile.write() else {
+            return;
+        };
+        let cap = profile.bucket_variance(bar_start) * self.config.max_ratio;
+        let squared = log_return * log_return;
+        let squared = if cap > 0.0 { squared.min(cap) } else { squared };
+        profile.observe(bar_start, squared, self.config.alpha);
+    }
+
+    fn save(&self, now: DateTime<Utc>, updates: u64) {
+        let Some(path) = &self.config.path else {
+            return;
+        };
+        let snapshot = match self.profile.write() {
+            Ok(mut profile) => {
+                if let Some(metadata) = profile.metadata.as_object_mut() {
+                    metadata.insert("updated_at_utc".to_string(), json!(now.to_rfc3339()));
+                    metadata.insert("online_updates".to_string(), json!(updates));
+                }
+                profile.clone()
+            }
+            Err(_) => return,
+        };
+        let path = path.clone();
+        let write = move || match snapshot.save(&path) {
+            Ok(()) => info!(
+                "[VarianceProfile] Saved profile to {} after {} online updates",
+                path.display(),
+                updates
+            ),
+            Err(e) => error!("[VarianceProfile] Failed to save profile: {:#}", e),
+        };
+        // Keep file I/O off the event processor.
+        match Handle::try_current() {
+            Ok(handle) => drop(handle.spawn_blocking(write)),
+            Err(_) => write(),
+        }
+    }
+}
+
+impl Strategy for UpdateVarianceProfileStrategy {
+    fn name(&self) -> &'static str {
+        "UpdateVarianceProfile"
+    }
+
+    fn crypto_handle_price_update(
+        &self,
+        ctx: Arc<StrategyContext>,
+        exchange: Exchange,
+        instrument: Instrument,
+        crypto: Crypto,
+        _depth: OrderbookDepth,
+        price_update: &CryptoPriceUpdate,
+    ) {
+        if crypto != self.config.crypto
+            || exchange != self.config.exchange
+            || instrument != self.config.instrument
+        {
+            return;
+        }
+        let mid = (price_update.best_bid_price + price_update.best_ask_price) / 2.0;
+        self.observe(ctx.poly_state.now(), mid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::pricing::variance_profile::VarianceProfile;
+    use chrono::Duration;
+
+    #[test]
+    fn updates_bucket_of_closed_minute() {
+        let profile = VarianceProfile::load("daily_half_hourly_variance_profiles_1m.json")
+            .unwrap()
+            .into_shared();
+        let config = VarianceUpdaterConfig {
+            alpha: 0.5,
+            ..VarianceUpdaterConfig::default()
+        };
+        let updater = UpdateVarianceProfileStrategy::new(Arc::clone(&profile), config);
+
+        let start = Utc.with_ymd_and_hms(2025, 10, 13, 9, 0, 0).unwrap();
+        let before = profile.read().unwrap().bucket_variance(start);
+        updater.observe(start, 100.0);
+        updater.observe(start + Duration::seconds(30), 100.0);
+        updater.observe(start + Duration::minutes(1), 100.1);
+        // Closing the 09:01 bar yields the first complete 1m return.
+        updater.observe(start + Duration::minutes(2), 100.1);
+
+        let after = profile.read().unwrap().bucket_variance(start);
+        let squared = (100.1f64 / 100.0).ln().powi(2).min(before * 25.0);
+        assert!((after - (before + 0.5 * (squared - before))).abs() < 1e-15);
+    }
+
+    #[test]
+    fn saves_to_runtime_path_and_reloads_from_it() {
+        let seed = "daily_half_hourly_variance_profiles_1m.json";
+        let dir = std::env::temp_dir().join(format!("variance-profile-{}", std::process::id()));
+        let path = dir.join("state").join("live.json");
+        let profile = VarianceProfile::load(seed).unwrap().into_shared();
+        let config = VarianceUpdaterConfig {
+            alpha: 0.5,
+            path: Some(path.clone()),
+            save_every_minutes: 3,
+            ..VarianceUpdaterConfig::default()
+        };
+        let updater = UpdateVarianceProfileStrategy::new(Arc::clone(&profile), config);
+
+        let start = Utc.with_ymd_and_hms(2025, 10, 13, 9, 0, 0).unwrap();
+        updater.observe(start, 100.0);
+        updater.observe(start + Duration::minutes(1), 100.1);
+        assert!(!path.exists());
+        // The third bar closes the first return and is due for a save.
+        updater.observe(start + Duration::minutes(2), 100.3);
+
+        let saved = VarianceProfile::load_or_seed(&path, seed).unwrap();
+        assert_eq!(
+            saved.bucket_variance(start),
+            profile.read().unwrap().bucket_variance(start)
+        );
+        assert_ne!(
+            saved.bucket_variance(start),
+            VarianceProfile::load(seed).unwrap().bucket_variance(start)
+        );
+        assert!(!path.with_extension("json.tmp").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
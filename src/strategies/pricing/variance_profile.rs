@@ -0,0 +1,241 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+pub const BUCKETS_PER_DAY: usize = 48;
+const BUCKETS_PER_WEEK: i64 = 7 * BUCKETS_PER_DAY as i64;
+const BUCKET_SECONDS: i64 = 30 * 60;
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Profile shared between the pricer and the online updater.
+pub type SharedVarianceProfile = Arc<RwLock<VarianceProfile>>;
+
+/// On-disk format of `daily_half_hourly_variance_profiles_1m.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileFile {
+    metadata: Value,
+    /// Weekday -> UTC half-hour bucket start (`"13.5"`) -> variance of 1m returns in percent².
+    average_profiles: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+/// Expected 1m log-return variance by UTC weekday and half-hour bucket.
+///
+/// Bucket values are taken to hold at the bucket midpoint and are linearly interpolated
+/// in between, wrapping from Sunday night back to Monday.
+#[derive(Debug, Clone)]
+pub struct VarianceProfile {
+    pub metadata: Value,
+    buckets: [[f64; BUCKETS_PER_DAY]; 7],
+}
+
+impl VarianceProfile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read variance profile {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    /// Loads `path` if it exists, and otherwise the `seed` profile it was derived from.
+    pub fn load_or_seed(path: impl AsRef<Path>, seed: impl AsRef<Path>) -> Result<Self> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Self::load(seed)
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: ProfileFile =
+            serde_json::from_str(json).context("failed to parse variance profile JSON")?;
+        let mut buckets = [[0.0; BUCKETS_PER_DAY]; 7];
+
+        for (day, weekday) in WEEKDAYS.iter().enumerate() {
+            let profile = file
+                .average_profiles
+                .get(*weekday)
+                .ok_or_else(|| anyhow!("variance profile has no {}", weekday))?;
+            for (key, value) in profile {
+                let hour: f64 = key
+                    .parse()
+                    .with_context(|| format!("invalid bucket '{}' for {}", key, weekday))?;
+                let bucket = (hour * 2.0).round() as usize;
+                if bucket >= BUCKETS_PER_DAY || !value.is_finite() || *value < 0.0 {
+                    return Err(anyhow!("invalid bucket '{}' for {}", key, weekday));
+                }
+                // Percent² to log-return variance.
+                buckets[day][bucket] = value / 10_000.0;
+            }
+        }
+
+        Ok(Self {
+            metadata: file.metadata,
+            buckets,
+        })
+    }
+
+    /// Serialises back to the same schema `from_json` reads.
+    pub fn to_json(&self) -> Result<String> {
+        let average_profiles = WEEKDAYS
+            .iter()
+            .zip(self.buckets.iter())
+            .map(|(weekday, buckets)| {
+                let profile = buckets
+                    .iter()
+                    .enumerate()
+                    .map(|(bucket, value)| {
+                        (format!("{:.1}", bucket as f64 / 2.0), value * 10_000.0)
+                    })
+                    .collect();
+                (weekday.to_string(), profile)
+            })
+            .collect();
+        let file = ProfileFile {
+            metadata: self.metadata.clone(),
+            average_profiles,
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+
+    /// Writes the profile to `path` through a temporary file, so readers never see a partial file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&tmp, self.to_json()?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn into_shared(self) -> SharedVarianceProfile {
+        Arc::new(RwLock::new(self))
+    }
+
+    /// Index of the bucket containing `at`, counted from Monday 00:00 UTC.
+    fn bucket_index(at: DateTime<Utc>) -> i64 {
+        let day = at.weekday().num_days_from_monday() as i64;
+        day * BUCKETS_PER_DAY as i64 + (at.hour() as i64) * 2 + (at.minute() as i64) / 30
+    }
+
+    fn bucket(&self, index: i64) -> f64 {
+        let index = index.rem_euclid(BUCKETS_PER_WEEK) as usize;
+        self.buckets[index / BUCKETS_PER_DAY][index % BUCKETS_PER_DAY]
+    }
+
+    /// Raw value of the bucket containing `at`.
+    pub fn bucket_variance(&self, at: DateTime<Utc>) -> f64 {
+        self.bucket(Self::bucket_index(at))
+    }
+
+    /// Seconds from the start of the week to `at`, including fractions.
+    fn week_seconds(at: DateTime<Utc>) -> f64 {
+        let day = at.weekday().num_days_from_monday() as f64;
+        day * 86_400.0
+            + at.num_seconds_from_midnight() as f64
+            + at.timestamp_subsec_nanos() as f64 / 1e9
+    }
+
+    /// Interpolated expected log-return variance of a single minute at `at`.
+    pub fn minute_variance(&self, at: DateTime<Utc>) -> f64 {
+        // Position measured in buckets relative to bucket midpoints.
+        let position = Self::week_seconds(at) / BUCKET_SECONDS as f64 - 0.5;
+        let lower = position.floor();
+        let frac = position - lower;
+        let lower = lower as i64;
+        self.bucket(lower) * (1.0 - frac) + self.bucket(lower + 1) * frac
+    }
+
+    /// Expected log-return variance accumulated between `from` and `to`.
+    pub fn expected_variance(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+        let mut total = 0.0;
+        let mut cursor = from;
+        while cursor < to {
+            // The rate is linear between consecutive bucket midpoints (:15 and :45).
+            let offset = (cursor.minute() as i64 % 30 - 15).rem_euclid(30);
+            let segment_start = cursor
+                .with_second(0)
+                .and_then(|t| t.with_nanosecond(0))
+                .unwrap_or(cursor)
+                - Duration::minutes(offset);
+            let next = (segment_start + Duration::minutes(30)).min(to);
+            let minutes = (next - cursor).num_milliseconds() as f64 / 60_000.0;
+            total += (self.minute_variance(cursor) + self.minute_variance(next)) / 2.0 * minutes;
+            cursor = next;
+        }
+        total
+    }
+
+    /// Moves the bucket containing `at` towards an observed squared 1m log-return.
+    pub fn observe(&mut self, at: DateTime<Utc>, squared_return: f64, alpha: f64) {
+        let index = Self::bucket_index(at).rem_euclid(BUCKETS_PER_WEEK) as usize;
+        let bucket = &mut self.buckets[index / BUCKETS_PER_DAY][index % BUCKETS_PER_DAY];
+        *bucket += alpha * (squared_return - *bucket);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn profile() -> VarianceProfile {
+        VarianceProfile::load("daily_half_hourly_variance_profiles_1m.json").unwrap()
+    }
+
+    #[test]
+    fn integrates_interpolated_rate() {
+        let profile = profile();
+        // Sunday 23:40 -> Monday 00:50 UTC crosses the week boundary and several midpoints.
+        let from = Utc.with_ymd_and_hms(2025, 10, 12, 23, 40, 7).unwrap();
+        let to = from + Duration::minutes(70);
+        let steps = 70 * 60;
+        let riemann: f64 = (0..steps)
+            .map(|s| {
+                profile.minute_variance(from + Duration::seconds(s) + Duration::milliseconds(500))
+            })
+            .sum::<f64>()
+            / 60.0;
+        assert!((profile.expected_variance(from, to) - riemann).abs() < 1e-9 * riemann);
+        assert_eq!(profile.expected_variance(to, from), 0.0);
+
+        // At a bucket midpoint the interpolated rate is the raw bucket value.
+        let midpoint = Utc.with_ymd_and_hms(2025, 10, 13, 13, 45, 0).unwrap();
+        assert_eq!(
+            profile.minute_variance(midpoint),
+            profile.bucket_variance(midpoint)
+        );
+    }
+
+    #[test]
+    fn json_round_trip_preserves_schema() {
+        let mut profile = profile();
+        let at = Utc.with_ymd_and_hms(2025, 10, 14, 9, 10, 0).unwrap();
+        profile.observe(at, 1e-6, 0.5);
+
+        let reloaded = VarianceProfile::from_json(&profile.to_json().unwrap()).unwrap();
+        assert_eq!(reloaded.metadata, profile.metadata);
+        for day in 0..7 {
+            for bucket in 0..BUCKETS_PER_DAY {
+                let (a, b) = (reloaded.buckets[day][bucket], profile.buckets[day][bucket]);
+                assert!((a - b).abs() <= 1e-15 * b.abs().max(1e-12));
+            }
+        }
+    }
+}
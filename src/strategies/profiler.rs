@@ -0,0 +1,195 @@
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use crate::config::STRATEGY_HANDLER_BUDGET_US;
+use crate::metrics::{Counter, Histogram, METRICS};
+
+lazy_static! {
+    /// Time spent in every strategy callback, keyed by (strategy, hook).
+    pub static ref STRATEGY_PROFILE: StrategyProfiler =
+        StrategyProfiler::new(Duration::from_micros(STRATEGY_HANDLER_BUDGET_US));
+}
+
+/// Timings of one strategy hook. The histogram runs since start; the other counters
+/// cover the current report window.
+#[derive(Debug)]
+pub struct HandlerProfile {
+    strategy: &'static str,
+    hook: &'static str,
+    budget: Duration,
+    histogram: Arc<Histogram>,
+    over_budget_total: Arc<Counter>,
+    calls: AtomicU64,
+    busy_ns: AtomicU64,
+    max_ns: AtomicU64,
+    over_budget: AtomicU64,
+}
+
+impl HandlerProfile {
+    pub fn record(&self, elapsed: Duration) {
+        let ns = elapsed.as_nanos() as u64;
+        self.histogram.observe_duration(elapsed);
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.busy_ns.fetch_add(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+        if elapsed > self.budget {
+            self.over_budget_total.inc();
+            // Warn once per window; the report has the totals.
+            if self.over_budget.fetch_add(1, Ordering::Relaxed) == 0 {
+                warn!(
+                    "[Profiler] {}::{} took {:?}, over its {:?} budget",
+                    self.strategy, self.hook, elapsed, self.budget
+                );
+            }
+        }
+    }
+
+    fn take_window(&self) -> HandlerReport {
+        HandlerReport {
+            strategy: self.strategy,
+            hook: self.hook,
+            calls: self.calls.swap(0, Ordering::Relaxed),
+            busy: Duration::from_nanos(self.busy_ns.swap(0, Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max_ns.swap(0, Ordering::Relaxed)),
+            over_budget: self.over_budget.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Time one hook of one strategy took over a report window.
+#[derive(Debug, Clone)]
+pub struct HandlerReport {
+    pub strategy: &'static str,
+    pub hook: &'static str,
+    pub calls: u64,
+    pub busy: Duration,
+    pub max: Duration,
+    pub over_budget: u64,
+}
+
+impl HandlerReport {
+    pub fn mean(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            self.busy / self.calls as u32
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StrategyProfiler {
+    budget: Duration,
+    handlers: DashMap<(&'static str, &'static str), Arc<HandlerProfile>>,
+}
+
+impl StrategyProfiler {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            handlers: DashMap::new(),
+        }
+    }
+
+    /// Profile of `hook` on `strategy`, registered on first use.
+    pub fn handler(&self, strategy: &'static str, hook: &'static str) -> Arc<HandlerProfile> {
+        if let Some(profile) = self.handlers.get(&(strategy, hook)) {
+            return Arc::clone(profile.value());
+        }
+        let entry = self.handlers.entry((strategy, hook)).or_insert_with(|| {
+            Arc::new(HandlerProfile {
+                strategy,
+                hook,
+                budget: self.budget,
+                histogram: METRICS.strategy_handler_seconds.with(&[strategy, hook]),
+                over_budget_total: METRICS.strategy_over_budget_total.with(&[strategy, hook]),
+                calls: AtomicU64::new(0),
+                busy_ns: AtomicU64::new(0),
+                max_ns: AtomicU64::new(0),
+                over_budget: AtomicU64::new(0),
+            })
+        });
+        Arc::clone(entry.value())
+    }
+
+    /// Reports of every hook called since the last report, busiest first, and resets
+    /// the window.
+    pub fn take_report(&self) -> Vec<HandlerReport> {
+        let mut reports: Vec<HandlerReport> = self
+            .handlers
+            .iter()
+            .map(|profile| profile.take_window())
+            .filter(|report| report.calls > 0)
+            .collect();
+        reports.sort_by_key(|report| Reverse(report.busy));
+        reports
+    }
+}
+
+/// Logs where the event processor spent its time each `every`, busiest hooks first.
+/// Hooks that went over budget are logged as warnings.
+pub async fn log_strategy_profile(every: Duration) {
+    let mut interval = time::interval(every);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let reports = STRATEGY_PROFILE.take_report();
+        let total: Duration = reports.iter().map(|report| report.busy).sum();
+        if total.is_zero() {
+            continue;
+        }
+        info!(
+            "[Profiler] {:?} in strategies over the last {:?} ({:.1}% busy)",
+            total,
+            every,
+            100.0 * total.as_secs_f64() / every.as_secs_f64()
+        );
+        for report in reports {
+            let line = format!(
+                "[Profiler] {}::{} share={:.1}% calls={} mean={:?} max={:?} over_budget={}",
+                report.strategy,
+                report.hook,
+                100.0 * report.busy.as_secs_f64() / total.as_secs_f64(),
+                report.calls,
+                report.mean(),
+                report.max,
+                report.over_budget
+            );
+            if report.over_budget > 0 {
+                warn!("{}", line);
+            } else {
+                info!("{}", line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_busiest_hooks_first_and_resets() {
+        let profiler = StrategyProfiler::new(Duration::from_millis(1));
+        let fast = profiler.handler("Fast", "poly_handle_market_price_change");
+        let slow = profiler.handler("Slow", "poly_handle_market_price_change");
+        fast.record(Duration::from_micros(10));
+        fast.record(Duration::from_micros(30));
+        slow.record(Duration::from_millis(3));
+
+        let report = profiler.take_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].strategy, "Slow");
+        assert_eq!(report[0].over_budget, 1);
+        assert_eq!(report[1].mean(), Duration::from_micros(20));
+        assert_eq!(report[1].over_budget, 0);
+
+        assert!(profiler.take_report().is_empty());
+    }
+}
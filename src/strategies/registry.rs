@@ -0,0 +1,508 @@
+use anyhow::{bail, Context, Result};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::config::LAUNCH_CONFIG_POLL_MS;
+use crate::strategies::{
+    app_state_updates::{
+        update_crypto_orderbooks::UpdateCryptoOrderbookStrategy,
+        update_fair_value::{FairValueConfig, UpdateFairValueStrategy},
+    },
+    custom::{
+        koen::{koen_strategy::KoenStrategy, models::KoenParams},
+        negrisk::{
+            maker_taker_config::NegRiskParams, negrisk_no_maker_strategy::NegRiskNoMakerStrategy,
+        },
+        tob::{models::TobParams, tob_strategy::TobStrategy},
+    },
+    logging::{
+        bbo_logging::BBOLoggingStrategy, crypto_logging::CryptoLoggingStrategy,
+        fill_metrics::FillMetricsStrategy, main_logging::MainLoggingStrategy,
+        order_logging::OrderLoggingStrategy, position_logging::PositionLoggingStrategy,
+        trade_logging::TradeLoggingStrategy,
+    },
+    params::parse_params,
+    pricing::{
+        up_down_pricing::UpDownPricingConfig, update_variance_profile::VarianceUpdaterConfig,
+        variance_profile::SharedVarianceProfile, UpDownPricingStrategy,
+        UpdateVarianceProfileStrategy,
+    },
+    PaperFillStrategy, Strategy, UpdateOrderStrategy, UpdateOrderbookStrategy,
+    UpdatePositionStrategy,
+};
+
+/// What strategies may need from `main` to be built.
+pub struct StrategyDeps {
+    pub book_resync_tx: UnboundedSender<String>,
+    pub variance_profile: SharedVarianceProfile,
+    /// Where the online-updated variance profile is written; `None` keeps it in memory.
+    pub variance_profile_path: Option<PathBuf>,
+}
+
+/// A strategy that can be selected by name at launch.
+pub struct StrategyEntry {
+    /// Same as `Strategy::name` of the built strategy.
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Always runs, ahead of the selected strategies, as it keeps the shared state current.
+    pub core: bool,
+    /// Parameters with their defaults; `{}` if the strategy takes none.
+    pub schema: fn() -> Value,
+    pub build: fn(&StrategyDeps, Value) -> Result<Arc<dyn Strategy>>,
+}
+
+pub const STRATEGIES: &[StrategyEntry] = &[
+    StrategyEntry {
+        name: "UpdateOrderbooks",
+        description: "Maintains Polymarket orderbooks and resyncs the stale ones",
+        core: true,
+        schema: no_schema,
+        build: |deps, params| {
+            no_params(params)?;
+            shared(UpdateOrderbookStrategy::new(deps.book_resync_tx.clone()))
+        },
+    },
+    StrategyEntry {
+        name: "UpdateOrders",
+        description: "Tracks our open orders from the user feed",
+        core: true,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(UpdateOrderStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "UpdatePositions",
+        description: "Tracks our positions from the user feed",
+        core: true,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(UpdatePositionStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "PaperFills",
+        description: "Fills paper orders against the books when paper trading",
+        core: true,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(PaperFillStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "UpdateCryptoOrderbooks",
+        description: "Maintains exchange orderbooks of the tracked cryptos",
+        core: true,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(UpdateCryptoOrderbookStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "UpdateFairValue",
+        description: "Blends exchange prices into a fair value per crypto",
+        core: true,
+        schema: || schema_of(FairValueConfig::default()),
+        build: |_, params| shared(UpdateFairValueStrategy::with_config(parse_params(params)?)),
+    },
+    StrategyEntry {
+        name: "KoenStrategy",
+        description: "Buys the ask when book imbalance predicts a move up",
+        core: false,
+        schema: || schema_of(KoenParams::default()),
+        build: |_, params| shared(KoenStrategy::new(parse_params(params)?)),
+    },
+    StrategyEntry {
+        name: "TobStrategy",
+        description: "Joins the top of book on quiet, tight markets",
+        core: false,
+        schema: || schema_of(TobParams::default()),
+        build: |_, params| shared(TobStrategy::new(parse_params(params)?)),
+    },
+    StrategyEntry {
+        name: "NegRiskNoMakerStrategy",
+        description: "Bids on neg-risk event outcomes from book signals",
+        core: false,
+        schema: || schema_of(NegRiskParams::default()),
+        build: |_, params| shared(NegRiskNoMakerStrategy::new(parse_params(params)?)),
+    },
+    StrategyEntry {
+        name: "UpdateVarianceProfile",
+        description: "Refreshes the intraday variance profile from live 1m returns",
+        core: false,
+        schema: no_schema,
+        build: |deps, params| {
+            no_params(params)?;
+            shared(UpdateVarianceProfileStrategy::new(
+                Arc::clone(&deps.variance_profile),
+                VarianceUpdaterConfig {
+                    path: deps.variance_profile_path.clone(),
+                    ..VarianceUpdaterConfig::default()
+                },
+            ))
+        },
+    },
+    StrategyEntry {
+        name: "UpDownPricing",
+        description: "Prices hourly up-or-down markets from the fair value",
+        core: false,
+        schema: || schema_of(UpDownPricingConfig::default()),
+        build: |deps, params| {
+            shared(UpDownPricingStrategy::with_config(
+                Arc::clone(&deps.variance_profile),
+                parse_params(params)?,
+            ))
+        },
+    },
+    StrategyEntry {
+        name: "PositionLogger",
+        description: "Logs positions at start and after every fill",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(PositionLoggingStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "OrderLogger",
+        description: "Logs order updates from the user feed",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(OrderLoggingStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "MainLogger",
+        description: "Placeholder for logging exchange price updates",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(MainLoggingStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "BBOLoggingStrategy",
+        description: "Records YES books to output/<asset_id>.ndjson",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(BBOLoggingStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "TradeLoggingStrategy",
+        description: "Records our fills to output/trades.ndjson",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(TradeLoggingStrategy::new())
+        },
+    },
+    StrategyEntry {
+        name: "CryptoLogging",
+        description: "Logs exchange prices",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(CryptoLoggingStrategy)
+        },
+    },
+    StrategyEntry {
+        name: "FillMetrics",
+        description: "Feeds fills and traded PnL to the metrics",
+        core: false,
+        schema: no_schema,
+        build: |_, params| {
+            no_params(params)?;
+            shared(FillMetricsStrategy::new())
+        },
+    },
+];
+
+fn shared(strategy: impl Strategy + 'static) -> Result<Arc<dyn Strategy>> {
+    Ok(Arc::new(strategy))
+}
+
+fn no_schema() -> Value {
+    json!({})
+}
+
+fn schema_of(defaults: impl Serialize) -> Value {
+    serde_json::to_value(defaults).unwrap_or_else(|_| json!({}))
+}
+
+fn no_params(params: Value) -> Result<()> {
+    match params {
+        Value::Null => Ok(()),
+        Value::Object(map) if map.is_empty() => Ok(()),
+        other => bail!("takes no parameters, got {}", other),
+    }
+}
+
+pub fn find(name: &str) -> Option<&'static StrategyEntry> {
+    STRATEGIES.iter().find(|entry| entry.name == name)
+}
+
+/// One strategy to run and its parameters.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategySelection {
+    pub name: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// Strategies to run, as read from the `--config` file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchConfig {
+    pub strategies: Vec<StrategySelection>,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        let strategies = [
+            "KoenStrategy",
+            "PositionLogger",
+            "UpdateVarianceProfile",
+            "UpDownPricing",
+            "BBOLoggingStrategy",
+            "TradeLoggingStrategy",
+            "FillMetrics",
+        ];
+        Self {
+            strategies: strategies
+                .into_iter()
+                .map(|name| StrategySelection {
+                    name: name.to_string(),
+                    params: Value::Null,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl LaunchConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Strategies to run: `names` in that order, with the params this config gives them,
+    /// or this config's own list if `names` is `None`.
+    pub fn select(self, names: Option<&[String]>) -> Vec<StrategySelection> {
+        let Some(names) = names else {
+            return self.strategies;
+        };
+        names
+            .iter()
+            .map(|name| {
+                self.strategies
+                    .iter()
+                    .find(|selection| &selection.name == name)
+                    .cloned()
+                    .unwrap_or_else(|| StrategySelection {
+                        name: name.clone(),
+                        params: Value::Null,
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Builds the core strategies followed by `selection`, in order. Fails on unknown or
+/// repeated names and invalid parameters.
+pub fn build_strategies(
+    selection: &[StrategySelection],
+    deps: &StrategyDeps,
+) -> Result<Vec<Arc<dyn Strategy>>> {
+    for (i, chosen) in selection.iter().enumerate() {
+        if find(&chosen.name).is_none() {
+            let known: Vec<&str> = STRATEGIES.iter().map(|entry| entry.name).collect();
+            bail!(
+                "unknown strategy '{}'; known: {}",
+                chosen.name,
+                known.join(", ")
+            );
+        }
+        if selection[..i]
+            .iter()
+            .any(|earlier| earlier.name == chosen.name)
+        {
+            bail!("strategy '{}' is selected twice", chosen.name);
+        }
+    }
+    let params_of = |name: &str| {
+        selection
+            .iter()
+            .find(|chosen| chosen.name == name)
+            .map_or(Value::Null, |chosen| chosen.params.clone())
+    };
+    STRATEGIES
+        .iter()
+        .filter(|entry| entry.core)
+        .chain(
+            selection
+                .iter()
+                .filter_map(|chosen| find(&chosen.name))
+                .filter(|entry| !entry.core),
+        )
+        .map(|entry| {
+            (entry.build)(deps, params_of(entry.name))
+                .with_context(|| format!("building {}", entry.name))
+        })
+        .collect()
+}
+
+/// Polls the launch config at `path` and hands strategies of `running` whose params
+/// changed their new params. Strategies added to or removed from the config only take
+/// effect on restart.
+pub async fn watch_launch_config(
+    path: PathBuf,
+    running: Vec<Arc<dyn Strategy>>,
+    selection: Vec<StrategySelection>,
+) {
+    let modified_at = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let mut last_modified: Option<SystemTime> = modified_at(&path);
+    let mut applied: HashMap<String, Value> = selection
+        .into_iter()
+        .map(|chosen| (chosen.name, chosen.params))
+        .collect();
+    let mut interval = tokio::time::interval(Duration::from_millis(LAUNCH_CONFIG_POLL_MS));
+    loop {
+        interval.tick().await;
+        let modified = modified_at(&path);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+        let config = match LaunchConfig::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("[Config] Keeping current params: {:#}", e);
+                continue;
+            }
+        };
+        for chosen in &config.strategies {
+            if !running
+                .iter()
+                .any(|strategy| strategy.name() == chosen.name)
+            {
+                warn!(
+                    "[Config] {} is not running; restart to start it",
+                    chosen.name
+                );
+            }
+        }
+        for strategy in &running {
+            let name = strategy.name();
+            let params = config
+                .strategies
+                .iter()
+                .find(|chosen| chosen.name == name)
+                .map_or(Value::Null, |chosen| chosen.params.clone());
+            if applied.get(name).unwrap_or(&Value::Null) == &params {
+                continue;
+            }
+            match strategy.reload_params(params.clone()) {
+                Ok(()) => {
+                    info!("[Config] Reloaded params of {}: {}", name, params);
+                    applied.insert(name.to_string(), params);
+                }
+                Err(e) => error!("[Config] Keeping current params of {}: {:#}", name, e),
+            }
+        }
+    }
+}
+
+/// Registered strategies with their descriptions and default parameters, for `--list-strategies`.
+pub fn describe() -> String {
+    STRATEGIES
+        .iter()
+        .map(|entry| {
+            format!(
+                "{:<24} {}{}\n{:<24} params: {}",
+                entry.name,
+                entry.description,
+                if entry.core { " (always on)" } else { "" },
+                "",
+                (entry.schema)()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::pricing::variance_profile::VarianceProfile;
+
+    #[test]
+    fn selects_by_name_with_params_from_config() {
+        let config: LaunchConfig = serde_json::from_str(
+            r#"{"strategies": [
+                {"name": "KoenStrategy"},
+                {"name": "UpDownPricing", "params": {"realized_weight": 0.25}}
+            ]}"#,
+        )
+        .unwrap();
+        let names = vec!["UpDownPricing".to_string(), "FillMetrics".to_string()];
+        let selection = config.select(Some(&names));
+        assert_eq!(selection[0].params, json!({"realized_weight": 0.25}));
+        assert_eq!(selection[1].params, Value::Null);
+
+        let config: UpDownPricingConfig = parse_params(selection[0].params.clone()).unwrap();
+        assert_eq!(config.realized_weight, 0.25);
+        assert_eq!(config.min_realized_samples, 15);
+        assert!(no_params(json!({"max_spread": 0.01})).is_err());
+        assert!(LaunchConfig::default()
+            .strategies
+            .iter()
+            .all(|chosen| find(&chosen.name).is_some()));
+    }
+
+    #[test]
+    fn strategies_with_params_reload_them() {
+        let (book_resync_tx, _book_resync_rx) = tokio::sync::mpsc::unbounded_channel();
+        let deps = StrategyDeps {
+            book_resync_tx,
+            variance_profile: VarianceProfile::load("daily_half_hourly_variance_profiles_1m.json")
+                .unwrap()
+                .into_shared(),
+            variance_profile_path: None,
+        };
+        for entry in STRATEGIES
+            .iter()
+            .filter(|entry| (entry.schema)() != json!({}))
+        {
+            let strategy = (entry.build)(&deps, Value::Null).unwrap();
+            let defaults = (entry.schema)();
+            assert_eq!(strategy.params(), defaults, "{}", entry.name);
+            assert!(
+                strategy.reload_params(defaults).is_ok(),
+                "{} cannot reload its params",
+                entry.name
+            );
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use serde_json::Value;
 use std::sync::Arc;
 
+use super::timers::{AsyncStrategy, TimerHandle};
+use crate::exchange_listeners::autodiscover_markets::MarketConfig;
 use crate::exchange_listeners::crypto_models::{CryptoPriceUpdate, RateKind};
 use crate::exchange_listeners::orderbooks::{CryptoOrderbook, OrderbookDepth, OrderbookLevel};
 use crate::exchange_listeners::poly_models::{LegacyPriceChange, Listener, PriceChange};
@@ -14,13 +17,19 @@ use crate::exchange_listeners::{
 pub struct StrategyContext {
     pub app_state: Arc<AppState>,
     pub poly_state: Arc<PolyMarketState>,
+    pub timers: TimerHandle,
 }
 
 impl StrategyContext {
-    pub fn new(app_state: Arc<AppState>, poly_state: Arc<PolyMarketState>) -> Self {
+    pub fn new(
+        app_state: Arc<AppState>,
+        poly_state: Arc<PolyMarketState>,
+        timers: TimerHandle,
+    ) -> Self {
         Self {
             app_state,
             poly_state,
+            timers,
         }
     }
 }
@@ -28,6 +37,37 @@ impl StrategyContext {
 pub trait Strategy: Send + Sync {
     fn name(&self) -> &'static str;
 
+    // Strategies that also implement `AsyncStrategy` return themselves to get timers
+    fn as_async(self: Arc<Self>) -> Option<Arc<dyn AsyncStrategy>> {
+        None
+    }
+
+    // Current params, as accepted by `reload_params`; null if the strategy takes none
+    fn params(&self) -> Value {
+        Value::Null
+    }
+
+    // Gets called when the launch config gives this strategy new params; on error the
+    // current params stay in place
+    fn reload_params(&self, _params: Value) -> anyhow::Result<()> {
+        anyhow::bail!(
+            "{} cannot change params at runtime, restart to apply them",
+            self.name()
+        )
+    }
+
+    // Gets called once before the first event, with the initial state loaded
+    fn on_start(&self, _ctx: Arc<StrategyContext>) {}
+
+    // Gets called before shutdown; events are still processed, so cancels can complete
+    fn on_stop(&self, _ctx: Arc<StrategyContext>) {}
+
+    // Gets called when assets are subscribed to the market feed
+    fn on_market_added(&self, _ctx: Arc<StrategyContext>, _asset_ids: &[String]) {}
+
+    // Gets called when assets are unsubscribed, typically because their market ended
+    fn on_market_closed(&self, _ctx: Arc<StrategyContext>, _asset_ids: &[String]) {}
+
     // Gets called by market socket on a market trade
     fn poly_handle_market_agg_orderbook(
         &self,
@@ -121,4 +161,26 @@ pub trait Strategy: Send + Sync {
         _depth: OrderbookDepth,
     ) {
     }
+
+    // Gets called after a stablecoin rate is stored and prices are re-based to USD
+    fn conversion_handle_rate_update(
+        &self,
+        _ctx: Arc<StrategyContext>,
+        _kind: RateKind,
+        _value: f64,
+    ) {
+    }
+
+    // Gets called when a rate feed goes down
+    fn conversion_handle_rate_clear(&self, _ctx: Arc<StrategyContext>, _kind: RateKind) {}
+
+    // Gets called when the hourly up-or-down market of `crypto` is replaced by the next one
+    fn market_handle_rollover(
+        &self,
+        _ctx: Arc<StrategyContext>,
+        _crypto: Crypto,
+        _previous: Option<&MarketConfig>,
+        _next: &MarketConfig,
+    ) {
+    }
 }
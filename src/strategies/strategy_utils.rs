@@ -119,8 +119,10 @@ impl StrategyAsset {
         ctx.poly_state
             .markets
             .get(asset_id)
-            .and_then(|m| m.clobTokenIds.as_ref())
-            .and_then(|clob_token_ids| serde_json::from_str::<Vec<String>>(clob_token_ids).ok())
+            .and_then(|m| {
+                let clob_token_ids = m.clobTokenIds.as_ref()?;
+                serde_json::from_str::<Vec<String>>(clob_token_ids).ok()
+            })
             .unwrap_or_default()
     }
 
@@ -142,7 +144,8 @@ impl StrategyAsset {
             None => return result,
         };
 
-        for (other_asset_id, other_market) in ctx.poly_state.markets.iter() {
+        for entry in ctx.poly_state.markets.iter() {
+            let (other_asset_id, other_market) = entry.pair();
             if other_market
                 .negRiskMarketID
                 .as_ref()
@@ -212,8 +215,10 @@ impl StrategyClient {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn place_limit_order(
         ctx: Arc<StrategyContext>,
+        strategy: &'static str,
         asset_id: &str,
         side: OrderSide,
         price: u32,
@@ -223,6 +228,7 @@ impl StrategyClient {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         PolyClient::place_limit_order(
             Arc::clone(&ctx.poly_state),
+            Some(strategy),
             asset_id,
             side,
             price,
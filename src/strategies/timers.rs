@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{self, MissedTickBehavior};
+
+use super::profiler::STRATEGY_PROFILE;
+use super::{Strategy, StrategyContext};
+
+/// Timer that calls `AsyncStrategy::on_timer` with `TimerEvent::Periodic(name)` every
+/// `every`, whether or not events arrive.
+#[derive(Debug, Clone, Copy)]
+pub struct TimerSpec {
+    pub name: &'static str,
+    pub every: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimerEvent {
+    /// A timer returned by `AsyncStrategy::timers` ticked.
+    Periodic(&'static str),
+    /// A callback scheduled with `TimerHandle::schedule` came due.
+    Scheduled(String),
+}
+
+/// Strategy that also acts on time: periodic timers (requotes, sweeps) and one-shot
+/// callbacks (cancel an order that is still open after a while). Timer callbacks run
+/// on their own tasks, so they may await without holding up the event processor.
+#[async_trait]
+pub trait AsyncStrategy: Strategy {
+    /// Periodic timers, read once when the event processor starts.
+    fn timers(&self) -> Vec<TimerSpec> {
+        Vec::new()
+    }
+
+    async fn on_timer(&self, ctx: Arc<StrategyContext>, timer: TimerEvent);
+}
+
+#[derive(Debug)]
+pub struct ScheduledTimer {
+    strategy: &'static str,
+    key: String,
+    after: Duration,
+}
+
+/// Schedules one-shot `on_timer` callbacks, available to strategies as `ctx.timers`.
+#[derive(Debug, Clone)]
+pub struct TimerHandle(mpsc::UnboundedSender<ScheduledTimer>);
+
+impl TimerHandle {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<ScheduledTimer>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self(tx), rx)
+    }
+
+    /// Calls `on_timer` of `strategy` with `TimerEvent::Scheduled(key)` after `after`.
+    /// Replaces a callback of the same strategy and key that is not yet due.
+    pub fn schedule(&self, strategy: &'static str, key: impl Into<String>, after: Duration) {
+        let _ = self.0.send(ScheduledTimer {
+            strategy,
+            key: key.into(),
+            after,
+        });
+    }
+}
+
+/// Runs the periodic timers of `strategies` and the callbacks they schedule through
+/// `ctx.timers`. Each periodic timer waits for its previous callback, skipping missed
+/// ticks rather than piling them up.
+pub async fn run_strategy_timers(
+    strategies: Vec<Arc<dyn AsyncStrategy>>,
+    ctx: Arc<StrategyContext>,
+    mut schedule_rx: mpsc::UnboundedReceiver<ScheduledTimer>,
+) {
+    let mut by_name: HashMap<&'static str, Arc<dyn AsyncStrategy>> = HashMap::new();
+    for strategy in strategies {
+        for spec in strategy.timers() {
+            tokio::spawn(run_periodic(Arc::clone(&strategy), Arc::clone(&ctx), spec));
+        }
+        by_name.insert(strategy.name(), strategy);
+    }
+
+    let mut waiting: HashMap<(&'static str, String), JoinHandle<()>> = HashMap::new();
+    while let Some(timer) = schedule_rx.recv().await {
+        waiting.retain(|_, task| !task.is_finished());
+        let Some(strategy) = by_name.get(timer.strategy) else {
+            warn!(
+                "[Timers] {} scheduled '{}' but is not an async strategy",
+                timer.strategy, timer.key
+            );
+            continue;
+        };
+        let strategy = Arc::clone(strategy);
+        let ctx = Arc::clone(&ctx);
+        let event = TimerEvent::Scheduled(timer.key.clone());
+        let task = tokio::spawn(async move {
+            time::sleep(timer.after).await;
+            // Detached, so replacing the key once due no longer aborts the callback.
+            tokio::spawn(async move { fire(strategy.as_ref(), ctx, event).await });
+        });
+        if let Some(previous) = waiting.insert((timer.strategy, timer.key), task) {
+            previous.abort();
+        }
+    }
+}
+
+async fn run_periodic(
+    strategy: Arc<dyn AsyncStrategy>,
+    ctx: Arc<StrategyContext>,
+    spec: TimerSpec,
+) {
+    let mut interval = time::interval(spec.every);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        fire(
+            strategy.as_ref(),
+            Arc::clone(&ctx),
+            TimerEvent::Periodic(spec.name),
+        )
+        .await;
+    }
+}
+
+async fn fire(strategy: &dyn AsyncStrategy, ctx: Arc<StrategyContext>, event: TimerEvent) {
+    let started = Instant::now();
+    strategy.on_timer(ctx, event).await;
+    STRATEGY_PROFILE
+        .handler(strategy.name(), "on_timer")
+        .record(started.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange_listeners::{AppState, PolyMarketState};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<TimerEvent>>);
+
+    impl Strategy for Recorder {
+        fn name(&self) -> &'static str {
+            "Recorder"
+        }
+    }
+
+    #[async_trait]
+    impl AsyncStrategy for Recorder {
+        fn timers(&self) -> Vec<TimerSpec> {
+            vec![TimerSpec {
+                name: "tick",
+                every: Duration::from_millis(20),
+            }]
+        }
+
+        async fn on_timer(&self, _ctx: Arc<StrategyContext>, timer: TimerEvent) {
+            self.0.lock().unwrap().push(timer);
+        }
+    }
+
+    #[tokio::test]
+    async fn fires_periodic_timers_and_latest_schedule_per_key() {
+        let (timers, schedule_rx) = TimerHandle::new();
+        let ctx = Arc::new(StrategyContext::new(
+            Arc::new(AppState::default()),
+            Arc::new(PolyMarketState::default()),
+            timers.clone(),
+        ));
+        let recorder = Arc::new(Recorder::default());
+        tokio::spawn(run_strategy_timers(
+            vec![recorder.clone() as Arc<dyn AsyncStrategy>],
+            ctx,
+            schedule_rx,
+        ));
+
+        timers.schedule("Recorder", "cancel", Duration::from_millis(10));
+        // Pushes the first callback back instead of adding a second one.
+        timers.schedule("Recorder", "cancel", Duration::from_millis(30));
+        timers.schedule("Recorder", "requote", Duration::from_millis(10));
+        time::sleep(Duration::from_millis(120)).await;
+
+        let fired = recorder.0.lock().unwrap().clone();
+        let count = |event: TimerEvent| fired.iter().filter(|e| **e == event).count();
+        assert_eq!(count(TimerEvent::Scheduled("cancel".into())), 1);
+        assert_eq!(count(TimerEvent::Scheduled("requote".into())), 1);
+        assert!(count(TimerEvent::Periodic("tick")) >= 2);
+    }
+}